@@ -109,7 +109,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let leaf_hash = public_inputs[0];
     assert_eq!(
         leaf_hash,
-        leaf_hash_from_inputs::<N_CURRENCIES>(user_name.clone(), balances.clone())
+        leaf_hash_from_inputs::<N_CURRENCIES>(user_name.clone(), balances.clone())?
     );
 
     // Get `mst_root` from contract. the `mst_root` is disptached by CEX with specific time `snapshot_time`.
@@ -5,24 +5,34 @@ pub mod round;
 use ethers::types::U256;
 use num_bigint::BigUint;
 use num_traits::Num;
-use summa_solvency::merkle_sum_tree::Entry;
+use std::error::Error;
+use summa_solvency::merkle_sum_tree::{utils::check_username, Entry};
 
+/// Computes the leaf hash of a user out of their username and balances, applying the same username mapping as the Merkle Sum Tree.
+/// Usernames longer than 31 bytes are mapped to the field through their Keccak-256 digest.
 pub fn leaf_hash_from_inputs<const N_CURRENCIES: usize>(
     username: String,
     balances: Vec<String>,
-) -> U256
+) -> Result<U256, Box<dyn Error>>
 where
     [usize; N_CURRENCIES + 1]: Sized,
 {
+    check_username(&username)?;
+
     // Convert balances to BigUint
     let balances: Vec<BigUint> = balances
         .iter()
-        .map(|balance| BigUint::from_str_radix(balance, 10).unwrap())
-        .collect();
+        .map(|balance| {
+            BigUint::from_str_radix(balance, 10).map_err(|_| format!("Invalid balance {}", balance))
+        })
+        .collect::<Result<_, _>>()?;
 
-    let entry: Entry<N_CURRENCIES> = Entry::new(username, balances.try_into().unwrap()).unwrap();
+    let balances: [BigUint; N_CURRENCIES] = balances
+        .try_into()
+        .map_err(|_| format!("Expected {} balances", N_CURRENCIES))?;
+    let entry: Entry<N_CURRENCIES> = Entry::new(username, balances)?;
 
     // Convert Fp to U256
     let hash_str = format!("{:?}", entry.compute_leaf().hash);
-    U256::from_str_radix(&hash_str, 16).unwrap()
+    Ok(U256::from_str_radix(&hash_str, 16)?)
 }
@@ -4,6 +4,7 @@ use halo2_proofs::{
     plonk::{ProvingKey, VerifyingKey},
     poly::kzg::commitment::ParamsKZG,
 };
+use num_bigint::BigUint;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 
@@ -13,7 +14,7 @@ use summa_solvency::{
         merkle_sum_tree::MstInclusionCircuit,
         utils::{gen_proof_solidity_calldata, generate_setup_artifacts},
     },
-    merkle_sum_tree::Tree,
+    merkle_sum_tree::{diff_trees, BalanceKind, Tree, TreeDiff},
 };
 
 pub(crate) type SetupArtifacts = (
@@ -75,6 +76,18 @@ where
         self.timestamp
     }
 
+    /// Computes the difference between the tree of the previous round and the tree of this round, and reconciles it against `published_root_balances`, the root balances committed for the previous round.
+    /// Meant to be called before [`Round::dispatch_commitment`], so that a root built out of inconsistent data is not published.
+    pub fn diff_with_previous_round(
+        &self,
+        previous_mst: &dyn Tree<N_CURRENCIES, N_BYTES>,
+        published_root_balances: &[BigUint; N_CURRENCIES],
+    ) -> Result<TreeDiff<N_CURRENCIES>, Box<dyn Error>> {
+        let diff = diff_trees(previous_mst, self.snapshot.mst.as_ref())?;
+        diff.reconcile(published_root_balances)?;
+        Ok(diff)
+    }
+
     pub async fn dispatch_commitment(&mut self) -> Result<(), Box<dyn Error>> {
         let root_str = format!("{:?}", self.snapshot.mst.root().hash);
         let mst_root = U256::from_str_radix(&root_str, 16).unwrap();
@@ -90,15 +103,7 @@ where
             .submit_commitment(
                 mst_root,
                 root_sums,
-                self.snapshot
-                    .mst
-                    .cryptocurrencies()
-                    .iter()
-                    .map(|cryptocurrency| Cryptocurrency {
-                        name: cryptocurrency.name.clone(),
-                        chain: cryptocurrency.chain.clone(),
-                    })
-                    .collect::<Vec<Cryptocurrency>>()
+                committed_cryptocurrencies(self.snapshot.mst.cryptocurrencies())
                     .as_slice()
                     .try_into()
                     .unwrap(),
@@ -123,6 +128,23 @@ where
     }
 }
 
+/// Returns the cryptocurrencies published with the root sums of a commitment, in the order of the balance columns of the tree.
+/// Debt root sums are published next to the liability root sums of the same currency, so they are labelled apart as `debt_<name>`.
+pub(crate) fn committed_cryptocurrencies(
+    cryptocurrencies: &[summa_solvency::merkle_sum_tree::Cryptocurrency],
+) -> Vec<Cryptocurrency> {
+    cryptocurrencies
+        .iter()
+        .map(|cryptocurrency| Cryptocurrency {
+            name: match cryptocurrency.kind {
+                BalanceKind::Liability => cryptocurrency.name.clone(),
+                BalanceKind::Debt => format!("debt_{}", cryptocurrency.name),
+            },
+            chain: cryptocurrency.chain.clone(),
+        })
+        .collect()
+}
+
 impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
     Snapshot<LEVELS, N_CURRENCIES, N_BYTES>
 where
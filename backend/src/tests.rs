@@ -104,6 +104,7 @@ mod test {
         types::{U256, U64},
         utils::to_checksum,
     };
+    use halo2_proofs::halo2curves::bn256::Fr as Fp;
     use std::{convert::TryFrom, error::Error};
     use summa_solvency::merkle_sum_tree::MerkleSumTree;
     use tokio::{
@@ -111,7 +112,10 @@ mod test {
         time::{sleep, Duration},
     };
 
-    use crate::apis::{address_ownership::AddressOwnership, round::Round};
+    use crate::apis::{
+        address_ownership::AddressOwnership,
+        round::{committed_cryptocurrencies, Round},
+    };
     use crate::contracts::{
         generated::summa_contract::{
             AddressOwnershipProof, AddressOwnershipProofSubmittedFilter, Cryptocurrency,
@@ -296,4 +300,27 @@ mod test {
         drop(anvil);
         Ok(())
     }
+
+    #[test]
+    fn test_committed_cryptocurrencies_with_debt_column() {
+        let csv_file = tempfile::Builder::new().suffix(".csv").tempfile().unwrap();
+        std::fs::write(
+            csv_file.path(),
+            "username,balance_ETH_ETH,debt_ETH_ETH\nalice,100,0\nbob,0,30\ncarol,50,20\n",
+        )
+        .unwrap();
+        let mst = MerkleSumTree::<2, 14>::new(csv_file.path().to_str().unwrap()).unwrap();
+
+        // The debt root sum is published under its own name, next to the liability root sum of the same currency
+        let cryptocurrencies = committed_cryptocurrencies(mst.cryptocurrencies());
+        assert_eq!(cryptocurrencies.len(), 2);
+        assert_eq!(cryptocurrencies[0].name, "ETH");
+        assert_eq!(cryptocurrencies[0].chain, "ETH");
+        assert_eq!(cryptocurrencies[1].name, "debt_ETH");
+        assert_eq!(cryptocurrencies[1].chain, "ETH");
+
+        // The root sums are published in the same order, without netting the debts
+        assert_eq!(mst.root().balances[0], Fp::from(150));
+        assert_eq!(mst.root().balances[1], Fp::from(50));
+    }
 }
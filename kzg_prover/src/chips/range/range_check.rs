@@ -1,18 +1,19 @@
 use halo2_proofs::arithmetic::Field;
-use halo2_proofs::circuit::{AssignedCell, Region, Value};
+use halo2_proofs::circuit::{AssignedCell, Layouter, Region, Value};
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
 use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed};
 use halo2_proofs::poly::Rotation;
 
 use std::fmt::Debug;
 
-use crate::chips::range::utils::decompose_fp_to_bytes;
+use crate::chips::range::utils::{decompose_fp_to_limbs, pow_of_two};
 
 /// Configuration for the Range Check Chip
 ///
 /// # Type Parameters
 ///
-/// * `N_BYTES`: Number of bytes in which the element to be checked should lie
+/// * `N_LIMBS`: Number of limbs in which the element to be checked is decomposed
+/// * `LIMB_BITS`: Number of bits of each limb. The lookup table contains the values from `0` to `2^LIMB_BITS - 1`.
 ///
 /// # Fields
 ///
@@ -20,12 +21,12 @@ use crate::chips::range::utils::decompose_fp_to_bytes;
 ///
 /// Patterned after [halo2_gadgets](https://github.com/privacy-scaling-explorations/halo2/blob/main/halo2_gadgets/src/utilities/decompose_running_sum.rs)
 #[derive(Debug, Copy, Clone)]
-pub struct RangeCheckConfig<const N_BYTES: usize> {
-    zs: [Column<Advice>; N_BYTES],
+pub struct RangeCheckConfig<const N_LIMBS: usize, const LIMB_BITS: usize = 8> {
+    zs: [Column<Advice>; N_LIMBS],
 }
 
-/// Helper chip that verfiies that the element witnessed in a given cell lies within a given range defined by N_BYTES.
-/// For example, Let's say we want to constraint 0x1f2f3f4f to be within the range N_BYTES=4.
+/// Helper chip that verfiies that the element witnessed in a given cell lies within a given range of `n_bits` bits, decomposed in N_LIMBS limbs of LIMB_BITS bits.
+/// For example, Let's say we want to constraint 0x1f2f3f4f to be within the range of 32 bits, with N_LIMBS=4 and LIMB_BITS=8.
 /// `z` is the advice column that contains the element to be checked.
 ///
 /// `z = 0x1f2f3f4f`
@@ -38,103 +39,159 @@ pub struct RangeCheckConfig<const N_BYTES: usize> {
 ///  ---------  | ---------- | ---------- | ---------- | ---------- |
 ///  0x1f2f3f4f | 0x1f2f3f   | 0x1f2f     | 0x1f       | 0x00       |
 ///
-/// Column zs[0], at offset 0, contains the truncated right-shifted value z - ks[0] / 2^8 (shift right by 8 bits) where ks[0] is the 0-th decomposition big-endian of the element to be checked
-/// Column zs[1], at offset 0, contains the truncated right-shifted value zs[0] - ks[1] / 2^8 (shift right by 8 bits) where ks[1] is the 1-th decomposition big-endian of the element to be checked
-/// Column zs[2], at offset 0, contains the truncated right-shifted value zs[1] - ks[2] / 2^8 (shift right by 8 bits) where ks[2] is the 2-th decomposition big-endian of the element to be checked
-/// Column zs[3], at offset 0, contains the truncated right-shifted value zs[2] - ks[3] / 2^8 (shift right by 8 bits) where ks[3] is the 3-th decomposition big-endian of the element to be checked
+/// Column zs[0], at offset 0, contains the truncated right-shifted value z - ks[0] / 2^LIMB_BITS (shift right by LIMB_BITS bits) where ks[0] is the 0-th decomposition big-endian of the element to be checked
+/// Column zs[1], at offset 0, contains the truncated right-shifted value zs[0] - ks[1] / 2^LIMB_BITS (shift right by LIMB_BITS bits) where ks[1] is the 1-th decomposition big-endian of the element to be checked
+/// Column zs[2], at offset 0, contains the truncated right-shifted value zs[1] - ks[2] / 2^LIMB_BITS (shift right by LIMB_BITS bits) where ks[2] is the 2-th decomposition big-endian of the element to be checked
+/// Column zs[3], at offset 0, contains the truncated right-shifted value zs[2] - ks[3] / 2^LIMB_BITS (shift right by LIMB_BITS bits) where ks[3] is the 3-th decomposition big-endian of the element to be checked
 ///
 /// The contraints that are enforced are:
 /// 1.
-/// z - 2^8⋅zs[0] = ks[0] ∈ lookup_u8
+/// z - 2^LIMB_BITS⋅zs[0] = ks[0] ∈ lookup_table
 ///
 /// 2.
-/// for i = 0..=N_BYTES - 2:
-///     zs[i] - 2^8⋅zs[i+1] = ks[i]  ∈ lookup_u8
+/// for i = 0..=N_LIMBS - 2:
+///     zs[i] - 2^LIMB_BITS⋅zs[i+1] = ks[i]  ∈ lookup_table
 ///
 /// 3.
-/// zs[N_BYTES - 1] == 0
+/// zs[N_LIMBS - 1] == 0
+///
+/// 4.
+/// If `n_bits` is not a multiple of `LIMB_BITS`, the most significant limb ks[N_LIMBS - 1] is `r = n_bits % LIMB_BITS` bits long:
+/// ks[N_LIMBS - 1]⋅2^(LIMB_BITS - r) ∈ lookup_table, where ks[N_LIMBS - 1] is zs[N_LIMBS - 2] (or z if N_LIMBS = 1)
+///
+/// Larger limbs take fewer advice columns per range check, but require a lookup table of `2^LIMB_BITS` rows, namely a circuit of at least `2^LIMB_BITS` rows.
 #[derive(Debug, Clone)]
-pub struct RangeCheckChip<const N_BYTES: usize> {
-    config: RangeCheckConfig<N_BYTES>,
+pub struct RangeCheckChip<const N_LIMBS: usize, const LIMB_BITS: usize = 8> {
+    config: RangeCheckConfig<N_LIMBS, LIMB_BITS>,
 }
 
-impl<const N_BYTES: usize> RangeCheckChip<N_BYTES> {
-    pub fn construct(config: RangeCheckConfig<N_BYTES>) -> Self {
+impl<const N_LIMBS: usize, const LIMB_BITS: usize> RangeCheckChip<N_LIMBS, LIMB_BITS> {
+    pub fn construct(config: RangeCheckConfig<N_LIMBS, LIMB_BITS>) -> Self {
         Self { config }
     }
 
-    /// Configures the Range Chip
+    /// Configures the Range Chip to check that elements lie in the range [0, 2^n_bits - 1]
+    /// `n_bits` must require exactly N_LIMBS limbs of LIMB_BITS bits, namely `(N_LIMBS - 1) * LIMB_BITS < n_bits <= N_LIMBS * LIMB_BITS`
     pub fn configure(
         meta: &mut ConstraintSystem<Fp>,
         z: Column<Advice>,
-        zs: [Column<Advice>; N_BYTES],
+        zs: [Column<Advice>; N_LIMBS],
         range: Column<Fixed>,
-    ) -> RangeCheckConfig<N_BYTES> {
+        n_bits: usize,
+    ) -> RangeCheckConfig<N_LIMBS, LIMB_BITS> {
+        assert!(
+            (N_LIMBS - 1) * LIMB_BITS < n_bits && n_bits <= N_LIMBS * LIMB_BITS,
+            "The range of {} bits doesn't fit in {} limbs of {} bits",
+            n_bits,
+            N_LIMBS,
+            LIMB_BITS
+        );
+
         meta.annotate_lookup_any_column(range, || "LOOKUP_MAXBITS_RANGE");
 
         // Constraint that the difference between the element to be checked and the 0-th truncated right-shifted value of the element to be within the range.
-        // z - 2^8⋅zs[0] = ks[0] ∈ lookup_u8
+        // z - 2^LIMB_BITS⋅zs[0] = ks[0] ∈ lookup_table
         meta.lookup_any(
-            "range u8 check for difference between the element to be checked and the 0-th truncated right-shifted value of the element",
+            "range check for difference between the element to be checked and the 0-th truncated right-shifted value of the element",
             |meta| {
                 let element = meta.query_advice(z, Rotation::cur());
 
                 let zero_truncation = meta.query_advice(zs[0], Rotation::cur());
 
-                let u8_range = meta.query_fixed(range, Rotation::cur());
+                let limb_range = meta.query_fixed(range, Rotation::cur());
 
-                let diff = element - zero_truncation * Expression::Constant(Fp::from(1 << 8));
+                let diff = element - zero_truncation * Expression::Constant(pow_of_two(LIMB_BITS));
 
-                vec![(diff, u8_range)]
+                vec![(diff, limb_range)]
             },
         );
 
-        // For i = 0..=N_BYTES - 2: Constraint that the difference between the i-th truncated right-shifted value and the (i+1)-th truncated right-shifted value to be within the range.
-        // zs[i] - 2^8⋅zs[i+1] = ks[i]  ∈ lookup_u8
-        for i in 0..=N_BYTES - 2 {
+        // For i = 0..=N_LIMBS - 2: Constraint that the difference between the i-th truncated right-shifted value and the (i+1)-th truncated right-shifted value to be within the range.
+        // zs[i] - 2^LIMB_BITS⋅zs[i+1] = ks[i]  ∈ lookup_table
+        for i in 0..N_LIMBS - 1 {
             meta.lookup_any(
-                format!("range u8 check for difference between the {}-th truncated right-shifted value and the {}-th truncated right-shifted value", i, i+1).as_str(),
+                format!("range check for difference between the {}-th truncated right-shifted value and the {}-th truncated right-shifted value", i, i+1).as_str(),
                 |meta| {
                     let i_truncation = meta.query_advice(zs[i], Rotation::cur());
                     let i_plus_one_truncation = meta.query_advice(zs[i + 1], Rotation::cur());
 
-                    let u8_range = meta.query_fixed(range, Rotation::cur());
+                    let limb_range = meta.query_fixed(range, Rotation::cur());
 
-                    let diff = i_truncation - i_plus_one_truncation * Expression::Constant(Fp::from(1 << 8));
+                    let diff = i_truncation - i_plus_one_truncation * Expression::Constant(pow_of_two(LIMB_BITS));
 
-                    vec![(diff, u8_range)]
+                    vec![(diff, limb_range)]
                 },
             );
         }
 
+        // The most significant limb needs an additional check if it is shorter than LIMB_BITS
+        let top_limb_bits = n_bits % LIMB_BITS;
+
+        if top_limb_bits != 0 {
+            // As zs[N_LIMBS - 1] is constrained to be zero, the most significant limb is equal to the previous truncated right-shifted value
+            let top_limb_column = if N_LIMBS == 1 { z } else { zs[N_LIMBS - 2] };
+
+            meta.lookup_any("range check for the most significant limb", |meta| {
+                let top_limb = meta.query_advice(top_limb_column, Rotation::cur());
+
+                let limb_range = meta.query_fixed(range, Rotation::cur());
+
+                // The limb lies in [0, 2^top_limb_bits - 1] if it still lies in the lookup table after being shifted left by LIMB_BITS - top_limb_bits bits
+                let shifted_limb =
+                    top_limb * Expression::Constant(pow_of_two(LIMB_BITS - top_limb_bits));
+
+                vec![(shifted_limb, limb_range)]
+            });
+        }
+
         RangeCheckConfig { zs }
     }
 
+    /// Loads the lookup table with values from `0` to `2^LIMB_BITS - 1`
+    pub fn load(layouter: &mut impl Layouter<Fp>, range: Column<Fixed>) -> Result<(), Error> {
+        let range_size = 1 << LIMB_BITS;
+
+        layouter.assign_region(
+            || format!("load range check table of {} bits", LIMB_BITS),
+            |mut region| {
+                for i in 0..range_size {
+                    region.assign_fixed(
+                        || "assign cell in fixed column",
+                        range,
+                        i,
+                        || Value::known(Fp::from(i as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
     /// Assign the truncated right-shifted values of the element to be checked to the corresponding columns zs at offset 0 starting from the element to be checked.
     pub fn assign(
         &self,
         region: &mut Region<'_, Fp>,
         element: &AssignedCell<Fp, Fp>,
     ) -> Result<(), Error> {
-        // Decompose the element in #N_BYTES bytes
+        // Decompose the element in #N_LIMBS limbs
         let ks = element
             .value()
             .copied()
-            .map(|x| decompose_fp_to_bytes(x, N_BYTES))
-            .transpose_vec(N_BYTES);
+            .map(|x| decompose_fp_to_limbs(x, LIMB_BITS, N_LIMBS))
+            .transpose_vec(N_LIMBS);
 
         // Initalize an empty vector of cells for the truncated right-shifted values of the element to be checked.
-        let mut zs = Vec::with_capacity(N_BYTES);
+        let mut zs = Vec::with_capacity(N_LIMBS);
         let mut z = element.clone();
 
-        // Calculate 1 / 2^8
-        let two_pow_eight_inv = Value::known(Fp::from(1 << 8).invert().unwrap());
+        // Calculate 1 / 2^LIMB_BITS
+        let two_pow_limb_bits_inv = Value::known(pow_of_two(LIMB_BITS).invert().unwrap());
 
         // Perform the assignment of the truncated right-shifted values to zs columns.
         for (i, k) in ks.iter().enumerate() {
             let zs_next = {
-                let k = k.map(|byte| Fp::from(byte as u64));
-                let zs_next_val = (z.value().copied() - k) * two_pow_eight_inv;
+                let k = k.map(Fp::from);
+                let zs_next_val = (z.value().copied() - k) * two_pow_limb_bits_inv;
                 region.assign_advice(
                     || format!("zs_{:?}", i),
                     self.config.zs[i],
@@ -148,7 +205,7 @@ impl<const N_BYTES: usize> RangeCheckChip<N_BYTES> {
         }
 
         // Constrain the final running sum output to be zero.
-        region.constrain_constant(zs[N_BYTES - 1].cell(), Fp::from(0))?;
+        region.constrain_constant(zs[N_LIMBS - 1].cell(), Fp::from(0))?;
 
         Ok(())
     }
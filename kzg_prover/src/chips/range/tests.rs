@@ -130,7 +130,8 @@ impl<const N_BYTES: usize> Circuit<Fp> for TestCircuit<N_BYTES> {
 
         let add_selector = meta.selector();
 
-        let range_check_config = RangeCheckChip::<N_BYTES>::configure(meta, c, zs, range);
+        let range_check_config =
+            RangeCheckChip::<N_BYTES>::configure(meta, c, zs, range, 8 * N_BYTES);
 
         let addchip_config = AddChip::configure(meta, a, b, c, add_selector);
 
@@ -152,22 +153,7 @@ impl<const N_BYTES: usize> Circuit<Fp> for TestCircuit<N_BYTES> {
         let range_chip = RangeCheckChip::construct(config.range_check_config);
 
         // Load the lookup table
-        let range = 1 << 8;
-
-        layouter.assign_region(
-            || format!("load range check table of {} bits", 8 * N_BYTES),
-            |mut region| {
-                for i in 0..range {
-                    region.assign_fixed(
-                        || "assign cell in fixed column",
-                        config.range,
-                        i,
-                        || Value::known(Fp::from(i as u64)),
-                    )?;
-                }
-                Ok(())
-            },
-        )?;
+        RangeCheckChip::<N_BYTES>::load(&mut layouter, config.range)?;
 
         // Initiate the add chip
         let addchip = AddChip::construct(config.addchip_config);
@@ -187,9 +173,89 @@ impl<const N_BYTES: usize> Circuit<Fp> for TestCircuit<N_BYTES> {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct LimbsTestConfig<const N_LIMBS: usize, const LIMB_BITS: usize> {
+    pub value: Column<Advice>,
+    pub range_check_config: RangeCheckConfig<N_LIMBS, LIMB_BITS>,
+    pub range: Column<Fixed>,
+}
+
+// The test circuit takes a single input value.
+// Performs a range check on the value, that should lie in N_BITS, decomposed in N_LIMBS limbs of LIMB_BITS.
+#[derive(Default, Clone, Debug)]
+struct LimbsTestCircuit<const N_BITS: usize, const N_LIMBS: usize, const LIMB_BITS: usize> {
+    pub value: Fp,
+}
+
+impl<const N_BITS: usize, const N_LIMBS: usize, const LIMB_BITS: usize> Circuit<Fp>
+    for LimbsTestCircuit<N_BITS, N_LIMBS, LIMB_BITS>
+{
+    type Config = LimbsTestConfig<N_LIMBS, LIMB_BITS>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let range = meta.fixed_column();
+
+        let value = meta.advice_column();
+        meta.enable_equality(value);
+
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+
+        let zs = [(); N_LIMBS].map(|_| meta.advice_column());
+
+        for column in zs.iter() {
+            meta.enable_equality(*column);
+        }
+
+        let range_check_config =
+            RangeCheckChip::<N_LIMBS, LIMB_BITS>::configure(meta, value, zs, range, N_BITS);
+
+        LimbsTestConfig {
+            value,
+            range_check_config,
+            range,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let range_chip = RangeCheckChip::construct(config.range_check_config);
+
+        // Load the lookup table of LIMB_BITS bits
+        RangeCheckChip::<N_LIMBS, LIMB_BITS>::load(&mut layouter, config.range)?;
+
+        // Assign the value and perform the range check in the same region, as the chip checks the value at offset 0
+        layouter.assign_region(
+            || "Perform range check on value",
+            |mut region| {
+                let value = region.assign_advice(
+                    || "value",
+                    config.value,
+                    0,
+                    || Value::known(self.value),
+                )?;
+
+                range_chip.assign(&mut region, &value)?;
+
+                Ok(())
+            },
+        )?;
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod testing {
-    use super::TestCircuit;
+    use super::{LimbsTestCircuit, TestCircuit};
     use halo2_proofs::{
         dev::{FailureLocation, MockProver, VerifyFailure},
         halo2curves::bn256::Fr as Fp,
@@ -278,6 +344,64 @@ mod testing {
         );
     }
 
+    // The range of 20 bits is decomposed in 2 limbs of 12 bits, the most significant limb is 8 bits long.
+    // 0xfffff is the max value within the range.
+    // 0x100000 overflows the range, 0xffffff overflows the most significant limb only.
+    #[test]
+    fn test_range_check_12_bits_limbs() {
+        let k = 13;
+
+        let circuit = LimbsTestCircuit::<20, 2, 12> {
+            value: Fp::from(0xfffff),
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+
+        for value in [0x100000, 0xffffff] {
+            let circuit = LimbsTestCircuit::<20, 2, 12> {
+                value: Fp::from(value),
+            };
+            let invalid_prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert!(invalid_prover.verify().is_err());
+        }
+    }
+
+    // The range of 32 bits is decomposed in 2 limbs of 16 bits.
+    #[test]
+    fn test_range_check_16_bits_limbs() {
+        let k = 17;
+
+        let circuit = LimbsTestCircuit::<32, 2, 16> {
+            value: Fp::from(0xffffffff),
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+
+        let circuit = LimbsTestCircuit::<32, 2, 16> {
+            value: Fp::from(0x100000000),
+        };
+        let invalid_prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert!(invalid_prover.verify().is_err());
+    }
+
+    // The range of 12 bits is decomposed in 2 limbs of 8 bits, the most significant limb is 4 bits long.
+    #[test]
+    fn test_range_check_8_bits_limbs_partial_range() {
+        let k = 9;
+
+        let circuit = LimbsTestCircuit::<12, 2, 8> {
+            value: Fp::from(0xfff),
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+
+        let circuit = LimbsTestCircuit::<12, 2, 8> {
+            value: Fp::from(0x1000),
+        };
+        let invalid_prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert!(invalid_prover.verify().is_err());
+    }
+
     #[cfg(feature = "dev-graph")]
     #[test]
     fn print_range_check_test() {
@@ -11,22 +11,36 @@ use crate::utils::{big_uint_to_fp, fp_to_big_uint};
 /// Example:
 /// decompose_fp_to_bytes(0x1f2f3f, 2) -> [0x3f, 0x2f]
 pub fn decompose_fp_to_bytes(value: Fp, n: usize) -> Vec<u8> {
-    let value_biguint = fp_to_big_uint(value);
-
-    let mut bytes = value_biguint.to_bytes_le();
-
-    // Pad with 0s at the most significant bytes if bytes length is less than n.
-    while bytes.len() < n {
-        bytes.push(0);
-    }
-
-    // If the bytes length exceeds n, print a warning and truncate the byte array at the most significant bytes.
-    if bytes.len() > n {
+    // If the value exceeds n bytes, print a warning as the most significant bytes are truncated.
+    if fp_to_big_uint(value).bits() > 8 * n as u64 {
         println!("Warning: `decompose_fp_to_bytes` value is decomposed in #bytes which are greater than n. Truncating the output to fit the specified length.");
-        bytes.truncate(n);
     }
 
-    bytes
+    decompose_fp_to_limbs(value, 8, n)
+        .into_iter()
+        .map(|limb| limb as u8)
+        .collect()
+}
+
+/// Converts value Fp to n limbs of `limb_bits` bits in little endian order. `limb_bits` must be at most 64.
+/// If value is decomposed in #limbs which are less than n, then the returned limbs are padded with 0s at the most significant limbs.
+/// Example:
+/// decompose_fp_to_limbs(0x1f2f3f, 12, 3) -> [0xf3f, 0x1f2, 0x000]
+/// If value is decomposed in #limbs which are greater than n, then the most significant limbs are truncated.
+/// Example:
+/// decompose_fp_to_limbs(0x1f2f3f, 12, 1) -> [0xf3f]
+pub fn decompose_fp_to_limbs(value: Fp, limb_bits: usize, n: usize) -> Vec<u64> {
+    let mut value_biguint = fp_to_big_uint(value);
+
+    let mask = (BigUint::from(1u8) << limb_bits) - 1u8;
+
+    (0..n)
+        .map(|_| {
+            let limb = &value_biguint & &mask;
+            value_biguint >>= limb_bits;
+            limb.to_u64_digits().first().copied().unwrap_or(0)
+        })
+        .collect()
 }
 
 pub fn pow_of_two(by: usize) -> Fp {
@@ -81,6 +95,22 @@ mod testing {
         assert_eq!(bytes, vec![0x3f, 0x2f]);
     }
 
+    // convert a 24 bit number in 3 limbs of 12 bits. Should correctly convert to 2 limbs and a 0 padded limb.
+    #[test]
+    fn test_decompose_fp_to_limbs_padding() {
+        let f = Fp::from(0x1f2f3f);
+        let limbs = decompose_fp_to_limbs(f, 12, 3);
+        assert_eq!(limbs, vec![0xf3f, 0x1f2, 0x000]);
+    }
+
+    // convert a 24 bit number in 1 limb of 12 bits. Should truncate the most significant limb
+    #[test]
+    fn test_decompose_fp_to_limbs_overflow() {
+        let f = Fp::from(0x1f2f3f);
+        let limbs = decompose_fp_to_limbs(f, 12, 1);
+        assert_eq!(limbs, vec![0xf3f]);
+    }
+
     #[test]
     fn test_pow_2() {
         let pow = pow_of_two(8);
@@ -8,7 +8,10 @@ mod test {
     };
     use crate::cryptocurrency::Cryptocurrency;
     use crate::entry::Entry;
-    use crate::utils::parse_csv_to_entries;
+    use crate::utils::{
+        big_intify_username, check_username, parse_csv_to_entries, parse_entries_from_source,
+        CurrencyManifest, JsonLinesSource,
+    };
     use halo2_proofs::dev::{FailureLocation, MockProver, VerifyFailure};
     use halo2_proofs::halo2curves::bn256::Fr as Fp;
     use halo2_proofs::plonk::Any;
@@ -36,6 +39,79 @@ mod test {
         assert_eq!(valid_prover.verify_par(), Ok(()))
     }
 
+    #[test]
+    fn test_valid_univariate_grand_sum_prover_from_json_lines() {
+        let path = "../csv/entry_16.csv";
+
+        let mut csv_entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
+        let mut cryptos = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
+        parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>(path, &mut csv_entries, &mut cryptos)
+            .unwrap();
+
+        // The same entries, read from a JSON Lines file
+        let jsonl_path = std::env::temp_dir().join("summa_kzg_test_json_lines.jsonl");
+        let mut jsonl = String::new();
+        for entry in &csv_entries {
+            jsonl.push_str(&format!(
+                "{{\"username\": \"{}\", \"balance_ETH_ETH\": {}, \"balance_USDT_ETH\": \"{}\"}}\n",
+                entry.username(),
+                entry.balances()[0],
+                entry.balances()[1]
+            ));
+        }
+        std::fs::write(&jsonl_path, jsonl).unwrap();
+
+        let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
+        parse_entries_from_source::<_, N_CURRENCIES>(
+            JsonLinesSource::open(&jsonl_path).unwrap(),
+            &mut entries,
+            &mut cryptos,
+            &CurrencyManifest::default(),
+        )
+        .unwrap();
+        for (entry, csv_entry) in entries.iter().zip(csv_entries.iter()) {
+            assert_eq!(entry.username(), csv_entry.username());
+            assert_eq!(entry.balances(), csv_entry.balances());
+        }
+
+        let circuit = UnivariateGrandSum::<N_BYTES, N_USERS, N_CURRENCIES>::init(entries.to_vec());
+
+        let valid_prover = MockProver::run(K, &circuit, vec![vec![]]).unwrap();
+
+        assert_eq!(valid_prover.verify_par(), Ok(()));
+
+        // A source can't hold more entries than the users of the circuit
+        let mut too_few_entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS - 1];
+        assert!(parse_entries_from_source::<_, N_CURRENCIES>(
+            JsonLinesSource::open(&jsonl_path).unwrap(),
+            &mut too_few_entries,
+            &mut cryptos,
+            &CurrencyManifest::default(),
+        )
+        .is_err());
+
+        std::fs::remove_file(&jsonl_path).unwrap();
+    }
+
+    #[test]
+    fn test_username_representation() {
+        let zero_balances = [BigUint::from(0_u32), BigUint::from(0_u32)];
+
+        // Usernames represented by 0 can't be distinguished from the padding entries
+        for username in ["", "\0", "\0\0"] {
+            assert!(check_username(username).is_err());
+            assert!(
+                Entry::<N_CURRENCIES>::new(username.to_string(), zero_balances.clone()).is_err()
+            );
+        }
+
+        // A username starting with a NUL byte would be represented as the username without it
+        assert_eq!(big_intify_username("\0alice"), big_intify_username("alice"));
+        assert!(check_username("\0alice").is_err());
+        assert!(check_username("alice").is_ok());
+        assert!(Entry::<N_CURRENCIES>::new("\0alice".to_string(), zero_balances).is_err());
+    }
+
     #[test]
     fn test_valid_univariate_grand_sum_full_prover() {
         const N_USERS: usize = 16;
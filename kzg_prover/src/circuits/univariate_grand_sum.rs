@@ -79,7 +79,8 @@ where
                 meta.enable_equality(*column);
             }
 
-            let range_check_config = RangeCheckChip::<N_BYTES>::configure(meta, z, zs, range);
+            let range_check_config =
+                RangeCheckChip::<N_BYTES>::configure(meta, z, zs, range, 8 * N_BYTES);
 
             range_check_configs.push(range_check_config);
         }
@@ -168,22 +169,7 @@ where
             .collect::<Vec<_>>();
 
         // Load lookup table to perform range check on individual balances -> Each balance should be in the range [0, 2^8 - 1]
-        let range = 1 << 8;
-
-        layouter.assign_region(
-            || format!("load range check table of {} bits", 8 * N_BYTES),
-            |mut region| {
-                for i in 0..range {
-                    region.assign_fixed(
-                        || "assign cell in fixed column",
-                        config.range,
-                        i,
-                        || Value::known(Fp::from(i as u64)),
-                    )?;
-                }
-                Ok(())
-            },
-        )?;
+        RangeCheckChip::<N_BYTES>::load(&mut layouter, config.range)?;
 
         // Assign entries
         let assigned_balances =
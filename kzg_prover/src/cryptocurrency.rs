@@ -1,7 +1,12 @@
+use crate::utils::format_decimal_balance;
+use num_bigint::BigUint;
+
 #[derive(Debug, Clone)]
 pub struct Cryptocurrency {
     pub name: String,
     pub chain: String,
+    /// Number of decimals of the cryptocurrency, namely the balances are expressed in units of `10^-decimals`
+    pub decimals: u32,
 }
 
 impl Cryptocurrency {
@@ -9,6 +14,12 @@ impl Cryptocurrency {
         Cryptocurrency {
             name: "".to_string(),
             chain: "".to_string(),
+            decimals: 0,
         }
     }
+
+    /// Formats a balance of this cryptocurrency, expressed in base units, as a human-readable decimal amount
+    pub fn format_balance(&self, balance: &BigUint) -> String {
+        format_decimal_balance(balance, self.decimals)
+    }
 }
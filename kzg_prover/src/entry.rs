@@ -1,6 +1,6 @@
 use num_bigint::BigUint;
 
-use crate::utils::big_intify_username;
+use crate::utils::{big_intify_username, check_username};
 
 /// An entry in the Merkle Sum Tree from the database of the CEX.
 /// It contains the username and the balances of the user.
@@ -12,7 +12,10 @@ pub struct Entry<const N_ASSETS: usize> {
 }
 
 impl<const N_ASSETS: usize> Entry<N_ASSETS> {
+    /// Builds an entry out of the username and the balances of a user. The username must not be empty, see [`check_username`].
     pub fn new(username: String, balances: [BigUint; N_ASSETS]) -> Result<Self, &'static str> {
+        check_username(&username)?;
+
         Ok(Entry {
             username_as_big_uint: big_intify_username(&username),
             balances,
@@ -0,0 +1,126 @@
+use crate::cryptocurrency::Cryptocurrency;
+use crate::entry::Entry;
+use crate::utils::{check_username, parse_decimal_balance, CurrencyManifest};
+use csv::StringRecord;
+use std::error::Error;
+use summa_sources::EntrySource;
+
+/// A lazy iterator over the entries of an [`EntrySource`], returned by [`read_entries`].
+/// Each record is validated as it is read, and the iteration stops at the first error.
+pub struct SourceEntries<S: EntrySource, const N_ASSETS: usize> {
+    source: S,
+    cryptocurrencies: Vec<Cryptocurrency>,
+    is_exhausted: bool,
+}
+
+impl<S: EntrySource, const N_ASSETS: usize> Iterator for SourceEntries<S, N_ASSETS> {
+    type Item = Result<Entry<N_ASSETS>, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_exhausted {
+            return None;
+        }
+
+        let entry = self
+            .source
+            .next_record()?
+            .and_then(|record| parse_record::<N_ASSETS>(&record, &self.cryptocurrencies));
+        if entry.is_err() {
+            self.is_exhausted = true;
+        }
+
+        Some(entry)
+    }
+}
+
+/// Reads the header of `source` into cryptocurrencies, whose decimals are declared by `manifest`, and returns them along with a lazy iterator over the entries of the source.
+/// The header is validated as the one of a CSV file: the first column must be `username`, followed by one `balance_<cryptocurrency>_<chain>` column per cryptocurrency.
+pub fn read_entries<S: EntrySource, const N_ASSETS: usize>(
+    source: S,
+    manifest: &CurrencyManifest,
+) -> Result<(Vec<Cryptocurrency>, SourceEntries<S, N_ASSETS>), Box<dyn Error>> {
+    let headers = source.headers();
+    if headers.get(0) != Some("username") {
+        return Err(Box::from("Username not found"));
+    }
+
+    let mut cryptocurrencies = Vec::with_capacity(N_ASSETS);
+    // Extracting cryptocurrency names from column names
+    for header in headers.iter().skip(1) {
+        // Skipping 'username' column
+        let parts: Vec<&str> = header.split('_').collect();
+        if parts.len() == 3 && parts[0] == "balance" {
+            cryptocurrencies.push(Cryptocurrency {
+                name: parts[1].to_owned(),
+                chain: parts[2].to_owned(),
+                decimals: manifest.decimals(header)?,
+            });
+        } else {
+            // Throw an error if the header is malformed
+            return Err(format!("Invalid header: {}", header).into());
+        }
+    }
+    if cryptocurrencies.len() != N_ASSETS {
+        return Err(Box::from("Number of currencies does not match"));
+    }
+
+    Ok((
+        cryptocurrencies.clone(),
+        SourceEntries {
+            source,
+            cryptocurrencies,
+            is_exhausted: false,
+        },
+    ))
+}
+
+/// Reads all the entries of `source`, as [`read_entries`], into `entries` and its cryptocurrencies into `cryptocurrencies`.
+/// The entries past the last record are left untouched, and an error is thrown if the source holds more records than `entries`.
+pub fn parse_entries_from_source<S: EntrySource, const N_ASSETS: usize>(
+    source: S,
+    entries: &mut [Entry<N_ASSETS>],
+    cryptocurrencies: &mut [Cryptocurrency],
+    manifest: &CurrencyManifest,
+) -> Result<(), Box<dyn Error>> {
+    let (source_cryptocurrencies, source_entries) = read_entries::<S, N_ASSETS>(source, manifest)?;
+
+    for (cryptocurrency, source_cryptocurrency) in
+        cryptocurrencies.iter_mut().zip(source_cryptocurrencies)
+    {
+        *cryptocurrency = source_cryptocurrency;
+    }
+
+    let n_entries = entries.len();
+    for (i, entry) in source_entries.enumerate() {
+        *entries
+            .get_mut(i)
+            .ok_or(format!("Too many entries, expected at most {}", n_entries))? = entry?;
+    }
+
+    Ok(())
+}
+
+/// Parses a record `username,balance_<cryptocurrency>_<chain>,...` into an entry
+fn parse_record<const N_ASSETS: usize>(
+    record: &StringRecord,
+    cryptocurrencies: &[Cryptocurrency],
+) -> Result<Entry<N_ASSETS>, Box<dyn Error>> {
+    let username = record.get(0).ok_or("Username not found")?.to_owned();
+    check_username(&username)?;
+
+    let mut balances_big_int = Vec::with_capacity(N_ASSETS);
+    for (i, cryptocurrency) in cryptocurrencies.iter().enumerate() {
+        let balance_str = record.get(i + 1).ok_or(format!(
+            "Balance for {} on {} not found",
+            cryptocurrency.name, cryptocurrency.chain
+        ))?;
+        let balance =
+            parse_decimal_balance(balance_str, cryptocurrency.decimals).ok_or(format!(
+                "Invalid balance for {} on {}, expected a decimal amount with at most {} decimals",
+                cryptocurrency.name, cryptocurrency.chain, cryptocurrency.decimals
+            ))?;
+        balances_big_int.push(balance);
+    }
+
+    Ok(Entry::new(username, balances_big_int.try_into().unwrap())?)
+}
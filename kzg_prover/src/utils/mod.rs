@@ -1,5 +1,16 @@
 mod csv_parser;
+mod entry_source;
 mod operation_helpers;
 
-pub use csv_parser::parse_csv_to_entries;
+pub use csv_parser::{parse_csv_to_entries, parse_csv_to_entries_with_manifest};
+pub use entry_source::{parse_entries_from_source, read_entries, SourceEntries};
 pub use operation_helpers::*;
+// The sources of liabilities and the currency manifest are shared with the `zk_prover` crate through the `summa-sources` crate
+#[cfg(feature = "parquet")]
+pub use summa_sources::ParquetSource;
+pub use summa_sources::{
+    format_decimal_balance, parse_decimal_balance, CsvSource, CurrencyManifest, EntrySource,
+    JsonLinesSource, MAX_DECIMALS,
+};
+#[cfg(feature = "sqlite")]
+pub use summa_sources::{SqliteSource, SQLITE_BATCH_SIZE};
@@ -0,0 +1,122 @@
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Declares the number of decimals of each balance column of a CSV file, e.g. `{"balance_BTC_BTC": 8, "balance_ETH_ETH": 18, "balance_USDT_ETH": 6}`.
+///
+/// The balances of a column with `d` decimals are decimal strings such as `1.23456789`, which are scaled by `10^d` into integer base units.
+/// An empty manifest declares no decimals, such that every balance must be an integer. A non-empty manifest must declare every balance column of the CSV file.
+/// The decimals of a column can't exceed [`MAX_DECIMALS`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "HashMap<String, u32>", into = "HashMap<String, u32>")]
+pub struct CurrencyManifest {
+    decimals: HashMap<String, u32>,
+}
+
+/// The maximum number of decimals of a balance column, such that the scale factor `10^decimals` is lower than the BN254 scalar field modulus
+pub const MAX_DECIMALS: u32 = 76;
+
+impl TryFrom<HashMap<String, u32>> for CurrencyManifest {
+    type Error = String;
+
+    fn try_from(decimals: HashMap<String, u32>) -> Result<Self, Self::Error> {
+        for (column, decimals) in decimals.iter() {
+            if *decimals > MAX_DECIMALS {
+                return Err(format!(
+                    "Decimals of {} exceed the maximum of {}, found {}",
+                    column, MAX_DECIMALS, decimals
+                ));
+            }
+        }
+        Ok(CurrencyManifest { decimals })
+    }
+}
+
+impl From<CurrencyManifest> for HashMap<String, u32> {
+    fn from(manifest: CurrencyManifest) -> Self {
+        manifest.decimals
+    }
+}
+
+impl CurrencyManifest {
+    /// Builds a manifest out of (column, decimals) pairs, e.g. `("balance_ETH_ETH", 18)`, throwing an error if any decimals exceed [`MAX_DECIMALS`]
+    pub fn new<S: Into<String>>(
+        decimals: impl IntoIterator<Item = (S, u32)>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let decimals = decimals
+            .into_iter()
+            .map(|(column, decimals)| (column.into(), decimals))
+            .collect::<HashMap<String, u32>>();
+        Ok(CurrencyManifest::try_from(decimals)?)
+    }
+
+    /// Reads a manifest from a JSON file stored at `path`
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Returns the number of decimals of the given balance column
+    pub fn decimals(&self, column: &str) -> Result<u32, Box<dyn Error>> {
+        if self.decimals.is_empty() {
+            return Ok(0);
+        }
+
+        self.decimals.get(column).copied().ok_or_else(|| {
+            format!(
+                "Decimals of {} not declared in the currency manifest",
+                column
+            )
+            .into()
+        })
+    }
+}
+
+/// Scales a non-negative decimal string with at most `decimals` significant fractional digits, such as `1.23456789`, into integer base units.
+///
+/// Returns `None` if the string is not a decimal number or if it is more precise than `decimals`
+pub fn parse_decimal_balance(balance_str: &str, decimals: u32) -> Option<BigUint> {
+    let (integer_part, fractional_part) = match balance_str.split_once('.') {
+        Some((integer_part, fractional_part)) => (integer_part, fractional_part),
+        None => (balance_str, ""),
+    };
+
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if integer_part.is_empty() || !is_digits(integer_part) || !is_digits(fractional_part) {
+        return None;
+    }
+    if balance_str.ends_with('.') {
+        return None;
+    }
+
+    // Trailing zeros beyond the precision of the currency don't change the amount
+    let fractional_part = fractional_part.trim_end_matches('0');
+    if fractional_part.len() > decimals as usize {
+        return None;
+    }
+
+    let digits = format!(
+        "{}{}{}",
+        integer_part,
+        fractional_part,
+        "0".repeat(decimals as usize - fractional_part.len())
+    );
+    BigUint::parse_bytes(digits.as_bytes(), 10)
+}
+
+/// Formats an amount of integer base units as a decimal string with `decimals` fractional digits, e.g. `123456789` with 8 decimals as `1.23456789`
+pub fn format_decimal_balance(balance: &BigUint, decimals: u32) -> String {
+    let digits = balance.to_str_radix(10);
+    if decimals == 0 {
+        return digits;
+    }
+
+    let decimals = decimals as usize;
+    let digits = format!("{:0>width$}", digits, width = decimals + 1);
+    let (integer_part, fractional_part) = digits.split_at(digits.len() - decimals);
+    format!("{}.{}", integer_part, fractional_part)
+}
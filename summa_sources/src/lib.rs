@@ -0,0 +1,15 @@
+//! This crate contains the sources of liabilities and the currency manifest shared by the `zk_prover` and `kzg_prover` crates,
+//! so that both crates read the same formats and scale the decimal balances in the same way.
+
+/// The number of decimals of each balance column, and the scaling of decimal balances into integer base units.
+pub mod currency_manifest;
+pub mod record_source;
+
+pub use currency_manifest::{
+    format_decimal_balance, parse_decimal_balance, CurrencyManifest, MAX_DECIMALS,
+};
+#[cfg(feature = "parquet")]
+pub use record_source::ParquetSource;
+pub use record_source::{CsvSource, EntrySource, JsonLinesSource};
+#[cfg(feature = "sqlite")]
+pub use record_source::{SqliteSource, SQLITE_BATCH_SIZE};
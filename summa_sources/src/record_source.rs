@@ -0,0 +1,392 @@
+//! The sources of liabilities, which read the records of a file or a database one at a time.
+//!
+//! The sources only depend on the records, and not on the entries of either prover crate.
+
+use csv::StringRecord;
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+use std::path::Path;
+
+/// A source of liabilities, read one record at a time so that the source never needs to be loaded in memory.
+///
+/// The header of a source holds the column names, formatted as the headers of a CSV file:
+///
+/// `username,balance_<cryptocurrency>_<chain>,balance_<cryptocurrency>_<chain>,...`
+///
+/// optionally with a `salt` column after the `username` column in the `zk_prover` crate, and each record holds one field per column, in the same order.
+/// The records are turned into entries by the `read_entries` function of each prover crate, which validates them as its `parse_csv_to_entries` does.
+pub trait EntrySource {
+    /// Returns the column names of the source
+    fn headers(&self) -> &StringRecord;
+
+    /// Reads the next record, or returns `None` once the source is exhausted
+    fn next_record(&mut self) -> Option<Result<StringRecord, Box<dyn Error>>>;
+}
+
+/// An [`EntrySource`] reading a CSV file, formatted as described by [`EntrySource`]
+pub struct CsvSource {
+    headers: StringRecord,
+    records: csv::StringRecordsIntoIter<File>,
+}
+
+impl CsvSource {
+    /// Opens the CSV file stored at `path` and reads its header
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let file = File::open(path)?;
+        let mut rdr = csv::ReaderBuilder::new().from_reader(file);
+        let headers = rdr.headers()?.clone();
+
+        Ok(CsvSource {
+            headers,
+            records: rdr.into_records(),
+        })
+    }
+}
+
+impl EntrySource for CsvSource {
+    fn headers(&self) -> &StringRecord {
+        &self.headers
+    }
+
+    fn next_record(&mut self) -> Option<Result<StringRecord, Box<dyn Error>>> {
+        self.records
+            .next()
+            .map(|record| record.map_err(|e| e.into()))
+    }
+}
+
+/// An [`EntrySource`] reading a JSON Lines file, namely a file holding one JSON object per line, e.g.
+///
+/// `{"username": "dxGaEAii", "balance_ETH_ETH": 11888, "balance_USDT_ETH": "41163"}`
+///
+/// The columns are the keys of the first object, in the order they appear, and every object must hold the same keys.
+/// The values are either strings or integers, the strings being required for decimal balances and, in the `zk_prover` crate, salts. Empty lines are skipped.
+pub struct JsonLinesSource {
+    headers: StringRecord,
+    first_record: Option<StringRecord>,
+    lines: Lines<BufReader<File>>,
+    line_number: usize,
+}
+
+impl JsonLinesSource {
+    /// Opens the JSON Lines file stored at `path` and reads its first object, whose keys are the column names
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let mut line_number = 0;
+
+        let object = loop {
+            line_number += 1;
+            match lines.next() {
+                Some(line) => {
+                    let line = line?;
+                    if !line.trim().is_empty() {
+                        break parse_json_line(&line, line_number)?;
+                    }
+                }
+                None => return Err(Box::from("JSON Lines file is empty")),
+            }
+        };
+
+        let headers = StringRecord::from(
+            object
+                .0
+                .iter()
+                .map(|(column, _)| column.as_str())
+                .collect::<Vec<_>>(),
+        );
+        let first_record = json_object_to_record(object, &headers, line_number)?;
+
+        Ok(JsonLinesSource {
+            headers,
+            first_record: Some(first_record),
+            lines,
+            line_number,
+        })
+    }
+}
+
+impl EntrySource for JsonLinesSource {
+    fn headers(&self) -> &StringRecord {
+        &self.headers
+    }
+
+    fn next_record(&mut self) -> Option<Result<StringRecord, Box<dyn Error>>> {
+        if let Some(record) = self.first_record.take() {
+            return Some(Ok(record));
+        }
+
+        for line in self.lines.by_ref() {
+            self.line_number += 1;
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            return Some(parse_json_line(&line, self.line_number).and_then(|object| {
+                json_object_to_record(object, &self.headers, self.line_number)
+            }));
+        }
+
+        None
+    }
+}
+
+/// A JSON object whose fields are kept in the order they appear, which sets the order of the columns
+struct OrderedJsonObject(Vec<(String, serde_json::Value)>);
+
+impl<'de> Deserialize<'de> for OrderedJsonObject {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct OrderedJsonObjectVisitor;
+
+        impl<'de> Visitor<'de> for OrderedJsonObjectVisitor {
+            type Value = OrderedJsonObject;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut fields = Vec::new();
+                while let Some(field) = map.next_entry::<String, serde_json::Value>()? {
+                    fields.push(field);
+                }
+                Ok(OrderedJsonObject(fields))
+            }
+        }
+
+        deserializer.deserialize_map(OrderedJsonObjectVisitor)
+    }
+}
+
+/// Parses a line of a JSON Lines file into a JSON object
+fn parse_json_line(line: &str, line_number: usize) -> Result<OrderedJsonObject, Box<dyn Error>> {
+    serde_json::from_str(line)
+        .map_err(|e| format!("Invalid JSON object on line {}: {}", line_number, e).into())
+}
+
+/// Orders the values of a JSON object according to `headers`, throwing an error if the object doesn't hold exactly the columns of `headers`
+fn json_object_to_record(
+    object: OrderedJsonObject,
+    headers: &StringRecord,
+    line_number: usize,
+) -> Result<StringRecord, Box<dyn Error>> {
+    if object.0.len() != headers.len() {
+        return Err(format!(
+            "Line {} doesn't hold the same columns as the first line",
+            line_number
+        )
+        .into());
+    }
+
+    let mut fields = Vec::with_capacity(headers.len());
+    for column in headers {
+        let (_, value) = object
+            .0
+            .iter()
+            .find(|(key, _)| key == column)
+            .ok_or(format!(
+                "Column {} not found on line {}",
+                column, line_number
+            ))?;
+        let field = match value {
+            serde_json::Value::String(string) => string.clone(),
+            // Negative integers are kept, so that they are rejected as in a CSV file
+            serde_json::Value::Number(number) if number.is_u64() || number.is_i64() => {
+                number.to_string()
+            }
+            _ => {
+                return Err(format!(
+                    "Invalid value for column {} on line {}, expected a string or an integer",
+                    column, line_number
+                )
+                .into())
+            }
+        };
+        fields.push(field);
+    }
+
+    Ok(StringRecord::from(fields))
+}
+
+/// An [`EntrySource`] reading a Parquet file with one column per CSV column, e.g. `username`, `balance_ETH_ETH` and `balance_USDT_ETH`, in the same order.
+/// The values are either UTF-8 strings or integers, the strings being required for decimal balances and, in the `zk_prover` crate, salts.
+#[cfg(feature = "parquet")]
+pub struct ParquetSource {
+    headers: StringRecord,
+    rows: parquet::record::reader::RowIter<'static>,
+}
+
+#[cfg(feature = "parquet")]
+impl ParquetSource {
+    /// Opens the Parquet file stored at `path` and reads its schema, whose column names are the headers
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let reader = SerializedFileReader::new(File::open(path)?)?;
+        let headers = StringRecord::from(
+            reader
+                .metadata()
+                .file_metadata()
+                .schema_descr()
+                .columns()
+                .iter()
+                .map(|column| column.name())
+                .collect::<Vec<_>>(),
+        );
+
+        Ok(ParquetSource {
+            headers,
+            rows: parquet::record::reader::RowIter::from_file_into(Box::new(reader)),
+        })
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl EntrySource for ParquetSource {
+    fn headers(&self) -> &StringRecord {
+        &self.headers
+    }
+
+    fn next_record(&mut self) -> Option<Result<StringRecord, Box<dyn Error>>> {
+        use parquet::record::Field;
+
+        let row = match self.rows.next()? {
+            Ok(row) => row,
+            Err(e) => return Some(Err(e.into())),
+        };
+
+        let mut fields = Vec::with_capacity(self.headers.len());
+        for (column, field) in row.get_column_iter() {
+            let field = match field {
+                Field::Str(string) => string.clone(),
+                Field::Byte(integer) => integer.to_string(),
+                Field::Short(integer) => integer.to_string(),
+                Field::Int(integer) => integer.to_string(),
+                Field::Long(integer) => integer.to_string(),
+                Field::UByte(integer) => integer.to_string(),
+                Field::UShort(integer) => integer.to_string(),
+                Field::UInt(integer) => integer.to_string(),
+                Field::ULong(integer) => integer.to_string(),
+                _ => {
+                    return Some(Err(format!(
+                        "Invalid value for column {}, expected a string or an integer",
+                        column
+                    )
+                    .into()))
+                }
+            };
+            fields.push(field);
+        }
+
+        Some(Ok(StringRecord::from(fields)))
+    }
+}
+
+/// Number of rows read from SQLite at once by [`SqliteSource`]
+#[cfg(feature = "sqlite")]
+pub const SQLITE_BATCH_SIZE: usize = 1024;
+
+/// An [`EntrySource`] reading a table of a SQLite database with one column per CSV column, e.g. `username`, `balance_ETH_ETH` and `balance_USDT_ETH`, in the same order.
+/// The values are either texts or integers, the texts being required for decimal balances and, in the `zk_prover` crate, salts.
+///
+/// The rows are read in order of `rowid`, in batches of [`SQLITE_BATCH_SIZE`] rows, so the table must not be a `WITHOUT ROWID` table.
+#[cfg(feature = "sqlite")]
+pub struct SqliteSource {
+    connection: rusqlite::Connection,
+    query: String,
+    headers: StringRecord,
+    batch: std::vec::IntoIter<StringRecord>,
+    last_rowid: i64,
+    is_exhausted: bool,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteSource {
+    /// Opens the SQLite database stored at `path` in read-only mode and reads the column names of `table`
+    pub fn open<P: AsRef<Path>>(path: P, table: &str) -> Result<Self, Box<dyn Error>> {
+        let connection = rusqlite::Connection::open_with_flags(
+            path,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY,
+        )?;
+        let table = format!("\"{}\"", table.replace('"', "\"\""));
+
+        let headers = {
+            let statement = connection.prepare(&format!("SELECT * FROM {}", table))?;
+            StringRecord::from(statement.column_names())
+        };
+
+        Ok(SqliteSource {
+            connection,
+            query: format!(
+                "SELECT rowid, * FROM {} WHERE rowid > ?1 ORDER BY rowid LIMIT ?2",
+                table
+            ),
+            headers,
+            batch: Vec::new().into_iter(),
+            last_rowid: i64::MIN,
+            is_exhausted: false,
+        })
+    }
+
+    fn read_batch(&mut self) -> Result<(), Box<dyn Error>> {
+        use rusqlite::types::ValueRef;
+
+        let mut statement = self.connection.prepare_cached(&self.query)?;
+        let mut rows = statement.query(rusqlite::params![self.last_rowid, SQLITE_BATCH_SIZE])?;
+
+        let mut batch = Vec::with_capacity(SQLITE_BATCH_SIZE);
+        while let Some(row) = rows.next()? {
+            self.last_rowid = row.get(0)?;
+
+            let mut fields = Vec::with_capacity(self.headers.len());
+            for (i, column) in self.headers.iter().enumerate() {
+                // The first column is the rowid
+                let field = match row.get_ref(i + 1)? {
+                    ValueRef::Text(text) => std::str::from_utf8(text)?.to_owned(),
+                    ValueRef::Integer(integer) => integer.to_string(),
+                    _ => {
+                        return Err(format!(
+                            "Invalid value for column {}, expected a text or an integer",
+                            column
+                        )
+                        .into())
+                    }
+                };
+                fields.push(field);
+            }
+            batch.push(StringRecord::from(fields));
+        }
+
+        self.is_exhausted = batch.len() < SQLITE_BATCH_SIZE;
+        self.batch = batch.into_iter();
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl EntrySource for SqliteSource {
+    fn headers(&self) -> &StringRecord {
+        &self.headers
+    }
+
+    fn next_record(&mut self) -> Option<Result<StringRecord, Box<dyn Error>>> {
+        if let Some(record) = self.batch.next() {
+            return Some(Ok(record));
+        }
+        if self.is_exhausted {
+            return None;
+        }
+
+        if let Err(e) = self.read_batch() {
+            self.is_exhausted = true;
+            return Some(Err(e));
+        }
+        self.batch.next().map(Ok)
+    }
+}
@@ -4,8 +4,9 @@ use halo2_proofs::plonk::{keygen_pk, keygen_vk};
 use snark_verifier_sdk::CircuitExt;
 use summa_solvency::{
     circuits::merkle_sum_tree::MstInclusionCircuit,
-    circuits::utils::{full_prover, full_verifier, generate_setup_artifacts},
-    merkle_sum_tree::{MerkleSumTree, Tree},
+    circuits::mst_quaternary_inclusion::QuaternaryMstInclusionCircuit,
+    circuits::utils::{full_prover, full_verifier, generate_setup_artifacts, measure_k},
+    merkle_sum_tree::{MerkleSumTree, QuaternaryMerkleSumTree, Tree},
 };
 
 const SAMPLE_SIZE: usize = 10;
@@ -13,6 +14,10 @@ const LEVELS: usize = 20;
 const N_CURRENCIES: usize = 1;
 const PATH_NAME: &str = "one_asset";
 const N_BYTES: usize = 14;
+// A 4-ary tree of 4^(LEVELS / 2) leaves holds as many entries as a binary tree of 2^LEVELS leaves
+const QUATERNARY_LEVELS: usize = LEVELS / 2;
+// Upper bound of the k measured for the circuits by `measure_k`
+const MAX_K: u32 = 20;
 
 fn build_mstree(_c: &mut Criterion) {
     let mut criterion = Criterion::default().sample_size(SAMPLE_SIZE);
@@ -160,6 +165,129 @@ fn verify_zk_proof_mst_inclusion_circuit(_c: &mut Criterion) {
     });
 }
 
+/// Prints the number of rows of the binary and of the 4-ary inclusion circuits for trees holding the same number of entries
+fn report_circuit_rows(_c: &mut Criterion) {
+    let k = measure_k(
+        &MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty(),
+        MAX_K,
+    )
+    .unwrap();
+    println!(
+        "mst inclusion circuit with {} levels and {} currencies fits in 2^{} = {} rows",
+        LEVELS,
+        N_CURRENCIES,
+        k,
+        1 << k
+    );
+
+    let quaternary_k = measure_k(
+        &QuaternaryMstInclusionCircuit::<QUATERNARY_LEVELS, N_CURRENCIES, N_BYTES>::init_empty(),
+        MAX_K,
+    )
+    .unwrap();
+    println!(
+        "4-ary mst inclusion circuit with {} levels and {} currencies fits in 2^{} = {} rows",
+        QUATERNARY_LEVELS,
+        N_CURRENCIES,
+        quaternary_k,
+        1 << quaternary_k
+    );
+}
+
+fn build_quaternary_mstree(_c: &mut Criterion) {
+    let mut criterion = Criterion::default().sample_size(SAMPLE_SIZE);
+
+    let csv_file = format!(
+        "benches/csv/{}/{}_entry_2_{}.csv",
+        PATH_NAME, PATH_NAME, LEVELS
+    );
+
+    let bench_name = format!(
+        "build 4-ary Merkle sum tree for 2 power of {} entries with {} currencies",
+        LEVELS, N_CURRENCIES
+    );
+
+    criterion.bench_function(&bench_name, |b| {
+        b.iter(|| {
+            QuaternaryMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(&csv_file).unwrap();
+        })
+    });
+}
+
+fn generate_zk_proof_quaternary_mst_inclusion_circuit(_c: &mut Criterion) {
+    let mut criterion = Criterion::default().sample_size(SAMPLE_SIZE);
+
+    let empty_circuit =
+        QuaternaryMstInclusionCircuit::<QUATERNARY_LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+
+    let k = measure_k(&empty_circuit, MAX_K).unwrap();
+    let (params, pk, _) = generate_setup_artifacts(k, None, empty_circuit).unwrap();
+
+    let csv_file = format!(
+        "benches/csv/{}/{}_entry_2_{}.csv",
+        PATH_NAME, PATH_NAME, LEVELS
+    );
+
+    let merkle_sum_tree = QuaternaryMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(&csv_file).unwrap();
+
+    let user_index = 0;
+
+    let merkle_proof = merkle_sum_tree.generate_proof(user_index).unwrap();
+
+    let circuit = QuaternaryMstInclusionCircuit::<QUATERNARY_LEVELS, N_CURRENCIES, N_BYTES>::init(
+        merkle_proof,
+    );
+
+    let bench_name = format!(
+        "generate zk proof - tree of 2 power of {} entries with {} currencies 4-ary mst inclusion circuit",
+        LEVELS, N_CURRENCIES
+    );
+    criterion.bench_function(&bench_name, |b| {
+        b.iter(|| {
+            full_prover(&params, &pk, circuit.clone(), circuit.instances());
+        })
+    });
+}
+
+fn verify_zk_proof_quaternary_mst_inclusion_circuit(_c: &mut Criterion) {
+    let mut criterion = Criterion::default().sample_size(SAMPLE_SIZE);
+
+    let empty_circuit =
+        QuaternaryMstInclusionCircuit::<QUATERNARY_LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+
+    let k = measure_k(&empty_circuit, MAX_K).unwrap();
+    let (params, pk, vk) = generate_setup_artifacts(k, None, empty_circuit).unwrap();
+
+    let csv_file = format!(
+        "benches/csv/{}/{}_entry_2_{}.csv",
+        PATH_NAME, PATH_NAME, LEVELS
+    );
+
+    let merkle_sum_tree = QuaternaryMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(&csv_file).unwrap();
+
+    let user_index = 0;
+
+    let merkle_proof = merkle_sum_tree.generate_proof(user_index).unwrap();
+
+    let circuit = QuaternaryMstInclusionCircuit::<QUATERNARY_LEVELS, N_CURRENCIES, N_BYTES>::init(
+        merkle_proof,
+    );
+
+    let proof = full_prover(&params, &pk, circuit.clone(), circuit.instances());
+
+    println!("proof size in bytes: {}", proof.len());
+
+    let bench_name = format!(
+        "verify zk proof - tree of 2 power of {} entries with {} currencies 4-ary mst inclusion circuit",
+        LEVELS, N_CURRENCIES
+    );
+    criterion.bench_function(&bench_name, |b| {
+        b.iter(|| {
+            full_verifier(&params, &vk, proof.clone(), circuit.instances());
+        })
+    });
+}
+
 criterion_group!(
     benches,
     build_mstree,
@@ -168,5 +296,9 @@ criterion_group!(
     proving_key_gen_mst_inclusion_circuit,
     generate_zk_proof_mst_inclusion_circuit,
     verify_zk_proof_mst_inclusion_circuit,
+    report_circuit_rows,
+    build_quaternary_mstree,
+    generate_zk_proof_quaternary_mst_inclusion_circuit,
+    verify_zk_proof_quaternary_mst_inclusion_circuit,
 );
 criterion_main!(benches);
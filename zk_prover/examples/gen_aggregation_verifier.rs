@@ -0,0 +1,90 @@
+#![feature(generic_const_exprs)]
+
+use halo2_proofs::{
+    halo2curves::bn256::Bn256,
+    poly::{commitment::Params, kzg::commitment::ParamsKZG},
+};
+use rand::rngs::OsRng;
+use snark_verifier_sdk::{
+    evm::{evm_verify, gen_evm_proof_shplonk, gen_evm_verifier_shplonk},
+    gen_pk, CircuitExt,
+};
+use std::path::Path;
+use summa_solvency::{
+    circuits::{
+        aggregation::{
+            aggregate_inclusion_snarks, gen_inclusion_snark, InclusionAggregationCircuit,
+        },
+        merkle_sum_tree::MstInclusionCircuit,
+        utils::write_verifier_sol_from_yul,
+    },
+    merkle_sum_tree::{MerkleSumTree, Tree},
+};
+
+const LEVELS: usize = 4;
+const N_CURRENCIES: usize = 2;
+const N_BYTES: usize = 14;
+
+// The size of the inclusion circuit and of the aggregation circuit. The latter has to be raised to aggregate more snarks
+const INCLUSION_K: u32 = 11;
+const AGGREGATION_K: u32 = 22;
+
+fn main() {
+    // generate a universal trusted setup for testing. The inclusion circuits use the same setup, downsized to their size, as required by the aggregation.
+    // For a production ready verifier, the setup should be read from a `ptau` file of at least 2^AGGREGATION_K rows.
+    let params = ParamsKZG::<Bn256>::setup(AGGREGATION_K, OsRng);
+    let mut inclusion_params = params.clone();
+    inclusion_params.downsize(INCLUSION_K);
+
+    let inclusion_pk = gen_pk(
+        &inclusion_params,
+        &MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty(),
+        None,
+    );
+
+    let merkle_sum_tree =
+        MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+    // Generate the snarks of the inclusion proofs of the users to be aggregated
+    let user_indices = [0, 1];
+
+    let snarks = user_indices
+        .iter()
+        .map(|user_index| {
+            let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(
+                merkle_sum_tree.generate_proof(*user_index).unwrap(),
+            );
+            gen_inclusion_snark(&inclusion_params, &inclusion_pk, circuit)
+        })
+        .collect();
+
+    let aggregation_circuit = aggregate_inclusion_snarks::<N_CURRENCIES>(&params, snarks).unwrap();
+
+    let aggregation_pk = gen_pk(&params, &aggregation_circuit, None);
+
+    let yul_output_path = "../contracts/src/AggregationVerifier.yul";
+    let sol_output_path = "../contracts/src/AggregationVerifier.sol";
+
+    // The verifier performs the pairing check on the accumulator exposed by the aggregation circuit
+    let deployment_code = gen_evm_verifier_shplonk::<InclusionAggregationCircuit<N_CURRENCIES>>(
+        &params,
+        aggregation_pk.get_vk(),
+        aggregation_circuit.num_instance(),
+        Some(Path::new(yul_output_path)),
+    );
+
+    write_verifier_sol_from_yul(yul_output_path, sol_output_path).unwrap();
+
+    let instances = aggregation_circuit.instances();
+
+    let proof = gen_evm_proof_shplonk(
+        &params,
+        &aggregation_pk,
+        aggregation_circuit,
+        instances.clone(),
+    );
+
+    let gas_cost = evm_verify(deployment_code, instances, proof);
+
+    print!("gas_cost: {:?}", gas_cost);
+}
@@ -0,0 +1,92 @@
+#![feature(generic_const_exprs)]
+
+use serde_json::to_string_pretty;
+use snark_verifier_sdk::{
+    evm::{evm_verify, gen_evm_proof_shplonk, gen_evm_verifier_shplonk},
+    CircuitExt,
+};
+use std::{fs::File, io::Write, path::Path};
+use summa_solvency::{
+    circuits::{
+        mst_batch_inclusion::MstBatchInclusionCircuit,
+        types::ProofSolidityCallData,
+        utils::{
+            gen_proof_solidity_calldata, generate_setup_artifacts, write_verifier_sol_from_yul,
+        },
+    },
+    merkle_sum_tree::{MerkleSumTree, Tree},
+};
+
+const LEVELS: usize = 4;
+const N_CURRENCIES: usize = 2;
+const N_BYTES: usize = 14;
+const BATCH_SIZE: usize = 4;
+
+fn main() {
+    // In order to generate the verifier we create the circuit using the init_empty() method, which means that the circuit is not initialized with any data.
+    let circuit =
+        MstBatchInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES, BATCH_SIZE>::init_empty();
+
+    // generate a universal trusted setup for testing, along with the verification key (vk) and the proving key (pk).
+    // The batch circuit verifies BATCH_SIZE merkle paths, so it needs a larger k than the single inclusion circuit, and therefore a larger ptau file than `hermez-raw-11`
+    let (params, pk, _) = generate_setup_artifacts(13, None, circuit.clone()).unwrap();
+
+    let num_instances = circuit.num_instance();
+
+    let yul_output_path = "../contracts/src/BatchInclusionVerifier.yul";
+    let sol_output_path = "../contracts/src/BatchInclusionVerifier.sol";
+
+    let deployment_code = gen_evm_verifier_shplonk::<
+        MstBatchInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES, BATCH_SIZE>,
+    >(
+        &params,
+        pk.get_vk(),
+        num_instances,
+        Some(Path::new(yul_output_path)),
+    );
+
+    write_verifier_sol_from_yul(yul_output_path, sol_output_path).unwrap();
+
+    let merkle_sum_tree =
+        MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+    // In order to generate a proof for testing purpose we create the circuit using the init() method
+    // which takes as input the merkle proofs of the users of the batch.
+    let user_indices = [0, 1, 2, 3];
+
+    let merkle_proofs = user_indices
+        .iter()
+        .map(|user_index| merkle_sum_tree.generate_proof(*user_index).unwrap())
+        .collect();
+
+    // Generate the circuit with the actual inputs
+    let circuit =
+        MstBatchInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES, BATCH_SIZE>::init(merkle_proofs)
+            .unwrap();
+
+    let instances = circuit.instances();
+
+    let proof = gen_evm_proof_shplonk(&params, &pk, circuit.clone(), instances.clone());
+
+    let proof_solidity_calldata = gen_proof_solidity_calldata(&params, &pk, circuit.clone());
+
+    let proof_hex_string = format!("0x{}", hex::encode(&proof_solidity_calldata.clone().0 .0));
+
+    let data = ProofSolidityCallData {
+        proof: proof_hex_string,
+        public_inputs: proof_solidity_calldata.1,
+    };
+
+    // Serialize the data to a JSON string
+    let serialized_data = to_string_pretty(&data).expect("Failed to serialize data");
+
+    // Save the serialized data to a JSON file
+    let mut file = File::create("./examples/batch_inclusion_proof_solidity_calldata.json")
+        .expect("Unable to create file");
+    file.write_all(serialized_data.as_bytes())
+        .expect("Unable to write data to file");
+
+    let gas_cost = evm_verify(deployment_code, instances, proof);
+
+    print!("gas_cost: {:?}", gas_cost);
+}
@@ -13,7 +13,6 @@ use nova_scotia::{
 use nova_snark::{provider, CompressedSNARK, PublicParams};
 use num_bigint::BigUint;
 use serde_json::json;
-use summa_solvency::merkle_sum_tree::utils::big_intify_username;
 
 const N_CURRENCIES: usize = 2;
 
@@ -233,72 +232,38 @@ fn main() {
     run_test(circuit_filepath.clone(), witness_gen_filepath);
 }
 
-use num_traits::Num;
 use poseidon_rs::{Fr, Poseidon};
+use summa_solvency::merkle_sum_tree::{
+    fp_to_fr, utils::fp_to_big_uint, BalanceKind, Cryptocurrency, Entry, MerkleSumTree,
+    NovaPoseidonHasher, Tree,
+};
+
+const N_BYTES: usize = 14;
 
-// Note that we cannot reuse the MerkleSumTree implementation from zk_prover because it is not compatible with circom's Poseidon Hasher
 #[derive(Clone, Debug)]
-struct Node<const N_CURRENCIES: usize> {
+struct Node {
     hash: Fr,
-    balance: [Fr; N_CURRENCIES],
 }
 
 #[derive(Clone, Debug)]
-struct MerkleProof<const N_CURRENCIES: usize> {
+struct MerkleProof {
     username: String,
     user_balances: Vec<String>,
     path_element_hashes: Vec<String>,
     path_element_balances: Vec<Vec<String>>,
     path_indices: Vec<String>,
-    root: Node<N_CURRENCIES>,
+    root: Node,
 }
 
-impl<const N_CURRENCIES: usize> Node<N_CURRENCIES> {
-    /// Constructs a new Node given left and right child hashes.
-    fn new(
-        left: &Node<N_CURRENCIES>,
-        right: &Node<N_CURRENCIES>,
-        hasher: &Poseidon,
-    ) -> Node<N_CURRENCIES> {
-        let mut input = vec![left.hash];
-        input.extend(left.balance);
-        input.push(right.hash);
-        input.extend(right.balance);
-
-        let mut balance = vec![];
-
-        // iterate over N_CURRENCIES
-        for i in 0..N_CURRENCIES {
-            let mut sum = Fr::from_str("0").unwrap();
-            sum.add_assign(&left.balance[i]);
-            sum.add_assign(&right.balance[i]);
-
-            balance.push(sum);
-        }
-
-        Node {
-            hash: hasher.hash(input).unwrap(),
-            balance: balance.try_into().unwrap(),
-        }
-    }
-}
-
-/// Generates a Merkle proof of inclusion for a leaf at a given index
-fn build_merkle_proof(
-    csv_filepath: String,
-    user_index: usize,
-) -> Option<MerkleProof<N_CURRENCIES>> {
+/// Generates a Merkle proof of inclusion for a leaf at a given index, in the format of the Nova circuit.
+/// The tree is hashed with `NovaPoseidonHasher`, so that its middle nodes are `H(left.hash, left.balances, right.hash, right.balances)` as in `src/circom/merkle_sum_tree.circom`
+fn build_merkle_proof(csv_filepath: String, user_index: usize) -> Option<MerkleProof> {
     let file = File::open(csv_filepath).expect("Unable to open file");
     let reader = BufReader::new(file);
 
-    let mut leaves = vec![];
+    let mut entries = vec![];
 
-    let hasher = Poseidon::new();
-
-    let mut captured_username = String::new();
-    let mut captured_user_balances = vec![];
-
-    for (idx, line) in reader.lines().skip(1).enumerate() {
+    for line in reader.lines().skip(1) {
         // skipping header
         let line = line.expect("Unable to read line");
         let data: Vec<&str> = line.split(';').collect();
@@ -307,91 +272,68 @@ fn build_merkle_proof(
             continue; // Invalid line format
         }
 
-        let username = big_intify_username(data[0]).to_string();
-
-        // convert balances to Fr
-        let balances: Vec<Fr> = data[1]
+        let balances: Vec<BigUint> = data[1]
             .split(',')
-            .map(|balance_str| Fr::from_str(balance_str).unwrap())
+            .map(|balance_str| balance_str.parse::<BigUint>().unwrap())
             .collect();
 
-        assert_eq!(balances.len(), N_CURRENCIES);
-
-        // capture user's data if the index matches
-        if idx == user_index {
-            captured_username = username.to_string();
-            captured_user_balances = data[1].split(',').map(|s| s.to_string()).collect();
-        }
-
-        let username = Fr::from_str(&username).unwrap();
-
-        // create a vector input that takes the username and the balances
-        let mut input = vec![username];
-        input.extend(balances.clone());
-
-        let hash = hasher.hash(input).unwrap();
-
-        leaves.push(Node {
-            hash,
-            balance: balances.try_into().unwrap(),
-        });
+        entries.push(
+            Entry::<N_CURRENCIES>::new(data[0].to_string(), balances.try_into().unwrap()).unwrap(),
+        );
     }
 
-    let mut current_level = leaves.clone();
+    let cryptocurrencies = (0..N_CURRENCIES)
+        .map(|i| Cryptocurrency {
+            name: format!("CURRENCY_{}", i),
+            chain: "ETH".to_string(),
+            kind: BalanceKind::Liability,
+            decimals: 0,
+        })
+        .collect();
+
+    let merkle_sum_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES, NovaPoseidonHasher>::from_entries(
+        entries,
+        cryptocurrencies,
+        false,
+    )
+    .unwrap();
+
+    let entry = merkle_sum_tree.get_entry(user_index).ok()?;
 
     let mut path_element_hashes = vec![];
     let mut path_element_balances = vec![];
     let mut path_indices = vec![];
 
-    while current_level.len() > 1 {
-        let mut next_level = vec![];
-
-        for i in (0..current_level.len()).step_by(2) {
-            let left = &current_level[i];
-            let right = if i + 1 < current_level.len() {
-                &current_level[i + 1]
-            } else {
-                &current_level[i]
-            };
-
-            if user_index == i {
-                path_element_hashes.push(fr_to_string(right.hash).unwrap());
-                path_element_balances.push(
-                    right
-                        .balance
-                        .iter()
-                        .map(|fr| fr_to_string(*fr).unwrap())
-                        .collect(),
-                );
-                path_indices.push("0".to_string()); // 0 means that the right element is the path element
-            } else if user_index == i + 1 {
-                path_element_hashes.push(fr_to_string(left.hash).unwrap());
-                path_element_balances.push(
-                    left.balance
-                        .iter()
-                        .map(|fr| fr_to_string(*fr).unwrap())
-                        .collect(), // 1 means that the left element is the path element
-                );
-                path_indices.push("1".to_string());
-            }
-
-            let parent = Node::new(left, right, &hasher);
-            next_level.push(parent);
-        }
-
-        current_level = next_level;
+    // The path elements are the sibling nodes themselves, from the leaves to the root
+    let mut index = user_index;
+    for level in 0..*merkle_sum_tree.depth() {
+        let sibling = merkle_sum_tree.get_node(level, index ^ 1).ok()?;
+        path_element_hashes.push(fp_to_big_uint(sibling.hash).to_string());
+        path_element_balances.push(
+            sibling
+                .balances
+                .iter()
+                .map(|balance| fp_to_big_uint(*balance).to_string())
+                .collect(),
+        );
+        // 0 means that the right element is the path element, 1 that the left element is
+        path_indices.push((index % 2).to_string());
+        index /= 2;
     }
 
-    // fetch the root
-    let root = current_level[0].clone();
-
     Some(MerkleProof {
-        username: captured_username,
-        user_balances: captured_user_balances,
+        username: entry.username_as_big_uint().to_string(),
+        user_balances: entry
+            .balances()
+            .iter()
+            .map(|balance| balance.to_string())
+            .collect(),
         path_element_hashes,
         path_element_balances,
         path_indices,
-        root,
+        root: Node {
+            hash: fp_to_fr(merkle_sum_tree.root().hash),
+        },
     })
 }
 
@@ -425,25 +367,3 @@ fn build_user_state_cur(user_state_prev: Fr, username: String, user_balances: Ve
 
     hasher.hash(input).unwrap()
 }
-
-/// Converts a Fr to a its decimal string representation
-fn fr_to_string(v: Fr) -> Option<String> {
-    // Convert v to string
-    let s = v.to_string();
-
-    // Extract the hexadecimal portion from the string.
-    // The pattern "Fr(" and ")" should surround the hexadecimal.
-    if let Some(hex_start) = s.find("Fr(0x") {
-        let hex_end = s.rfind(')')?;
-        let hex_str = &s[hex_start + 5..hex_end];
-
-        // Convert the hexadecimal to a decimal string.
-        let decimal_str = BigUint::from_str_radix(hex_str, 16)
-            .ok()
-            .map(|bigint| bigint.to_str_radix(10))
-            .unwrap();
-
-        return Some(decimal_str);
-    }
-    None
-}
@@ -0,0 +1,30 @@
+use std::process::exit;
+use summa_solvency::merkle_sum_tree::utils::{validate_csv, CurrencyManifest};
+
+const N_BYTES: usize = 14;
+
+/// Prints the validation report of a liabilities CSV file as JSON, and exits with a non-zero code if no tree can be built out of the file.
+///
+/// Usage: `cargo run --release --example validate_csv -- <csv_path> [manifest_path]`
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let csv_path = args
+        .next()
+        .unwrap_or_else(|| "../csv/entry_16.csv".to_string());
+    let manifest = match args.next() {
+        Some(manifest_path) => {
+            CurrencyManifest::from_json_file(manifest_path).expect("Unable to read the manifest")
+        }
+        None => CurrencyManifest::default(),
+    };
+
+    let report = validate_csv::<_, N_BYTES>(&csv_path, &manifest).expect("Unable to read the file");
+    println!(
+        "{}",
+        report.to_json().expect("Failed to serialize the report")
+    );
+
+    if !report.is_valid() {
+        exit(1);
+    }
+}
@@ -1,4 +1,4 @@
-use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::circuit::{AssignedCell, Layouter, Region, Value};
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
 use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector};
 use halo2_proofs::poly::Rotation;
@@ -24,9 +24,11 @@ pub struct MerkleSumTreeConfig {
 /// * `s * (element_l_cur - element_r_cur) * swap_bit + element_r_cur - element_r_next = 0` (if `bool_and_swap_selector` is toggled).
 /// These 2 constraints enforce that if the swap_bit is equal to 1, the values will be swapped on the next row. If the swap_bit is equal to 0, the values will not be swapped on the next row.
 /// * `s * (left_balance + right_balance - computed_sum)`. It constraints the computed sum to be equal to the sum of the left and right balances (if `sum_selector` is toggled).
+///
+/// The chip of a 4-ary tree, `MerkleSumTreeChip<N_CURRENCIES, 4>`, places a node among its 3 siblings according to 2 swap bits instead, and sums the balances of 4 nodes.
 
 #[derive(Debug, Clone)]
-pub struct MerkleSumTreeChip<const N_CURRENCIES: usize> {
+pub struct MerkleSumTreeChip<const N_CURRENCIES: usize, const ARITY: usize = 2> {
     config: MerkleSumTreeConfig,
 }
 
@@ -272,3 +274,219 @@ impl<const N_CURRENCIES: usize> MerkleSumTreeChip<N_CURRENCIES> {
         )
     }
 }
+
+impl<const N_CURRENCIES: usize> MerkleSumTreeChip<N_CURRENCIES, 4> {
+    pub fn construct(config: MerkleSumTreeConfig) -> Self {
+        Self { config }
+    }
+
+    /// Configures the chip of a 4-ary tree, with the following constraints:
+    ///
+    /// * `s * swap_bit_0 * (1 - swap_bit_0) = 0` and `s * swap_bit_1 * (1 - swap_bit_1) = 0` (if `bool_and_swap_selector` is toggled). They enforce that both swap bits are either 0 or 1.
+    /// * `s * (e_i * current + sum_j(c_ij * sibling_j) - element_i) = 0` for each of the 4 positions `i` (if `bool_and_swap_selector` is toggled), where `e_i` is 1 if the position `swap_bit_0 + 2 * swap_bit_1` is `i` and 0 otherwise.
+    /// These 4 constraints enforce that the current element is placed at the position given by the swap bits, and that the siblings fill the other positions in order.
+    /// * `s * (element_0 + element_1 + element_2 + element_3 - computed_sum) = 0`. It constraints the computed sum to be equal to the sum of the 4 balances (if `sum_selector` is toggled).
+    pub fn configure(
+        meta: &mut ConstraintSystem<Fp>,
+        advice: [Column<Advice>; 3],
+        selectors: [Selector; 2],
+    ) -> MerkleSumTreeConfig {
+        let col_a: Column<Advice> = advice[0];
+        let col_b: Column<Advice> = advice[1];
+        let col_c: Column<Advice> = advice[2];
+
+        let bool_and_swap_selector = selectors[0];
+        let sum_selector = selectors[1];
+
+        meta.create_gate("quaternary bool constraint", |meta| {
+            let s = meta.query_selector(bool_and_swap_selector);
+            let swap_bit_0 = meta.query_advice(col_c, Rotation::cur());
+            let swap_bit_1 = meta.query_advice(col_c, Rotation::next());
+            vec![
+                s.clone() * swap_bit_0.clone() * (Expression::Constant(Fp::one()) - swap_bit_0),
+                s * swap_bit_1.clone() * (Expression::Constant(Fp::one()) - swap_bit_1),
+            ]
+        });
+
+        meta.create_gate("quaternary swap constraint", |meta| {
+            let s = meta.query_selector(bool_and_swap_selector);
+            let one = Expression::Constant(Fp::one());
+
+            let current = meta.query_advice(col_a, Rotation::cur());
+            let sibling_0 = meta.query_advice(col_b, Rotation::cur());
+            let sibling_1 = meta.query_advice(col_a, Rotation::next());
+            let sibling_2 = meta.query_advice(col_b, Rotation::next());
+            let swap_bit_0 = meta.query_advice(col_c, Rotation::cur());
+            let swap_bit_1 = meta.query_advice(col_c, Rotation::next());
+            let element_0 = meta.query_advice(col_a, Rotation(2));
+            let element_1 = meta.query_advice(col_b, Rotation(2));
+            let element_2 = meta.query_advice(col_c, Rotation(2));
+            let element_3 = meta.query_advice(col_a, Rotation(3));
+
+            // is_position_i is 1 if the current element is at position i, and 0 otherwise
+            let is_position_0 =
+                (one.clone() - swap_bit_0.clone()) * (one.clone() - swap_bit_1.clone());
+            let is_position_1 = swap_bit_0.clone() * (one.clone() - swap_bit_1.clone());
+            let is_position_2 = (one.clone() - swap_bit_0.clone()) * swap_bit_1.clone();
+            let is_position_3 = swap_bit_0 * swap_bit_1;
+
+            // The siblings preceding the current element keep their position, the following ones are shifted by one position
+            let expected_0 = is_position_0.clone() * current.clone()
+                + (one.clone() - is_position_0.clone()) * sibling_0.clone();
+            let expected_1 = is_position_1.clone() * current.clone()
+                + is_position_0.clone() * sibling_0
+                + (is_position_2.clone() + is_position_3.clone()) * sibling_1.clone();
+            let expected_2 = is_position_2 * current.clone()
+                + (is_position_0 + is_position_1) * sibling_1
+                + is_position_3.clone() * sibling_2.clone();
+            let expected_3 = is_position_3.clone() * current + (one - is_position_3) * sibling_2;
+
+            vec![
+                s.clone() * (expected_0 - element_0),
+                s.clone() * (expected_1 - element_1),
+                s.clone() * (expected_2 - element_2),
+                s * (expected_3 - element_3),
+            ]
+        });
+
+        meta.create_gate("quaternary sum constraint", |meta| {
+            let s = meta.query_selector(sum_selector);
+            let element_0 = meta.query_advice(col_a, Rotation::cur());
+            let element_1 = meta.query_advice(col_b, Rotation::cur());
+            let element_2 = meta.query_advice(col_c, Rotation::cur());
+            let element_3 = meta.query_advice(col_a, Rotation::next());
+            let computed_sum = meta.query_advice(col_b, Rotation::next());
+            vec![s * (element_0 + element_1 + element_2 + element_3 - computed_sum)]
+        });
+
+        MerkleSumTreeConfig {
+            advice,
+            bool_and_swap_selector,
+            sum_selector,
+        }
+    }
+
+    /// Places the current value among the values of its 3 siblings in a region following this layout on 3 advice columns:
+    ///
+    /// | a           | b           | c            |
+    /// | ----------- | ----------- | ------------ |
+    /// | `current`   | `sibling_0` | `swap_bit_0` |
+    /// | `sibling_1` | `sibling_2` | `swap_bit_1` |
+    /// | `element_0` | `element_1` | `element_2`  |
+    /// | `element_3` | -           | -            |
+    ///
+    /// At row 0 bool_and_swap_selector is enabled
+    /// The current value is at position `swap_bit_0 + 2 * swap_bit_1` of the elements, and the siblings fill the other positions in order
+    fn place_per_level(
+        &self,
+        region: &mut Region<'_, Fp>,
+        current: &AssignedCell<Fp, Fp>,
+        siblings: &[AssignedCell<Fp, Fp>; 3],
+        swap_bits: &[AssignedCell<Fp, Fp>; 2],
+    ) -> Result<[AssignedCell<Fp, Fp>; 4], Error> {
+        // enable the bool_and_swap_selector at row 0
+        self.config.bool_and_swap_selector.enable(region, 0)?;
+
+        let current =
+            current.copy_advice(|| "copy current value", region, self.config.advice[0], 0)?;
+        let sibling_cells = [
+            siblings[0].copy_advice(|| "copy sibling 0", region, self.config.advice[1], 0)?,
+            siblings[1].copy_advice(|| "copy sibling 1", region, self.config.advice[0], 1)?,
+            siblings[2].copy_advice(|| "copy sibling 2", region, self.config.advice[1], 1)?,
+        ];
+        let swap_bit_0 =
+            swap_bits[0].copy_advice(|| "swap bit 0", region, self.config.advice[2], 0)?;
+        let swap_bit_1 =
+            swap_bits[1].copy_advice(|| "swap bit 1", region, self.config.advice[2], 1)?;
+
+        // The position of the current value, if the swap bits are known
+        let position = swap_bit_0
+            .value()
+            .zip(swap_bit_1.value())
+            .map(|(bit_0, bit_1)| {
+                usize::from(*bit_0 == Fp::one()) + 2 * usize::from(*bit_1 == Fp::one())
+            });
+
+        let elements = position
+            .zip(current.value().copied())
+            .zip(sibling_cells[0].value().copied())
+            .zip(sibling_cells[1].value().copied())
+            .zip(sibling_cells[2].value().copied())
+            .map(
+                |((((position, current), sibling_0), sibling_1), sibling_2)| {
+                    let mut elements = vec![sibling_0, sibling_1, sibling_2];
+                    elements.insert(position, current);
+                    elements
+                },
+            );
+
+        // The elements are assigned on rows 2 and 3, from left to right
+        let mut element_cells = Vec::with_capacity(4);
+        for (i, (column, offset)) in [(0, 2), (1, 2), (2, 2), (0, 3)].into_iter().enumerate() {
+            element_cells.push(region.assign_advice(
+                || format!("assign element {}", i),
+                self.config.advice[column],
+                offset,
+                || elements.as_ref().map(|elements| elements[i]),
+            )?);
+        }
+
+        Ok(element_cells.try_into().unwrap())
+    }
+
+    /// Places the hash of the current node among the hashes of its 3 siblings, following the layout of `place_per_level`
+    pub fn swap_hashes_per_level(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        current_hash: &AssignedCell<Fp, Fp>,
+        sibling_hashes: &[AssignedCell<Fp, Fp>; 3],
+        swap_bits: &[AssignedCell<Fp, Fp>; 2],
+    ) -> Result<[AssignedCell<Fp, Fp>; 4], Error> {
+        layouter.assign_region(
+            || "assign nodes hashes per merkle tree level",
+            |mut region| self.place_per_level(&mut region, current_hash, sibling_hashes, swap_bits),
+        )
+    }
+
+    /// Places the balance of the current node for a single currency among the balances of its 3 siblings, following the layout of `place_per_level`, and sums them:
+    ///
+    /// | a           | b           | c           |
+    /// | ----------- | ----------- | ----------- |
+    /// | ...         | ...         | ...         |
+    /// | `element_0` | `element_1` | `element_2` |
+    /// | `element_3` | `sum`       | -           |
+    ///
+    /// At row 2 sum_selector is enabled
+    pub fn swap_balances_per_level(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        current_balance: &AssignedCell<Fp, Fp>,
+        sibling_balances: &[AssignedCell<Fp, Fp>; 3],
+        swap_bits: &[AssignedCell<Fp, Fp>; 2],
+    ) -> Result<([AssignedCell<Fp, Fp>; 4], AssignedCell<Fp, Fp>), Error> {
+        layouter.assign_region(
+            || "assign nodes balances per currency",
+            |mut region| {
+                let elements = self.place_per_level(
+                    &mut region,
+                    current_balance,
+                    sibling_balances,
+                    swap_bits,
+                )?;
+
+                // enable the sum_selector at offset 2
+                self.config.sum_selector.enable(&mut region, 2)?;
+
+                let sum = elements
+                    .iter()
+                    .fold(Value::known(Fp::zero()), |sum, element| {
+                        sum.zip(element.value()).map(|(sum, element)| sum + element)
+                    });
+                let sum_cell =
+                    region.assign_advice(|| "sum of balances", self.config.advice[1], 3, || sum)?;
+
+                Ok((elements, sum_cell))
+            },
+        )
+    }
+}
@@ -0,0 +1,103 @@
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Selector};
+use halo2_proofs::poly::Rotation;
+
+use super::range_check::{RangeCheckChip, RangeCheckConfig};
+
+/// Configuration for the Less Than Or Equal Chip
+///
+/// # Type Parameters
+///
+/// * `LIMB_BITS`: Number of bits of the limbs of the range check chip
+///
+/// # Fields
+///
+/// * `advice`: Advice columns for the left hand side, the right hand side and their difference.
+/// * `diff_selector`: Selector to enable the difference constraint.
+/// * `range_check_config`: Configuration of the range check chip used to range check the difference.
+#[derive(Debug, Copy, Clone)]
+pub struct LessThanOrEqualConfig<const LIMB_BITS: usize = 8> {
+    advice: [Column<Advice>; 3],
+    diff_selector: Selector,
+    range_check_config: RangeCheckConfig<LIMB_BITS>,
+}
+
+/// Chip that verifies that the value witnessed in a cell `lhs` is less than or equal to the value witnessed in a cell `rhs`.
+///
+/// The difference `diff = rhs - lhs` is witnessed following this layout on 3 advice columns:
+///
+/// | a     | b     | c      |
+/// | ----- | ----- | ------ |
+/// | `lhs` | `rhs` | `diff` |
+///
+/// The constraints that are enforced are:
+/// - `s * (rhs - lhs - diff) = 0` (if `diff_selector` is toggled)
+/// - `diff` ∈ [0, 2^n_bits), enforced by the [`RangeCheckChip`] configured to check values of `n_bits` bits
+///
+/// If `lhs > rhs`, the difference wraps around the field modulus and fails the range check.
+/// Note: `lhs` should be range checked to `n_bits` by the caller. Otherwise, a large enough `lhs` may wrap around the field modulus and pass the check.
+#[derive(Debug, Clone)]
+pub struct LessThanOrEqualChip<const LIMB_BITS: usize = 8> {
+    config: LessThanOrEqualConfig<LIMB_BITS>,
+}
+
+impl<const LIMB_BITS: usize> LessThanOrEqualChip<LIMB_BITS> {
+    pub fn construct(config: LessThanOrEqualConfig<LIMB_BITS>) -> Self {
+        Self { config }
+    }
+
+    /// Configures the Less Than Or Equal Chip
+    /// Note: the lookup table of the range check chip should be loaded with values from `0` to `2^LIMB_BITS - 1`, as done by [`RangeCheckChip::load`](crate::chips::range::range_check::RangeCheckChip::load), otherwise the check will fail.
+    pub fn configure(
+        meta: &mut ConstraintSystem<Fp>,
+        advice: [Column<Advice>; 3],
+        diff_selector: Selector,
+        range_check_config: RangeCheckConfig<LIMB_BITS>,
+    ) -> LessThanOrEqualConfig<LIMB_BITS> {
+        meta.create_gate("difference constraint", |meta| {
+            let s = meta.query_selector(diff_selector);
+            let lhs = meta.query_advice(advice[0], Rotation::cur());
+            let rhs = meta.query_advice(advice[1], Rotation::cur());
+            let diff = meta.query_advice(advice[2], Rotation::cur());
+            vec![s * (rhs - lhs - diff)]
+        });
+
+        LessThanOrEqualConfig {
+            advice,
+            diff_selector,
+            range_check_config,
+        }
+    }
+
+    /// Constrains the value within `lhs` to be less than or equal to the value within `rhs`.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        lhs: &AssignedCell<Fp, Fp>,
+        rhs: &AssignedCell<Fp, Fp>,
+    ) -> Result<(), Error> {
+        let diff = layouter.assign_region(
+            || "assign difference to perform less than or equal check",
+            |mut region| {
+                // enable the diff_selector at offset 0
+                self.config.diff_selector.enable(&mut region, 0)?;
+
+                let lhs = lhs.copy_advice(|| "copy lhs", &mut region, self.config.advice[0], 0)?;
+
+                let rhs = rhs.copy_advice(|| "copy rhs", &mut region, self.config.advice[1], 0)?;
+
+                region.assign_advice(
+                    || "rhs - lhs",
+                    self.config.advice[2],
+                    0,
+                    || rhs.value().copied() - lhs.value(),
+                )
+            },
+        )?;
+
+        let range_check_chip = RangeCheckChip::construct(self.config.range_check_config);
+
+        range_check_chip.assign(layouter.namespace(|| "range check difference"), &diff)
+    }
+}
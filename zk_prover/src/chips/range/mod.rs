@@ -1,3 +1,5 @@
+pub mod less_than_or_equal;
 pub mod range_check;
+#[cfg(feature = "prover")]
 mod tests;
 pub mod utils;
@@ -6,28 +6,32 @@ use halo2_proofs::poly::Rotation;
 
 use std::fmt::Debug;
 
-use super::utils::decompose_fp_to_bytes;
+use super::utils::{decompose_fp_to_limbs, pow_of_two};
 
 /// Configuration for the Range Check Chip
 ///
 /// # Type Parameters
 ///
-/// * `N_BYTES`: Number of bytes in which the value to be checked should lie
+/// * `LIMB_BITS`: Number of bits of each limb in which the value to be checked is decomposed. The lookup table contains the values from `0` to `2^LIMB_BITS - 1`.
 ///
 /// # Fields
 ///
 /// * `z`: Advice column for the value to be checked and its running sum.
 /// * `lookup_enable_selector`: Selector to enable the lookup check.
+/// * `top_limb_selector`: Selector to enable the lookup check of the most significant limb, if `n_bits` is not a multiple of `LIMB_BITS`.
+/// * `n_bits`: Number of bits in which the value to be checked should lie.
 ///
 /// Patterned after [halo2_gadgets](https://github.com/privacy-scaling-explorations/halo2/blob/main/halo2_gadgets/src/utilities/decompose_running_sum.rs)
 #[derive(Debug, Copy, Clone)]
-pub struct RangeCheckConfig<const N_BYTES: usize> {
+pub struct RangeCheckConfig<const LIMB_BITS: usize = 8> {
     z: Column<Advice>,
     lookup_enable_selector: Selector,
+    top_limb_selector: Option<Selector>,
+    n_bits: usize,
 }
 
-/// Helper chip that verifies that the value witnessed in a given cell lies within a given range defined by N_BYTES.
-/// For example, Let's say we want to constraint 0x1f2f3f4f to be within the range N_BYTES=4.
+/// Helper chip that verifies that the value witnessed in a given cell lies within a given range of `n_bits` bits.
+/// For example, Let's say we want to constraint 0x1f2f3f4f to be within the range of 32 bits, with limbs of LIMB_BITS=8 bits.
 ///
 /// `z(0) = 0x1f2f3f4f`
 /// `z(1) = (0x1f2f3f4f - 0x4f) / 2^8 = 0x1f2f3f`
@@ -44,65 +48,133 @@ pub struct RangeCheckConfig<const N_BYTES: usize> {
 ///  | 4   | 0x00       |
 ///
 /// The column z contains the witnessed value to be checked at offset 0
-/// At offset i, the column z contains the value z(i+1) = (z(i) - k(i)) / 2^8 (shift right by 8 bits) where k(i) is the i-th decomposition big-endian of `value`
-/// The constraints that are enforced are:
-/// - z(i) - 2^8⋅z(i+1) ∈ lookup_u8_table (enabled by lookup_enable_selector at offset [0, N_BYTES - 1])
-/// - z(N_BYTES) == 0
+/// At offset i, the column z contains the value z(i+1) = (z(i) - k(i)) / 2^LIMB_BITS (shift right by LIMB_BITS bits) where k(i) is the i-th decomposition big-endian of `value`
+/// The value is decomposed in `N_LIMBS = ceil(n_bits / LIMB_BITS)` limbs. The constraints that are enforced are:
+/// - z(i) - 2^LIMB_BITS⋅z(i+1) ∈ lookup_table (enabled by lookup_enable_selector at offset [0, N_LIMBS - 1])
+/// - z(N_LIMBS) == 0
+/// - If `n_bits` is not a multiple of `LIMB_BITS`, the most significant limb `z(N_LIMBS - 1)` is shorter than LIMB_BITS, namely `r = n_bits % LIMB_BITS` bits long.
+///   This is enforced by z(N_LIMBS - 1)⋅2^(LIMB_BITS - r) ∈ lookup_table (enabled by top_limb_selector at offset N_LIMBS - 1)
+///
+/// Larger limbs take fewer rows per range check, but require a lookup table of `2^LIMB_BITS` rows, namely a circuit of at least `2^LIMB_BITS` rows.
 #[derive(Debug, Clone)]
-pub struct RangeCheckChip<const N_BYTES: usize> {
-    config: RangeCheckConfig<N_BYTES>,
+pub struct RangeCheckChip<const LIMB_BITS: usize = 8> {
+    config: RangeCheckConfig<LIMB_BITS>,
 }
 
-impl<const N_BYTES: usize> RangeCheckChip<N_BYTES> {
-    pub fn construct(config: RangeCheckConfig<N_BYTES>) -> Self {
+impl<const LIMB_BITS: usize> RangeCheckChip<LIMB_BITS> {
+    pub fn construct(config: RangeCheckConfig<LIMB_BITS>) -> Self {
         Self { config }
     }
 
-    /// Configures the Range Chip
-    /// Note: the lookup table should be loaded with values from `0` to `2^8 - 1` otherwise the range check will fail.
+    /// Configures the Range Chip to check that values lie in the range [0, 2^n_bits - 1]
+    /// Note: the lookup table should be loaded with values from `0` to `2^LIMB_BITS - 1`, as done by [`RangeCheckChip::load`], otherwise the range check will fail.
     pub fn configure(
         meta: &mut ConstraintSystem<Fp>,
         z: Column<Advice>,
-        lookup_u8_table: Column<Fixed>,
+        lookup_table: Column<Fixed>,
         lookup_enable_selector: Selector,
-    ) -> RangeCheckConfig<N_BYTES> {
-        meta.annotate_lookup_any_column(lookup_u8_table, || "LOOKUP_MAXBITS_RANGE");
+        n_bits: usize,
+    ) -> RangeCheckConfig<LIMB_BITS> {
+        assert!(n_bits > 0, "The range should be at least 1 bit");
+
+        meta.annotate_lookup_any_column(lookup_table, || "LOOKUP_MAXBITS_RANGE");
 
         meta.lookup_any(
-            "range u8 check for difference between each interstitial running sum output",
+            "range check for difference between each interstitial running sum output",
             |meta| {
                 let z_cur = meta.query_advice(z, Rotation::cur());
                 let z_next = meta.query_advice(z, Rotation::next());
 
                 let lookup_enable_selector = meta.query_selector(lookup_enable_selector);
-                let u8_range = meta.query_fixed(lookup_u8_table, Rotation::cur());
+                let limb_range = meta.query_fixed(lookup_table, Rotation::cur());
 
-                let diff = z_cur - z_next * Expression::Constant(Fp::from(1 << 8));
+                let diff = z_cur - z_next * Expression::Constant(pow_of_two(LIMB_BITS));
 
-                vec![(lookup_enable_selector * diff, u8_range)]
+                vec![(lookup_enable_selector * diff, limb_range)]
             },
         );
 
+        // The most significant limb needs an additional check if it is shorter than LIMB_BITS
+        let top_limb_bits = n_bits % LIMB_BITS;
+
+        let top_limb_selector = if top_limb_bits == 0 {
+            None
+        } else {
+            let top_limb_selector = meta.complex_selector();
+
+            meta.lookup_any("range check for the most significant limb", |meta| {
+                let z_cur = meta.query_advice(z, Rotation::cur());
+
+                let top_limb_selector = meta.query_selector(top_limb_selector);
+                let limb_range = meta.query_fixed(lookup_table, Rotation::cur());
+
+                // The limb lies in [0, 2^top_limb_bits - 1] if it still lies in the lookup table after being shifted left by LIMB_BITS - top_limb_bits bits
+                let shifted_limb =
+                    z_cur * Expression::Constant(pow_of_two(LIMB_BITS - top_limb_bits));
+
+                vec![(top_limb_selector * shifted_limb, limb_range)]
+            });
+
+            Some(top_limb_selector)
+        };
+
         RangeCheckConfig {
             z,
             lookup_enable_selector,
+            top_limb_selector,
+            n_bits,
         }
     }
 
+    /// Loads the lookup table with values from `0` to `2^LIMB_BITS - 1`
+    pub fn load(
+        layouter: &mut impl Layouter<Fp>,
+        lookup_table: Column<Fixed>,
+    ) -> Result<(), Error> {
+        let range = 1 << LIMB_BITS;
+
+        layouter.assign_region(
+            || format!("load range check table of {} bits", LIMB_BITS),
+            |mut region| {
+                for i in 0..range {
+                    region.assign_fixed(
+                        || "assign cell in fixed column",
+                        lookup_table,
+                        i,
+                        || Value::known(Fp::from(i as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Returns the number of limbs in which the values are decomposed, namely the number of rows of a range check
+    pub fn n_limbs(&self) -> usize {
+        (self.config.n_bits + LIMB_BITS - 1) / LIMB_BITS
+    }
+
     /// Assign the running sum to the chip starting from the value within an assigned cell.
     pub fn assign(
         &self,
         mut layouter: impl Layouter<Fp>,
         value: &AssignedCell<Fp, Fp>,
     ) -> Result<(), Error> {
+        let n_limbs = self.n_limbs();
+
         layouter.assign_region(
             || "assign value to perform range check",
             |mut region| {
-                // enable the lookup at offset [0, N_BYTES - 1]
-                for i in 0..N_BYTES {
+                // enable the lookup at offset [0, n_limbs - 1]
+                for i in 0..n_limbs {
                     self.config.lookup_enable_selector.enable(&mut region, i)?;
                 }
 
+                // enable the lookup of the most significant limb, if shorter than LIMB_BITS
+                if let Some(top_limb_selector) = self.config.top_limb_selector {
+                    top_limb_selector.enable(&mut region, n_limbs - 1)?;
+                }
+
                 // copy `value` to `z_0` at offset 0
                 let z_0 = value.copy_advice(
                     || "assign value to be range checked",
@@ -111,26 +183,26 @@ impl<const N_BYTES: usize> RangeCheckChip<N_BYTES> {
                     0,
                 )?;
 
-                // Decompose the value in #N_BYTES bytes
-                let bytes = value
+                // Decompose the value in #n_limbs limbs
+                let limbs = value
                     .value()
                     .copied()
-                    .map(|x| decompose_fp_to_bytes(x, N_BYTES))
-                    .transpose_vec(N_BYTES);
+                    .map(|x| decompose_fp_to_limbs(x, LIMB_BITS, n_limbs))
+                    .transpose_vec(n_limbs);
 
                 // Initialize empty vector to store running sum values [z_0, ..., z_W].
                 let mut zs: Vec<AssignedCell<Fp, Fp>> = vec![z_0.clone()];
                 let mut z = z_0;
 
-                // Assign running sum `z_{i+1}` = (z_i - k_i) / (2^8) for i = 0..=N_BYTES - 1.
-                let two_pow_k_inv = Value::known(Fp::from(1 << 8).invert().unwrap());
+                // Assign running sum `z_{i+1}` = (z_i - k_i) / (2^LIMB_BITS) for i = 0..=n_limbs - 1.
+                let two_pow_k_inv = Value::known(pow_of_two(LIMB_BITS).invert().unwrap());
 
-                for (i, byte) in bytes.iter().enumerate() {
-                    // z_next = (z_cur - byte) / (2^K)
+                for (i, limb) in limbs.iter().enumerate() {
+                    // z_next = (z_cur - limb) / (2^K)
                     let z_next = {
                         let z_cur_val = z.value().copied();
-                        let byte = byte.map(|byte| Fp::from(byte as u64));
-                        let z_next_val = (z_cur_val - byte) * two_pow_k_inv;
+                        let limb = limb.map(Fp::from);
+                        let z_next_val = (z_cur_val - limb) * two_pow_k_inv;
                         region.assign_advice(
                             || format!("z_{:?}", i + 1),
                             self.config.z,
@@ -145,7 +217,7 @@ impl<const N_BYTES: usize> RangeCheckChip<N_BYTES> {
                 }
 
                 // Constrain the final running sum output to be zero.
-                region.constrain_constant(zs[N_BYTES].cell(), Fp::from(0))?;
+                region.constrain_constant(zs[n_limbs].cell(), Fp::from(0))?;
 
                 Ok(())
             },
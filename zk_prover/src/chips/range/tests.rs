@@ -90,7 +90,7 @@ impl AddChip {
 #[derive(Debug, Clone)]
 pub struct TestConfig<const N_BYTES: usize> {
     pub addchip_config: AddConfig,
-    pub range_check_config: RangeCheckConfig<N_BYTES>,
+    pub range_check_config: RangeCheckConfig,
     pub lookup_u8_table: Column<Fixed>,
 }
 
@@ -133,8 +133,13 @@ impl<const N_BYTES: usize> Circuit<Fp> for TestCircuit<N_BYTES> {
         let add_selector = meta.selector();
         let lookup_enable_selector = meta.complex_selector();
 
-        let range_check_config =
-            RangeCheckChip::<N_BYTES>::configure(meta, z, lookup_u8_table, lookup_enable_selector);
+        let range_check_config = RangeCheckChip::configure(
+            meta,
+            z,
+            lookup_u8_table,
+            lookup_enable_selector,
+            8 * N_BYTES,
+        );
 
         let addchip_config = AddChip::configure(meta, a, b, c, add_selector);
 
@@ -158,7 +163,7 @@ impl<const N_BYTES: usize> Circuit<Fp> for TestCircuit<N_BYTES> {
             addchip.assign(self.a, self.b, layouter.namespace(|| "add chip"))?;
 
         // Load the lookup table
-        self.load(&mut layouter, config.lookup_u8_table)?;
+        self.load::<8>(&mut layouter, config.lookup_u8_table)?;
 
         // Initiate the range check chip
         let range_chip = RangeCheckChip::construct(config.range_check_config);
@@ -181,9 +186,89 @@ impl<const N_BYTES: usize> Circuit<Fp> for TestCircuit<N_BYTES> {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct LimbsTestConfig<const LIMB_BITS: usize> {
+    pub value: Column<Advice>,
+    pub range_check_config: RangeCheckConfig<LIMB_BITS>,
+    pub lookup_table: Column<Fixed>,
+}
+
+// The test circuit takes a single input value.
+// Performs a range check on the value, that should lie in N_BITS, with limbs of LIMB_BITS.
+#[derive(Default, Clone, Debug)]
+struct LimbsTestCircuit<const N_BITS: usize, const LIMB_BITS: usize> {
+    pub value: Fp,
+}
+
+impl<const N_BITS: usize, const LIMB_BITS: usize> CircuitBase
+    for LimbsTestCircuit<N_BITS, LIMB_BITS>
+{
+}
+
+impl<const N_BITS: usize, const LIMB_BITS: usize> Circuit<Fp>
+    for LimbsTestCircuit<N_BITS, LIMB_BITS>
+{
+    type Config = LimbsTestConfig<LIMB_BITS>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let z = meta.advice_column();
+        let value = meta.advice_column();
+        let lookup_table = meta.fixed_column();
+
+        meta.enable_equality(z);
+        meta.enable_equality(value);
+
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+
+        let lookup_enable_selector = meta.complex_selector();
+
+        let range_check_config = RangeCheckChip::<LIMB_BITS>::configure(
+            meta,
+            z,
+            lookup_table,
+            lookup_enable_selector,
+            N_BITS,
+        );
+
+        LimbsTestConfig {
+            value,
+            range_check_config,
+            lookup_table,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let value = self.assign_value_to_witness(
+            layouter.namespace(|| "assign value"),
+            self.value,
+            "value",
+            config.value,
+        )?;
+
+        // Load the lookup table of LIMB_BITS bits
+        self.load::<LIMB_BITS>(&mut layouter, config.lookup_table)?;
+
+        let range_chip = RangeCheckChip::construct(config.range_check_config);
+
+        range_chip.assign(layouter.namespace(|| "checking value is in range"), &value)?;
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod testing {
-    use super::TestCircuit;
+    use super::{LimbsTestCircuit, TestCircuit};
     use halo2_proofs::{
         dev::{FailureLocation, MockProver, VerifyFailure},
         halo2curves::bn256::Fr as Fp,
@@ -272,6 +357,63 @@ mod testing {
         );
     }
 
+    // The range of 20 bits is not a multiple of the 12 bits limbs, so the most significant limb is 8 bits long.
+    // 0xfffff lies in 20 bits, while 0x100000 and 0xffffff don't, even though they fit in 2 limbs of 12 bits.
+    #[test]
+    fn test_range_check_12_bits_limbs() {
+        let k = 13;
+
+        let circuit = LimbsTestCircuit::<20, 12> {
+            value: Fp::from(0xfffff),
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+
+        for value in [0x100000, 0xffffff] {
+            let circuit = LimbsTestCircuit::<20, 12> {
+                value: Fp::from(value),
+            };
+            let invalid_prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert!(invalid_prover.verify().is_err());
+        }
+    }
+
+    // 2 limbs of 16 bits check a range of 32 bits.
+    #[test]
+    fn test_range_check_16_bits_limbs() {
+        let k = 17;
+
+        let circuit = LimbsTestCircuit::<32, 16> {
+            value: Fp::from(0xffffffff),
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+
+        let circuit = LimbsTestCircuit::<32, 16> {
+            value: Fp::from(0x100000000),
+        };
+        let invalid_prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert!(invalid_prover.verify().is_err());
+    }
+
+    // A range of 12 bits with limbs of 8 bits, where the most significant limb is 4 bits long.
+    #[test]
+    fn test_range_check_8_bits_limbs_partial_range() {
+        let k = 9;
+
+        let circuit = LimbsTestCircuit::<12, 8> {
+            value: Fp::from(0xfff),
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+
+        let circuit = LimbsTestCircuit::<12, 8> {
+            value: Fp::from(0x1000),
+        };
+        let invalid_prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert!(invalid_prover.verify().is_err());
+    }
+
     #[cfg(feature = "dev-graph")]
     #[test]
     fn print_range_check_test() {
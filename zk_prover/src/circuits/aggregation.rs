@@ -0,0 +1,416 @@
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::halo2curves::bn256::{Bn256, Fq, Fr as Fp, G1Affine};
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error, ProvingKey};
+use halo2_proofs::poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG};
+use itertools::Itertools;
+use rand::rngs::OsRng;
+use snark_verifier::loader::halo2::halo2_wrong_ecc::{
+    self,
+    integer::rns::Rns,
+    maingate::{
+        MainGate, MainGateConfig, MainGateInstructions, RangeChip, RangeConfig, RangeInstructions,
+        RegionCtx,
+    },
+    EccConfig,
+};
+use snark_verifier::loader::{self, native::NativeLoader};
+use snark_verifier::pcs::kzg::{
+    KzgAccumulator, KzgSuccinctVerifyingKey, LimbsEncoding, LimbsEncodingInstructions,
+};
+use snark_verifier::pcs::{AccumulationScheme, AccumulationSchemeProver};
+use snark_verifier::system;
+use snark_verifier::util::arithmetic::fe_to_limbs;
+use snark_verifier::verifier::plonk::{self as plonk_verifier, PlonkProtocol};
+use snark_verifier::verifier::SnarkVerifier;
+use snark_verifier_sdk::{halo2::gen_snark_shplonk, CircuitExt, Snark, BITS, LIMBS, SHPLONK};
+use std::rc::Rc;
+
+use crate::circuits::merkle_sum_tree::MstInclusionCircuit;
+
+// The parameters of the Poseidon transcript of the snarks, as used by `gen_snark_shplonk`
+const T: usize = 5;
+const RATE: usize = 4;
+const R_F: usize = 8;
+const R_P: usize = 60;
+
+type Svk = KzgSuccinctVerifyingKey<G1Affine>;
+type BaseFieldEccChip = halo2_wrong_ecc::BaseFieldEccChip<G1Affine, LIMBS, BITS>;
+type Halo2Loader<'a> = loader::halo2::Halo2Loader<'a, G1Affine, BaseFieldEccChip>;
+type PoseidonTranscript<L, S> =
+    system::halo2::transcript::halo2::PoseidonTranscript<G1Affine, L, S, T, RATE, R_F, R_P>;
+type PlonkSuccinctVerifier =
+    plonk_verifier::PlonkSuccinctVerifier<SHPLONK, LimbsEncoding<LIMBS, BITS>>;
+
+/// Generates the snark of an inclusion circuit, namely its proof together with its protocol and public inputs, to be aggregated by [`aggregate_inclusion_snarks`].
+///
+/// Unlike [`full_prover`](crate::circuits::utils::full_prover), the proof uses the Poseidon transcript that the aggregation circuit can verify.
+/// `params` must be the parameters of the aggregation circuit downsized to the `k` of the inclusion circuit, so that both circuits share the same trusted setup.
+pub fn gen_inclusion_snark<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>,
+) -> Snark
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    gen_snark_shplonk(params, pk, circuit, None)
+}
+
+/// Aggregates the snarks of [`MstInclusionCircuit`]s of the same round into a single [`InclusionAggregationCircuit`].
+///
+/// The snarks are checked to share the same root hash and root balances before being aggregated, which the aggregation circuit also constrains.
+/// They are then verified natively up to the final pairing check, so that a malformed proof is reported as an error rather than when building the circuit.
+pub fn aggregate_inclusion_snarks<const N_CURRENCIES: usize>(
+    params: &ParamsKZG<Bn256>,
+    snarks: Vec<Snark>,
+) -> Result<InclusionAggregationCircuit<N_CURRENCIES>, Box<dyn std::error::Error>> {
+    let first = snarks.first().ok_or("No snarks to aggregate")?;
+
+    for snark in &snarks {
+        if snark.instances.len() != 1 || snark.instances[0].len() != 2 + N_CURRENCIES {
+            return Err(Box::from(
+                "The snarks must be proofs of MstInclusionCircuit with N_CURRENCIES currencies",
+            ));
+        }
+
+        // The public inputs of an inclusion proof are the leaf hash, the root hash and the root balances
+        if snark.instances[0][1..] != first.instances[0][1..] {
+            return Err(Box::from("The snarks must be proofs of the same round"));
+        }
+    }
+
+    let accumulators = succinct_verify_snarks(params, &snarks)?;
+
+    InclusionAggregationCircuit::new(params, snarks, accumulators)
+}
+
+/// Verifies the snarks natively up to the final pairing check, and returns the accumulators of their pairing checks
+pub(crate) fn succinct_verify_snarks(
+    params: &ParamsKZG<Bn256>,
+    snarks: &[Snark],
+) -> Result<Vec<KzgAccumulator<G1Affine, NativeLoader>>, Box<dyn std::error::Error>> {
+    let svk: Svk = params.get_g()[0].into();
+
+    let mut accumulators = Vec::new();
+    for (index, snark) in snarks.iter().enumerate() {
+        let mut transcript = PoseidonTranscript::<NativeLoader, _>::new(snark.proof.as_slice());
+        let proof = PlonkSuccinctVerifier::read_proof(
+            &svk,
+            &snark.protocol,
+            &snark.instances,
+            &mut transcript,
+        )
+        .map_err(|err| format!("Invalid proof for the snark {}: {:?}", index, err))?;
+        accumulators.extend(
+            PlonkSuccinctVerifier::verify(&svk, &snark.protocol, &snark.instances, &proof)
+                .map_err(|err| format!("Verification failed for the snark {}: {:?}", index, err))?,
+        );
+    }
+
+    Ok(accumulators)
+}
+
+/// The witness of a snark verified by [`InclusionAggregationCircuit`]
+#[derive(Clone)]
+struct SnarkWitness {
+    protocol: PlonkProtocol<G1Affine>,
+    instances: Vec<Vec<Value<Fp>>>,
+    proof: Value<Vec<u8>>,
+}
+
+impl From<Snark> for SnarkWitness {
+    fn from(snark: Snark) -> Self {
+        Self {
+            protocol: snark.protocol,
+            instances: snark
+                .instances
+                .into_iter()
+                .map(|instances| instances.into_iter().map(Value::known).collect())
+                .collect(),
+            proof: Value::known(snark.proof),
+        }
+    }
+}
+
+impl SnarkWitness {
+    fn without_witnesses(&self) -> Self {
+        Self {
+            protocol: self.protocol.clone(),
+            instances: self
+                .instances
+                .iter()
+                .map(|instances| vec![Value::unknown(); instances.len()])
+                .collect(),
+            proof: Value::unknown(),
+        }
+    }
+
+    fn proof(&self) -> Value<&[u8]> {
+        self.proof.as_ref().map(Vec::as_slice)
+    }
+}
+
+/// Verifies the snarks up to the final pairing check. Returns the loaded public inputs of every snark, one vector per snark, and the accumulator of the pairing check.
+fn aggregate<'a>(
+    svk: &Svk,
+    loader: &Rc<Halo2Loader<'a>>,
+    snarks: &[SnarkWitness],
+    as_proof: Value<&'_ [u8]>,
+) -> (
+    Vec<Vec<loader::halo2::Scalar<'a, G1Affine, BaseFieldEccChip>>>,
+    KzgAccumulator<G1Affine, Rc<Halo2Loader<'a>>>,
+) {
+    let mut snark_instances = Vec::with_capacity(snarks.len());
+
+    let accumulators = snarks
+        .iter()
+        .flat_map(|snark| {
+            let protocol = snark.protocol.loaded(loader);
+            let instances = snark
+                .instances
+                .iter()
+                .map(|instances| {
+                    instances
+                        .iter()
+                        .map(|instance| loader.assign_scalar(*instance))
+                        .collect_vec()
+                })
+                .collect_vec();
+            let mut transcript =
+                PoseidonTranscript::<Rc<Halo2Loader>, _>::new(loader, snark.proof());
+            let proof =
+                PlonkSuccinctVerifier::read_proof(svk, &protocol, &instances, &mut transcript)
+                    .unwrap();
+            let accumulators =
+                PlonkSuccinctVerifier::verify(svk, &protocol, &instances, &proof).unwrap();
+
+            snark_instances.push(instances.into_iter().flatten().collect_vec());
+            accumulators
+        })
+        .collect_vec();
+
+    let accumulator = {
+        let mut transcript = PoseidonTranscript::<Rc<Halo2Loader>, _>::new(loader, as_proof);
+        let proof =
+            SHPLONK::read_proof(&Default::default(), &accumulators, &mut transcript).unwrap();
+        SHPLONK::verify(&Default::default(), &accumulators, &proof).unwrap()
+    };
+
+    (snark_instances, accumulator)
+}
+
+/// Configuration for the Inclusion Aggregation circuit
+///
+/// # Fields
+///
+/// * `main_gate_config`: Configuration for the main gate, which performs the field arithmetic of the verifier and exposes the public inputs
+/// * `range_config`: Configuration for the range chip, needed by the non-native arithmetic over the base field of BN254
+#[derive(Clone)]
+pub struct InclusionAggregationConfig {
+    main_gate_config: MainGateConfig,
+    range_config: RangeConfig,
+}
+
+impl InclusionAggregationConfig {
+    pub fn configure(meta: &mut ConstraintSystem<Fp>) -> Self {
+        let main_gate_config = MainGate::<Fp>::configure(meta);
+        let range_config = RangeChip::<Fp>::configure(
+            meta,
+            &main_gate_config,
+            vec![BITS / LIMBS],
+            Rns::<Fq, Fp, LIMBS, BITS>::construct().overflow_lengths(),
+        );
+
+        Self {
+            main_gate_config,
+            range_config,
+        }
+    }
+
+    fn main_gate(&self) -> MainGate<Fp> {
+        MainGate::new(self.main_gate_config.clone())
+    }
+
+    fn range_chip(&self) -> RangeChip<Fp> {
+        RangeChip::new(self.range_config.clone())
+    }
+
+    fn ecc_chip(&self) -> BaseFieldEccChip {
+        BaseFieldEccChip::new(EccConfig::new(
+            self.range_config.clone(),
+            self.main_gate_config.clone(),
+        ))
+    }
+}
+
+/// Circuit aggregating the snarks of [`MstInclusionCircuit`]s of the same round.
+///
+/// The circuit verifies every snark up to the final pairing check, and constrains the root hash and the root balances of every snark to be equal to the ones of the first snark.
+/// The public inputs are:
+/// * the `4 * LIMBS` limbs encoding the two points of the KZG accumulator, on which the verifier of the aggregation proof performs the final pairing check, for instance the contract generated with `gen_evm_verifier_shplonk::<InclusionAggregationCircuit<N_CURRENCIES>>`
+/// * the leaf hash of every snark, in the order of the snarks
+/// * the root hash and the `N_CURRENCIES` root balances shared by the snarks
+///
+/// # Type Parameters
+///
+/// * `N_CURRENCIES`: The number of currencies of the aggregated inclusion circuits
+///
+/// # Fields
+///
+/// * `svk`: The succinct verifying key of the trusted setup shared by the inclusion circuits and the aggregation circuit
+/// * `snarks`: The witnesses of the aggregated snarks
+/// * `instances`: The public inputs of the aggregation circuit
+/// * `as_proof`: The proof of the accumulation of the snarks into a single accumulator
+#[derive(Clone)]
+pub struct InclusionAggregationCircuit<const N_CURRENCIES: usize> {
+    svk: Svk,
+    snarks: Vec<SnarkWitness>,
+    instances: Vec<Fp>,
+    as_proof: Value<Vec<u8>>,
+}
+
+impl<const N_CURRENCIES: usize> InclusionAggregationCircuit<N_CURRENCIES> {
+    /// Accumulates the snarks and computes the public inputs of the circuit. The snarks must be inclusion proofs of the same round, as checked by [`aggregate_inclusion_snarks`],
+    /// and `accumulators` must be returned by [`succinct_verify_snarks`] for the same snarks.
+    pub(crate) fn new(
+        params: &ParamsKZG<Bn256>,
+        snarks: Vec<Snark>,
+        accumulators: Vec<KzgAccumulator<G1Affine, NativeLoader>>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let svk: Svk = params.get_g()[0].into();
+
+        let (accumulator, as_proof) = {
+            let mut transcript = PoseidonTranscript::<NativeLoader, _>::new(Vec::new());
+            let accumulator =
+                SHPLONK::create_proof(&Default::default(), &accumulators, &mut transcript, OsRng)
+                    .map_err(|err| format!("Failed to accumulate the snarks: {:?}", err))?;
+            (accumulator, transcript.finalize())
+        };
+
+        let KzgAccumulator { lhs, rhs } = accumulator;
+        let mut instances = [lhs.x, lhs.y, rhs.x, rhs.y]
+            .map(fe_to_limbs::<_, _, LIMBS, BITS>)
+            .concat();
+        instances.extend(snarks.iter().map(|snark| snark.instances[0][0]));
+        instances.extend_from_slice(&snarks[0].instances[0][1..]);
+
+        Ok(Self {
+            svk,
+            snarks: snarks.into_iter().map_into().collect(),
+            instances,
+            as_proof: Value::known(as_proof),
+        })
+    }
+
+    fn as_proof(&self) -> Value<&[u8]> {
+        self.as_proof.as_ref().map(Vec::as_slice)
+    }
+}
+
+impl<const N_CURRENCIES: usize> CircuitExt<Fp> for InclusionAggregationCircuit<N_CURRENCIES> {
+    /// Returns the number of public inputs of the circuit. It is {4 * LIMBS + n + 1 + N_CURRENCIES}, namely the limbs of the accumulator, the leaf hashes of the n snarks, the root hash and the root balances.
+    fn num_instance(&self) -> Vec<usize> {
+        vec![4 * LIMBS + self.snarks.len() + 1 + N_CURRENCIES]
+    }
+
+    /// Returns the values of the public inputs of the circuit. Namely the limbs of the accumulator, followed by the leaf hashes of the snarks, the root hash and the root balances.
+    fn instances(&self) -> Vec<Vec<Fp>> {
+        vec![self.instances.clone()]
+    }
+
+    /// Returns the positions of the limbs of the accumulator in the public inputs
+    fn accumulator_indices() -> Option<Vec<(usize, usize)>> {
+        Some((0..4 * LIMBS).map(|idx| (0, idx)).collect())
+    }
+}
+
+impl<const N_CURRENCIES: usize> Circuit<Fp> for InclusionAggregationCircuit<N_CURRENCIES> {
+    type Config = InclusionAggregationConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            svk: self.svk,
+            snarks: self
+                .snarks
+                .iter()
+                .map(SnarkWitness::without_witnesses)
+                .collect(),
+            instances: Vec::new(),
+            as_proof: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        InclusionAggregationConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let main_gate = config.main_gate();
+        let range_chip = config.range_chip();
+
+        range_chip.load_table(&mut layouter)?;
+
+        let public_cells = layouter.assign_region(
+            || "aggregate inclusion snarks",
+            |region| {
+                let ctx = RegionCtx::new(region, 0);
+                let loader = Halo2Loader::new(config.ecc_chip(), ctx);
+                let (snark_instances, accumulator) =
+                    aggregate(&self.svk, &loader, &self.snarks, self.as_proof());
+
+                let mut public_cells: Vec<AssignedCell<Fp, Fp>> =
+                    [accumulator.lhs, accumulator.rhs]
+                        .iter()
+                        .map(|ec_point| {
+                            loader.ecc_chip().assign_ec_point_to_limbs(
+                                &mut loader.ctx_mut(),
+                                ec_point.assigned(),
+                            )
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?
+                        .into_iter()
+                        .flatten()
+                        .collect();
+
+                // The public inputs of an inclusion snark are the leaf hash, the root hash and the root balances
+                let snark_instances = snark_instances
+                    .into_iter()
+                    .map(|instances| {
+                        instances
+                            .into_iter()
+                            .map(|instance| instance.into_assigned())
+                            .collect_vec()
+                    })
+                    .collect_vec();
+
+                // Every snark must be of the same round, namely have the root hash and the root balances of the first snark
+                for instances in snark_instances.iter().skip(1) {
+                    for (cell, first_cell) in instances[1..].iter().zip(&snark_instances[0][1..]) {
+                        main_gate.assert_equal(&mut loader.ctx_mut(), cell, first_cell)?;
+                    }
+                }
+
+                public_cells.extend(snark_instances.iter().map(|instances| instances[0].clone()));
+                public_cells.extend_from_slice(&snark_instances[0][1..]);
+
+                Ok(public_cells)
+            },
+        )?;
+
+        for (row, cell) in public_cells.into_iter().enumerate() {
+            main_gate.expose_public(
+                layouter.namespace(|| format!("public input {}", row)),
+                cell,
+                row,
+            )?;
+        }
+
+        Ok(())
+    }
+}
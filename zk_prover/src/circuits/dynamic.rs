@@ -0,0 +1,110 @@
+use crate::circuits::merkle_sum_tree::MstInclusionCircuit;
+use crate::circuits::utils::{full_prover, gen_proof_solidity_calldata, generate_setup_artifacts};
+use crate::merkle_sum_tree::{dispatch_currencies, with_currencies, DynamicMerkleSumTree, Tree};
+use ethers::types::{Bytes, U256};
+use halo2_proofs::halo2curves::bn256::{Bn256, Fr as Fp, G1Affine};
+use halo2_proofs::plonk::{ProvingKey, VerifyingKey};
+use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use snark_verifier_sdk::CircuitExt;
+
+/// Mst Inclusion circuit whose number of currencies is selected at runtime.
+///
+/// Each variant wraps a [`MstInclusionCircuit`] precompiled for a given `N_CURRENCIES`, matching the variants of [`DynamicMerkleSumTree`].
+/// The proving and verifying keys depend on the circuit shape, so they must be generated, or loaded, for the number of currencies of the tree.
+///
+/// # Type Parameters
+///
+/// * `LEVELS`: The number of levels of the merkle sum tree
+/// * `N_BYTES`: The number of bytes in which the balances should lie
+#[derive(Clone)]
+pub enum DynamicMstInclusionCircuit<const LEVELS: usize, const N_BYTES: usize> {
+    Currencies1(MstInclusionCircuit<LEVELS, 1, N_BYTES>),
+    Currencies2(MstInclusionCircuit<LEVELS, 2, N_BYTES>),
+    Currencies3(MstInclusionCircuit<LEVELS, 3, N_BYTES>),
+    Currencies4(MstInclusionCircuit<LEVELS, 4, N_BYTES>),
+    Currencies5(MstInclusionCircuit<LEVELS, 5, N_BYTES>),
+    Currencies6(MstInclusionCircuit<LEVELS, 6, N_BYTES>),
+    Currencies7(MstInclusionCircuit<LEVELS, 7, N_BYTES>),
+    Currencies8(MstInclusionCircuit<LEVELS, 8, N_BYTES>),
+}
+
+impl<const LEVELS: usize, const N_BYTES: usize> DynamicMstInclusionCircuit<LEVELS, N_BYTES> {
+    /// Initializes an empty circuit for `n_currencies` currencies, used to generate the keys of the circuit shape
+    pub fn init_empty(n_currencies: usize) -> Result<Self, Box<dyn std::error::Error>> {
+        let circuit = dispatch_currencies!(DynamicMstInclusionCircuit, n_currencies, N =>
+            MstInclusionCircuit::<LEVELS, N, N_BYTES>::init_empty()
+        );
+        Ok(circuit)
+    }
+
+    /// Initializes the circuit with the proof of inclusion of the entry at `user_index` of the tree. The depth of the tree must be equal to `LEVELS`.
+    pub fn init(
+        tree: &DynamicMerkleSumTree<N_BYTES>,
+        user_index: usize,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if tree.depth() != LEVELS {
+            return Err(format!(
+                "The depth of the tree is {}, but the circuit has {} levels",
+                tree.depth(),
+                LEVELS
+            )
+            .into());
+        }
+
+        let circuit = dispatch_currencies!(DynamicMstInclusionCircuit, tree.n_currencies(), N => {
+            let merkle_sum_tree = tree
+                .as_tree::<N>()
+                .ok_or("The tree doesn't match the number of currencies")?;
+            MstInclusionCircuit::<LEVELS, N, N_BYTES>::init(
+                merkle_sum_tree.generate_proof(user_index)?,
+            )
+        });
+        Ok(circuit)
+    }
+
+    /// Returns the number of currencies of the circuit shape
+    pub fn n_currencies(&self) -> usize {
+        with_currencies!(DynamicMstInclusionCircuit, self, circuit => circuit.root.balances.len())
+    }
+
+    /// Returns the values of the public inputs of the circuit, see [`MstInclusionCircuit`]
+    pub fn instances(&self) -> Vec<Vec<Fp>> {
+        with_currencies!(DynamicMstInclusionCircuit, self, circuit => circuit.instances())
+    }
+
+    /// Generates the setup artifacts of the circuit shape, see [`generate_setup_artifacts`]
+    pub fn generate_setup_artifacts(
+        &self,
+        k: u32,
+        params_path: Option<&str>,
+    ) -> Result<
+        (
+            ParamsKZG<Bn256>,
+            ProvingKey<G1Affine>,
+            VerifyingKey<G1Affine>,
+        ),
+        &'static str,
+    > {
+        with_currencies!(DynamicMstInclusionCircuit, self, circuit =>
+            generate_setup_artifacts(k, params_path, circuit.clone())
+        )
+    }
+
+    /// Generates a proof with the proving key of the circuit shape, see [`full_prover`]
+    pub fn full_prover(&self, params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>) -> Vec<u8> {
+        with_currencies!(DynamicMstInclusionCircuit, self, circuit =>
+            full_prover(params, pk, circuit.clone(), circuit.instances())
+        )
+    }
+
+    /// Generates the proof calldata for the verifier contract of the circuit shape, see [`gen_proof_solidity_calldata`]
+    pub fn gen_proof_solidity_calldata(
+        &self,
+        params: &ParamsKZG<Bn256>,
+        pk: &ProvingKey<G1Affine>,
+    ) -> (Bytes, Vec<U256>) {
+        with_currencies!(DynamicMstInclusionCircuit, self, circuit =>
+            gen_proof_solidity_calldata(params, pk, circuit.clone())
+        )
+    }
+}
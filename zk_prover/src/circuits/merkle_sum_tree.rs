@@ -19,17 +19,22 @@ use snark_verifier_sdk::CircuitExt;
 /// * `LEVELS`: The number of levels of the merkle sum tree. In particular, it indicates the number of hashing operations that are performed from the leaf to the root. For example a tree with 16 entries has 4 levels.
 /// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
 /// * `N_BYTES`: The number of bytes in which the balances should lie
+/// * `LIMB_BITS`: The number of bits of the limbs in which the balances are decomposed by the range check. Defaults to 8. The lookup table has `2^LIMB_BITS` rows, so the circuit needs `k >= LIMB_BITS + 1`.
 ///
 /// # Fields
 ///
-/// * `entry`: The entry to be verified inclusion of.
+/// * `entry`: The entry to be verified inclusion of. For a salted entry, the leaf commits to `H(username, salt)` instead of the username, so that the public leaf hash can only be computed by the user holding the salt.
 /// * `path_indices`: The boolean indices of the path elements from the leaf to the root. 0 indicates that the element is on the right to the path, 1 indicates that the element is on the left to the path. The length of this vector is LEVELS
 /// * `sibling_leaf_node_hash_preimage`: The preimage of the hash that corresponds to the Sibling Leaf Node (part of the Merkle Proof).
 /// * `sibling_middle_node_hash_preimages`: The preimages of the hashes that corresponds to the Sibling Middle Nodes (part of the Merkle Proof).  
 /// * `root`: The root of the Merkle Sum Tree
 #[derive(Clone)]
-pub struct MstInclusionCircuit<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
-where
+pub struct MstInclusionCircuit<
+    const LEVELS: usize,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+    const LIMB_BITS: usize = 8,
+> where
     [usize; N_CURRENCIES + 1]: Sized,
     [usize; N_CURRENCIES + 2]: Sized,
 {
@@ -40,8 +45,12 @@ where
     pub root: Node<N_CURRENCIES>,
 }
 
-impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> CircuitExt<Fp>
-    for MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+impl<
+        const LEVELS: usize,
+        const N_CURRENCIES: usize,
+        const N_BYTES: usize,
+        const LIMB_BITS: usize,
+    > CircuitExt<Fp> for MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES, LIMB_BITS>
 where
     [usize; N_CURRENCIES + 1]: Sized,
     [usize; N_CURRENCIES + 2]: Sized,
@@ -58,16 +67,24 @@ where
     }
 }
 
-impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> CircuitBase
-    for MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+impl<
+        const LEVELS: usize,
+        const N_CURRENCIES: usize,
+        const N_BYTES: usize,
+        const LIMB_BITS: usize,
+    > CircuitBase for MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES, LIMB_BITS>
 where
     [usize; N_CURRENCIES + 1]: Sized,
     [usize; N_CURRENCIES + 2]: Sized,
 {
 }
 
-impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
-    MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+impl<
+        const LEVELS: usize,
+        const N_CURRENCIES: usize,
+        const N_BYTES: usize,
+        const LIMB_BITS: usize,
+    > MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES, LIMB_BITS>
 where
     [usize; N_CURRENCIES + 1]: Sized,
     [usize; N_CURRENCIES + 2]: Sized,
@@ -108,6 +125,7 @@ where
 ///
 /// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
 /// * `N_BYTES`: The number of bytes in which the balances should lie
+/// * `LIMB_BITS`: The number of bits of the limbs of the range check
 ///
 /// # Fields
 ///
@@ -119,21 +137,25 @@ where
 /// * `advices`: Advice columns used to store the private inputs
 
 #[derive(Debug, Clone)]
-pub struct MstInclusionConfig<const N_CURRENCIES: usize, const N_BYTES: usize>
-where
+pub struct MstInclusionConfig<
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+    const LIMB_BITS: usize = 8,
+> where
     [usize; N_CURRENCIES + 1]: Sized,
     [usize; N_CURRENCIES + 2]: Sized,
 {
-    merkle_sum_tree_config: MerkleSumTreeConfig,
-    poseidon_entry_config: PoseidonConfig<2, 1, { N_CURRENCIES + 1 }>,
-    poseidon_middle_config: PoseidonConfig<2, 1, { N_CURRENCIES + 2 }>,
-    range_check_config: RangeCheckConfig<N_BYTES>,
-    instance: Column<Instance>,
-    advices: [Column<Advice>; 3],
-    fixed_columns: [Column<Fixed>; 5],
+    pub(crate) merkle_sum_tree_config: MerkleSumTreeConfig,
+    pub(crate) poseidon_entry_config: PoseidonConfig<2, 1, { N_CURRENCIES + 1 }>,
+    pub(crate) poseidon_middle_config: PoseidonConfig<2, 1, { N_CURRENCIES + 2 }>,
+    pub(crate) range_check_config: RangeCheckConfig<LIMB_BITS>,
+    pub(crate) instance: Column<Instance>,
+    pub(crate) advices: [Column<Advice>; 3],
+    pub(crate) fixed_columns: [Column<Fixed>; 5],
 }
 
-impl<const N_CURRENCIES: usize, const N_BYTES: usize> MstInclusionConfig<N_CURRENCIES, N_BYTES>
+impl<const N_CURRENCIES: usize, const N_BYTES: usize, const LIMB_BITS: usize>
+    MstInclusionConfig<N_CURRENCIES, N_BYTES, LIMB_BITS>
 where
     [usize; N_CURRENCIES + 1]: Sized,
     [usize; N_CURRENCIES + 2]: Sized,
@@ -185,11 +207,12 @@ where
             selectors[0..2].try_into().unwrap(),
         );
 
-        let range_check_config = RangeCheckChip::<N_BYTES>::configure(
+        let range_check_config = RangeCheckChip::<LIMB_BITS>::configure(
             meta,
             advices[0],
             fixed_columns[4],
             enable_lookup_selector,
+            8 * N_BYTES,
         );
 
         let instance = meta.instance_column();
@@ -207,13 +230,17 @@ where
     }
 }
 
-impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> Circuit<Fp>
-    for MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+impl<
+        const LEVELS: usize,
+        const N_CURRENCIES: usize,
+        const N_BYTES: usize,
+        const LIMB_BITS: usize,
+    > Circuit<Fp> for MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES, LIMB_BITS>
 where
     [usize; N_CURRENCIES + 1]: Sized,
     [usize; N_CURRENCIES + 2]: Sized,
 {
-    type Config = MstInclusionConfig<N_CURRENCIES, N_BYTES>;
+    type Config = MstInclusionConfig<N_CURRENCIES, N_BYTES, LIMB_BITS>;
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
@@ -222,7 +249,7 @@ where
 
     /// Configures the circuit
     fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
-        MstInclusionConfig::<N_CURRENCIES, N_BYTES>::configure(meta)
+        MstInclusionConfig::<N_CURRENCIES, N_BYTES, LIMB_BITS>::configure(meta)
     }
 
     fn synthesize(
@@ -230,296 +257,378 @@ where
         config: Self::Config,
         mut layouter: impl Layouter<Fp>,
     ) -> Result<(), Error> {
-        // build auxiliary chips
-        let merkle_sum_tree_chip =
-            MerkleSumTreeChip::<N_CURRENCIES>::construct(config.merkle_sum_tree_config);
-
-        let poseidon_entry_chip =
-            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 1 }>::construct(
-                config.poseidon_entry_config,
-            );
+        let (root_hash, root_balances) =
+            self.assign_path(&config, layouter.namespace(|| "merkle path"))?;
 
-        let poseidon_middle_chip =
-            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 2 }>::construct(
-                config.poseidon_middle_config,
-            );
-
-        let range_check_chip = RangeCheckChip::<N_BYTES>::construct(config.range_check_config);
-
-        // Assign the entry username to the witness
-        let username = self.assign_value_to_witness(
-            layouter.namespace(|| "assign entry username"),
-            big_uint_to_fp(self.entry.username_as_big_uint()),
-            "entry username",
-            config.advices[0],
+        // expose the last current hash, namely the root hash, as public input
+        self.expose_public(
+            layouter.namespace(|| "public root hash"),
+            &root_hash,
+            1,
+            config.instance,
         )?;
 
-        // Assign the entry balances to the witness
-        let mut current_balances = vec![];
-
-        for i in 0..N_CURRENCIES {
-            let balance = self.assign_value_to_witness(
-                layouter.namespace(|| format!("assign entry balance {}", i)),
-                big_uint_to_fp(&self.entry.balances()[i]),
-                "entry balance",
-                config.advices[1],
+        // expose the last current balances, namely the root balances, as public input
+        for (i, balance) in root_balances.iter().enumerate() {
+            self.expose_public(
+                layouter.namespace(|| format!("public root balance {}", i)),
+                balance,
+                2 + i,
+                config.instance,
             )?;
-            current_balances.push(balance);
         }
+        Ok(())
+    }
+}
 
-        // Perform the hashing to username and balances to obtain the leaf hash
-        // create an hash_input array of length N_CURRENCIES + 1 that contains the entry username and the entry balances
-        let entry_hasher_input_vec: Vec<AssignedCell<Fp, Fp>> = [username]
-            .iter()
-            .chain(current_balances.iter())
-            .map(|x| x.to_owned())
-            .collect();
-
-        let entry_hasher_input: [AssignedCell<Fp, Fp>; N_CURRENCIES + 1] =
-            match entry_hasher_input_vec.try_into() {
-                Ok(arr) => arr,
-                Err(_) => panic!("Failed to convert Vec to Array"),
-            };
-
-        // compute the entry hash
-        let mut current_hash = poseidon_entry_chip.hash(
-            layouter.namespace(|| "perform poseidon entry hash"),
-            entry_hasher_input,
+impl<
+        const LEVELS: usize,
+        const N_CURRENCIES: usize,
+        const N_BYTES: usize,
+        const LIMB_BITS: usize,
+    > MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES, LIMB_BITS>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    /// Assigns the entry, exposes its leaf hash as public input at row 0 and computes the path from the leaf to the root.
+    /// Returns the cells of the root hash and of the root balances, which are left to the caller to expose.
+    pub(crate) fn assign_path(
+        &self,
+        config: &MstInclusionConfig<N_CURRENCIES, N_BYTES, LIMB_BITS>,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(AssignedCell<Fp, Fp>, Vec<AssignedCell<Fp, Fp>>), Error> {
+        // load lookup table for range check
+        self.load::<LIMB_BITS>(&mut layouter, config.fixed_columns[4])?;
+
+        let merkle_proof = MerkleProof {
+            entry: self.entry.clone(),
+            root: self.root.clone(),
+            sibling_leaf_node_hash_preimage: self.sibling_leaf_node_hash_preimage,
+            sibling_middle_node_hash_preimages: self.sibling_middle_node_hash_preimages.clone(),
+            path_indices: self.path_indices.clone(),
+        };
+        let path = assign_path::<LEVELS, N_CURRENCIES, N_BYTES, LIMB_BITS>(
+            self,
+            layouter.namespace(|| "assign path"),
+            config,
+            &merkle_proof,
         )?;
 
         // expose the first current hash, namely the leaf hash, as public input
         self.expose_public(
             layouter.namespace(|| "public leaf hash"),
-            &current_hash,
+            &path.leaf_hash,
             0,
             config.instance,
         )?;
 
-        // load lookup table for range check
-        self.load(&mut layouter, config.fixed_columns[4])?;
-
-        for level in 0..LEVELS {
-            let namespace_prefix = format!("level {}", level);
-
-            let sibling_hash: AssignedCell<Fp, Fp>; // hash of the sibling node
-            let mut sibling_balances: Vec<AssignedCell<Fp, Fp>> = vec![]; // balances of the sibling node
-
-            // Perform the hashing of sibling leaf hash preimage to obtain the sibling leaf hash
-            if level == 0 {
-                // Assign username from sibling leaf node hash preimage to the circuit
-                let sibling_leaf_node_username = self.assign_value_to_witness(
-                    layouter.namespace(|| format!("sibling leaf node username")),
-                    self.sibling_leaf_node_hash_preimage[0],
-                    "sibling leaf node username",
-                    config.advices[0],
-                )?;
+        Ok((path.root_hash, path.root_balances))
+    }
+}
 
-                // Assign balances from sibling leaf node hash preimage to the circuit
-                for currency in 0..N_CURRENCIES {
-                    let leaf_node_sibling_balance = self.assign_value_to_witness(
-                        layouter.namespace(|| format!("sibling leaf node balance {}", currency)),
-                        self.sibling_leaf_node_hash_preimage[currency + 1],
-                        "sibling leaf balance",
-                        config.advices[1],
-                    )?;
-                    sibling_balances.push(leaf_node_sibling_balance);
-                }
-
-                // create an hash_input array of length N_CURRENCIES + 1 that contains the sibling_leaf_node_username and the sibling_balances (the sibling leaf node hash preimage)
-                let sibling_hasher_input_vec: Vec<AssignedCell<Fp, Fp>> =
-                    [sibling_leaf_node_username]
-                        .iter()
-                        .chain(sibling_balances.iter())
-                        .map(|x| x.to_owned())
-                        .collect();
-
-                let sibling_hasher_input: [AssignedCell<Fp, Fp>; N_CURRENCIES + 1] =
-                    match sibling_hasher_input_vec.try_into() {
-                        Ok(arr) => arr,
-                        Err(_) => panic!("Failed to convert Vec to Array"),
-                    };
-
-                // compute the sibling hash
-                let computed_sibling_hash = poseidon_entry_chip.hash(
-                    layouter.namespace(|| format!("{}: perform poseidon hash", namespace_prefix)),
-                    sibling_hasher_input,
-                )?;
+/// The cells of a merkle path assigned by [`assign_path`]
+///
+/// # Fields
+///
+/// * `username`: The username of the leaf, namely `H(username, salt)` for a salted entry
+/// * `leaf_hash`: The hash of the leaf
+/// * `root_hash`: The hash of the root computed from the leaf and the path
+/// * `root_balances`: The balances of the root computed from the leaf and the path
+/// * `swap_bits`: The path indices, from the leaf to the root
+pub(crate) struct AssignedPath {
+    pub(crate) username: AssignedCell<Fp, Fp>,
+    pub(crate) leaf_hash: AssignedCell<Fp, Fp>,
+    pub(crate) root_hash: AssignedCell<Fp, Fp>,
+    pub(crate) root_balances: Vec<AssignedCell<Fp, Fp>>,
+    pub(crate) swap_bits: Vec<AssignedCell<Fp, Fp>>,
+}
 
-                // For level 0, perform range check on the leaf node balances and on the sibling node balances
-                for currency in 0..N_CURRENCIES {
-                    // Each balance cell is constrained to be within the range defined by N_BYTES
-                    range_check_chip.assign(
-                        layouter.namespace(|| {
-                            format!(
-                                "{}: currency {}: range check leaf balance",
-                                namespace_prefix, currency
-                            )
-                        }),
-                        &current_balances[currency],
-                    )?;
-                    range_check_chip.assign(
-                        layouter.namespace(|| {
-                            format!(
-                                "{}: currency {}: range check sibling balance",
-                                namespace_prefix, currency
-                            )
-                        }),
-                        &sibling_balances[currency],
-                    )?;
-                }
-
-                sibling_hash = computed_sibling_hash;
-            }
-            // Other levels
-            // Assign sibling node hash preimage to the circuit (split it in balances, left child hash and right child hash)
-            // Perform the hashing of sibling node hash preimage to obtain the sibling node hash
-            else {
-                // Assign balances from sibling middle node hash preimage to the circuit
-                for currency in 0..N_CURRENCIES {
-                    let middle_node_sibling_balance = self.assign_value_to_witness(
-                        layouter.namespace(|| format!("sibling node balance {}", currency)),
-                        self.sibling_middle_node_hash_preimages[level - 1][currency],
-                        "sibling node balance",
-                        config.advices[1],
-                    )?;
-                    sibling_balances.push(middle_node_sibling_balance);
-                }
-
-                // Assign middle_node_sibling_child_left_hash from middle node hash preimage to the circuit
-                let middle_node_sibling_child_left_hash = self.assign_value_to_witness(
-                    layouter.namespace(|| format!("sibling left hash")),
-                    self.sibling_middle_node_hash_preimages[level - 1][N_CURRENCIES],
-                    "sibling left hash",
-                    config.advices[2],
-                )?;
+/// Assigns the leaf of `proof` and computes the path from the leaf to the root, range checking the balances of the leaf and of the siblings.
+/// This is the merkle path verification shared by all the circuits of the merkle sum tree. The lookup table of the range check must be loaded by `circuit`,
+/// which is also left to expose the returned cells.
+pub(crate) fn assign_path<
+    const LEVELS: usize,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+    const LIMB_BITS: usize,
+>(
+    circuit: &impl CircuitBase,
+    mut layouter: impl Layouter<Fp>,
+    config: &MstInclusionConfig<N_CURRENCIES, N_BYTES, LIMB_BITS>,
+    proof: &MerkleProof<N_CURRENCIES, N_BYTES>,
+) -> Result<AssignedPath, Error>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    // build auxiliary chips
+    let merkle_sum_tree_chip =
+        MerkleSumTreeChip::<N_CURRENCIES>::construct(config.merkle_sum_tree_config.clone());
+
+    let poseidon_entry_chip = PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 1 }>::construct(
+        config.poseidon_entry_config.clone(),
+    );
+
+    let poseidon_middle_chip = PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 2 }>::construct(
+        config.poseidon_middle_config.clone(),
+    );
+
+    let range_check_chip = RangeCheckChip::construct(config.range_check_config);
+
+    // Assign the entry username to the witness. For a salted entry, the leaf commits to `H(username, salt)` instead of the username
+    let username = circuit.assign_value_to_witness(
+        layouter.namespace(|| "assign entry username"),
+        proof.entry.username_commitment(),
+        "entry username",
+        config.advices[0],
+    )?;
+
+    // Assign the entry balances to the witness
+    let mut current_balances = vec![];
+
+    for i in 0..N_CURRENCIES {
+        let balance = circuit.assign_value_to_witness(
+            layouter.namespace(|| format!("assign entry balance {}", i)),
+            big_uint_to_fp(&proof.entry.balances()[i]),
+            "entry balance",
+            config.advices[1],
+        )?;
+        current_balances.push(balance);
+    }
 
-                // Assign middle_node_sibling_child_right_hash from middle node hash preimage to the circuit
-                let middle_node_sibling_child_right_hash = self.assign_value_to_witness(
-                    layouter.namespace(|| format!("sibling right hash")),
-                    self.sibling_middle_node_hash_preimages[level - 1][N_CURRENCIES + 1],
-                    "sibling right hash",
-                    config.advices[2],
-                )?;
+    // Perform the hashing to username and balances to obtain the leaf hash
+    // create an hash_input array of length N_CURRENCIES + 1 that contains the entry username and the entry balances
+    let entry_hasher_input_vec: Vec<AssignedCell<Fp, Fp>> = [username.clone()]
+        .iter()
+        .chain(current_balances.iter())
+        .map(|x| x.to_owned())
+        .collect();
+
+    let entry_hasher_input: [AssignedCell<Fp, Fp>; N_CURRENCIES + 1] =
+        match entry_hasher_input_vec.try_into() {
+            Ok(arr) => arr,
+            Err(_) => panic!("Failed to convert Vec to Array"),
+        };
+
+    // compute the entry hash
+    let mut current_hash = poseidon_entry_chip.hash(
+        layouter.namespace(|| "perform poseidon entry hash"),
+        entry_hasher_input,
+    )?;
+
+    let leaf_hash = current_hash.clone();
+    let mut swap_bits = Vec::with_capacity(LEVELS);
+
+    for level in 0..LEVELS {
+        let namespace_prefix = format!("level {}", level);
+
+        let sibling_hash: AssignedCell<Fp, Fp>; // hash of the sibling node
+        let mut sibling_balances: Vec<AssignedCell<Fp, Fp>> = vec![]; // balances of the sibling node
+
+        // Perform the hashing of sibling leaf hash preimage to obtain the sibling leaf hash
+        if level == 0 {
+            // Assign username from sibling leaf node hash preimage to the circuit
+            let sibling_leaf_node_username = circuit.assign_value_to_witness(
+                layouter.namespace(|| format!("sibling leaf node username")),
+                proof.sibling_leaf_node_hash_preimage[0],
+                "sibling leaf node username",
+                config.advices[0],
+            )?;
 
-                // create an hash_input array of length 2 + N_CURRENCIES that contains the sibling balances, the middle_node_sibling_child_left_hash and the middle_node_sibling_child_right_hash
-                let sibling_hasher_input_vec: Vec<AssignedCell<Fp, Fp>> = sibling_balances
-                    .iter()
-                    .chain([middle_node_sibling_child_left_hash].iter())
-                    .chain([middle_node_sibling_child_right_hash].iter())
-                    .map(|x| x.to_owned())
-                    .collect();
-
-                let sibling_hasher_input: [AssignedCell<Fp, Fp>; N_CURRENCIES + 2] =
-                    match sibling_hasher_input_vec.try_into() {
-                        Ok(arr) => arr,
-                        Err(_) => panic!("Failed to convert Vec to Array"),
-                    };
-
-                // compute the sibling hash
-                let computed_sibling_hash = poseidon_middle_chip.hash(
-                    layouter.namespace(|| format!("{}: perform poseidon hash", namespace_prefix)),
-                    sibling_hasher_input,
+            // Assign balances from sibling leaf node hash preimage to the circuit
+            for currency in 0..N_CURRENCIES {
+                let leaf_node_sibling_balance = circuit.assign_value_to_witness(
+                    layouter.namespace(|| format!("sibling leaf node balance {}", currency)),
+                    proof.sibling_leaf_node_hash_preimage[currency + 1],
+                    "sibling leaf balance",
+                    config.advices[1],
                 )?;
+                sibling_balances.push(leaf_node_sibling_balance);
+            }
 
-                // For other levels, only perform range on the sibling node balances. Any risk of overflow of the `current_balances` will be checked during verification
-                for currency in 0..N_CURRENCIES {
-                    // Each balance cell is constrained to be within the range defined by N_BYTES
-                    range_check_chip.assign(
-                        layouter.namespace(|| {
-                            format!(
-                                "{}: currency {}: range check sibling balance",
-                                namespace_prefix, currency
-                            )
-                        }),
-                        &sibling_balances[currency],
-                    )?;
-                }
-
-                sibling_hash = computed_sibling_hash;
-            };
+            // create an hash_input array of length N_CURRENCIES + 1 that contains the sibling_leaf_node_username and the sibling_balances (the sibling leaf node hash preimage)
+            let sibling_hasher_input_vec: Vec<AssignedCell<Fp, Fp>> = [sibling_leaf_node_username]
+                .iter()
+                .chain(sibling_balances.iter())
+                .map(|x| x.to_owned())
+                .collect();
 
-            // For each level assign the swap bit to the circuit
-            let swap_bit_level = self.assign_value_to_witness(
-                layouter.namespace(|| format!("{}: assign swap bit", namespace_prefix)),
-                self.path_indices[level],
-                "swap bit",
-                config.advices[0],
+            let sibling_hasher_input: [AssignedCell<Fp, Fp>; N_CURRENCIES + 1] =
+                match sibling_hasher_input_vec.try_into() {
+                    Ok(arr) => arr,
+                    Err(_) => panic!("Failed to convert Vec to Array"),
+                };
+
+            // compute the sibling hash
+            let computed_sibling_hash = poseidon_entry_chip.hash(
+                layouter.namespace(|| format!("{}: perform poseidon hash", namespace_prefix)),
+                sibling_hasher_input,
             )?;
 
-            // For every level, perform the swap of the hashes (between `current_hash` and `sibling_hash`) according to the swap bit
-            let (hash_left_current, hash_right_current) = merkle_sum_tree_chip
-                .swap_hashes_per_level(
-                    layouter.namespace(|| format!("{}: swap hashes", namespace_prefix)),
-                    &current_hash,
-                    &sibling_hash,
-                    &swap_bit_level,
+            // For level 0, perform range check on the leaf node balances and on the sibling node balances
+            for currency in 0..N_CURRENCIES {
+                // Each balance cell is constrained to be within the range defined by N_BYTES
+                range_check_chip.assign(
+                    layouter.namespace(|| {
+                        format!(
+                            "{}: currency {}: range check leaf balance",
+                            namespace_prefix, currency
+                        )
+                    }),
+                    &current_balances[currency],
                 )?;
+                range_check_chip.assign(
+                    layouter.namespace(|| {
+                        format!(
+                            "{}: currency {}: range check sibling balance",
+                            namespace_prefix, currency
+                        )
+                    }),
+                    &sibling_balances[currency],
+                )?;
+            }
 
-            let mut next_balances = vec![];
-            let mut left_balances = vec![];
-            let mut right_balances = vec![];
-
-            // For every level, perform the swap of the balances (between `current_balances` and `sibling_balances`) according to the swap bit
+            sibling_hash = computed_sibling_hash;
+        }
+        // Other levels
+        // Assign sibling node hash preimage to the circuit (split it in balances, left child hash and right child hash)
+        // Perform the hashing of sibling node hash preimage to obtain the sibling node hash
+        else {
+            // Assign balances from sibling middle node hash preimage to the circuit
             for currency in 0..N_CURRENCIES {
-                let (left_balance, right_balance, next_balance) = merkle_sum_tree_chip
-                    .swap_balances_per_level(
-                        layouter.namespace(|| {
-                            format!(
-                                "{}: currency {}: assign nodes balance",
-                                namespace_prefix, currency
-                            )
-                        }),
-                        &current_balances[currency],
-                        &sibling_balances[currency],
-                        &swap_bit_level,
-                    )?;
-
-                next_balances.push(next_balance);
-                left_balances.push(left_balance);
-                right_balances.push(right_balance);
+                let middle_node_sibling_balance = circuit.assign_value_to_witness(
+                    layouter.namespace(|| format!("sibling node balance {}", currency)),
+                    proof.sibling_middle_node_hash_preimages[level - 1][currency],
+                    "sibling node balance",
+                    config.advices[1],
+                )?;
+                sibling_balances.push(middle_node_sibling_balance);
             }
 
-            // create an hash_input array of length N_CURRENCIES + 2 that contains the next balances, the left hash and the right hash
-            let middle_hasher_input_vec: Vec<AssignedCell<Fp, Fp>> = next_balances
+            // Assign middle_node_sibling_child_left_hash from middle node hash preimage to the circuit
+            let middle_node_sibling_child_left_hash = circuit.assign_value_to_witness(
+                layouter.namespace(|| format!("sibling left hash")),
+                proof.sibling_middle_node_hash_preimages[level - 1][N_CURRENCIES],
+                "sibling left hash",
+                config.advices[2],
+            )?;
+
+            // Assign middle_node_sibling_child_right_hash from middle node hash preimage to the circuit
+            let middle_node_sibling_child_right_hash = circuit.assign_value_to_witness(
+                layouter.namespace(|| format!("sibling right hash")),
+                proof.sibling_middle_node_hash_preimages[level - 1][N_CURRENCIES + 1],
+                "sibling right hash",
+                config.advices[2],
+            )?;
+
+            // create an hash_input array of length 2 + N_CURRENCIES that contains the sibling balances, the middle_node_sibling_child_left_hash and the middle_node_sibling_child_right_hash
+            let sibling_hasher_input_vec: Vec<AssignedCell<Fp, Fp>> = sibling_balances
                 .iter()
-                .chain([hash_left_current].iter())
-                .chain([hash_right_current].iter())
+                .chain([middle_node_sibling_child_left_hash].iter())
+                .chain([middle_node_sibling_child_right_hash].iter())
                 .map(|x| x.to_owned())
                 .collect();
 
-            let middle_hasher_input: [AssignedCell<Fp, Fp>; N_CURRENCIES + 2] =
-                match middle_hasher_input_vec.try_into() {
+            let sibling_hasher_input: [AssignedCell<Fp, Fp>; N_CURRENCIES + 2] =
+                match sibling_hasher_input_vec.try_into() {
                     Ok(arr) => arr,
                     Err(_) => panic!("Failed to convert Vec to Array"),
                 };
 
-            // compute the next hash
-            let computed_hash = poseidon_middle_chip.hash(
+            // compute the sibling hash
+            let computed_sibling_hash = poseidon_middle_chip.hash(
                 layouter.namespace(|| format!("{}: perform poseidon hash", namespace_prefix)),
-                middle_hasher_input,
+                sibling_hasher_input,
             )?;
 
-            current_balances = next_balances;
-            current_hash = computed_hash;
-        }
+            // For other levels, only perform range on the sibling node balances. Any risk of overflow of the `current_balances` will be checked during verification
+            for currency in 0..N_CURRENCIES {
+                // Each balance cell is constrained to be within the range defined by N_BYTES
+                range_check_chip.assign(
+                    layouter.namespace(|| {
+                        format!(
+                            "{}: currency {}: range check sibling balance",
+                            namespace_prefix, currency
+                        )
+                    }),
+                    &sibling_balances[currency],
+                )?;
+            }
 
-        // expose the last current hash, namely the root hash, as public input
-        self.expose_public(
-            layouter.namespace(|| "public root hash"),
+            sibling_hash = computed_sibling_hash;
+        };
+
+        // For each level assign the swap bit to the circuit
+        let swap_bit_level = circuit.assign_value_to_witness(
+            layouter.namespace(|| format!("{}: assign swap bit", namespace_prefix)),
+            proof.path_indices[level],
+            "swap bit",
+            config.advices[0],
+        )?;
+
+        // For every level, perform the swap of the hashes (between `current_hash` and `sibling_hash`) according to the swap bit
+        let (hash_left_current, hash_right_current) = merkle_sum_tree_chip.swap_hashes_per_level(
+            layouter.namespace(|| format!("{}: swap hashes", namespace_prefix)),
             &current_hash,
-            1,
-            config.instance,
+            &sibling_hash,
+            &swap_bit_level,
         )?;
 
-        // expose the last current balances, namely the root balances, as public input
-        for (i, balance) in current_balances.iter().enumerate() {
-            self.expose_public(
-                layouter.namespace(|| format!("public root balance {}", i)),
-                balance,
-                2 + i,
-                config.instance,
-            )?;
+        let mut next_balances = vec![];
+        let mut left_balances = vec![];
+        let mut right_balances = vec![];
+
+        // For every level, perform the swap of the balances (between `current_balances` and `sibling_balances`) according to the swap bit
+        for currency in 0..N_CURRENCIES {
+            let (left_balance, right_balance, next_balance) = merkle_sum_tree_chip
+                .swap_balances_per_level(
+                    layouter.namespace(|| {
+                        format!(
+                            "{}: currency {}: assign nodes balance",
+                            namespace_prefix, currency
+                        )
+                    }),
+                    &current_balances[currency],
+                    &sibling_balances[currency],
+                    &swap_bit_level,
+                )?;
+
+            next_balances.push(next_balance);
+            left_balances.push(left_balance);
+            right_balances.push(right_balance);
         }
-        Ok(())
+
+        // create an hash_input array of length N_CURRENCIES + 2 that contains the next balances, the left hash and the right hash
+        let middle_hasher_input_vec: Vec<AssignedCell<Fp, Fp>> = next_balances
+            .iter()
+            .chain([hash_left_current].iter())
+            .chain([hash_right_current].iter())
+            .map(|x| x.to_owned())
+            .collect();
+
+        let middle_hasher_input: [AssignedCell<Fp, Fp>; N_CURRENCIES + 2] =
+            match middle_hasher_input_vec.try_into() {
+                Ok(arr) => arr,
+                Err(_) => panic!("Failed to convert Vec to Array"),
+            };
+
+        // compute the next hash
+        let computed_hash = poseidon_middle_chip.hash(
+            layouter.namespace(|| format!("{}: perform poseidon hash", namespace_prefix)),
+            middle_hasher_input,
+        )?;
+
+        current_balances = next_balances;
+        current_hash = computed_hash;
+        swap_bits.push(swap_bit_level);
     }
+
+    Ok(AssignedPath {
+        username,
+        leaf_hash,
+        root_hash: current_hash,
+        root_balances: current_balances,
+        swap_bits,
+    })
 }
@@ -1,4 +1,10 @@
+pub mod aggregation;
+pub mod dynamic;
 pub mod merkle_sum_tree;
+pub mod mst_batch_inclusion;
+pub mod mst_non_inclusion;
+pub mod mst_quaternary_inclusion;
+pub mod solvency;
 mod tests;
 pub mod traits;
 pub mod types;
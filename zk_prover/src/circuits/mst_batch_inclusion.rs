@@ -0,0 +1,195 @@
+use crate::circuits::merkle_sum_tree::{assign_path, MstInclusionConfig};
+use crate::circuits::traits::CircuitBase;
+use crate::merkle_sum_tree::{Entry, MerkleProof, Node};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+use snark_verifier_sdk::CircuitExt;
+
+/// Circuit for verifying the inclusion of `BATCH_SIZE` entries inside a merkle sum tree with a given root, in a single proof.
+///
+/// Each merkle proof is verified as in [`MstInclusionCircuit`](crate::circuits::merkle_sum_tree::MstInclusionCircuit). The proofs share the lookup table of the range check and the public root, namely every path is constrained to the same root hash and root balances instance cells.
+/// To prove fewer than `BATCH_SIZE` users, the last proof can be repeated.
+///
+/// # Type Parameters
+///
+/// * `LEVELS`: The number of levels of the merkle sum tree
+/// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
+/// * `N_BYTES`: The number of bytes in which the balances should lie
+/// * `BATCH_SIZE`: The number of entries whose inclusion is verified
+///
+/// # Fields
+///
+/// * `proofs`: The `BATCH_SIZE` merkle proofs to be verified, all against the same root. There is at least one proof, as checked by [`MstBatchInclusionCircuit::init`]
+#[derive(Clone)]
+pub struct MstBatchInclusionCircuit<
+    const LEVELS: usize,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+    const BATCH_SIZE: usize,
+> where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub(crate) proofs: Vec<MerkleProof<N_CURRENCIES, N_BYTES>>,
+}
+
+impl<
+        const LEVELS: usize,
+        const N_CURRENCIES: usize,
+        const N_BYTES: usize,
+        const BATCH_SIZE: usize,
+    > CircuitExt<Fp> for MstBatchInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES, BATCH_SIZE>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    /// Returns the number of public inputs of the circuit. It is {BATCH_SIZE + 1 + N_CURRENCIES}, namely the leaf hashes to be verified inclusion of, the root hash of the merkle sum tree and the root balances of the merkle sum tree.
+    fn num_instance(&self) -> Vec<usize> {
+        vec![{ BATCH_SIZE + 1 + N_CURRENCIES }]
+    }
+    /// Returns the values of the public inputs of the circuit. Namely the leaf hashes to be verified inclusion of, in the order of the proofs, followed by the root hash and the root balances of the merkle sum tree.
+    fn instances(&self) -> Vec<Vec<Fp>> {
+        let mut instance: Vec<Fp> = self
+            .proofs
+            .iter()
+            .map(|proof| proof.entry.compute_leaf().hash)
+            .collect();
+        instance.push(self.proofs[0].root.hash);
+        instance.extend_from_slice(&self.proofs[0].root.balances);
+        vec![instance]
+    }
+}
+
+impl<
+        const LEVELS: usize,
+        const N_CURRENCIES: usize,
+        const N_BYTES: usize,
+        const BATCH_SIZE: usize,
+    > CircuitBase for MstBatchInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES, BATCH_SIZE>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+}
+
+impl<
+        const LEVELS: usize,
+        const N_CURRENCIES: usize,
+        const N_BYTES: usize,
+        const BATCH_SIZE: usize,
+    > MstBatchInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES, BATCH_SIZE>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub fn init_empty() -> Self {
+        let empty_proof = MerkleProof {
+            entry: Entry::init_empty(),
+            root: Node::init_empty(),
+            sibling_leaf_node_hash_preimage: [Fp::zero(); N_CURRENCIES + 1],
+            sibling_middle_node_hash_preimages: vec![[Fp::zero(); N_CURRENCIES + 2]; LEVELS - 1],
+            path_indices: vec![Fp::zero(); LEVELS],
+        };
+
+        Self {
+            proofs: vec![empty_proof; BATCH_SIZE],
+        }
+    }
+
+    /// Initializes the circuit with the merkle proofs of the users of which the inclusion is to be verified.
+    /// There must be exactly `BATCH_SIZE` proofs of `LEVELS` levels, all against the same root.
+    pub fn init(
+        proofs: Vec<MerkleProof<N_CURRENCIES, N_BYTES>>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if BATCH_SIZE == 0 {
+            return Err(Box::from("The batch must contain at least one proof"));
+        }
+        if proofs.len() != BATCH_SIZE {
+            return Err(format!("The batch must contain {} proofs", BATCH_SIZE).into());
+        }
+
+        for proof in &proofs {
+            if proof.path_indices.len() != LEVELS
+                || proof.sibling_middle_node_hash_preimages.len() != LEVELS - 1
+            {
+                return Err(format!("The proofs must have {} levels", LEVELS).into());
+            }
+            if proof.root.hash != proofs[0].root.hash
+                || proof.root.balances != proofs[0].root.balances
+            {
+                return Err(Box::from("The proofs must be against the same root"));
+            }
+        }
+
+        Ok(Self { proofs })
+    }
+}
+
+impl<
+        const LEVELS: usize,
+        const N_CURRENCIES: usize,
+        const N_BYTES: usize,
+        const BATCH_SIZE: usize,
+    > Circuit<Fp> for MstBatchInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES, BATCH_SIZE>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    type Config = MstInclusionConfig<N_CURRENCIES, N_BYTES>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::init_empty()
+    }
+
+    /// Configures the circuit, which has the same columns and gates as [`MstInclusionCircuit`](crate::circuits::merkle_sum_tree::MstInclusionCircuit)
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MstInclusionConfig::<N_CURRENCIES, N_BYTES>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        // load lookup table for range check, shared by all the paths
+        self.load::<8>(&mut layouter, config.fixed_columns[4])?;
+
+        for (i, proof) in self.proofs.iter().enumerate() {
+            let path = assign_path::<LEVELS, N_CURRENCIES, N_BYTES, 8>(
+                self,
+                layouter.namespace(|| format!("path {}", i)),
+                &config,
+                proof,
+            )?;
+
+            // expose the leaf hash of each path as public input
+            self.expose_public(
+                layouter.namespace(|| format!("public leaf hash {}", i)),
+                &path.leaf_hash,
+                i,
+                config.instance,
+            )?;
+
+            // Every path must lead to the public root
+            self.expose_public(
+                layouter.namespace(|| "public root hash"),
+                &path.root_hash,
+                BATCH_SIZE,
+                config.instance,
+            )?;
+
+            for (currency, balance) in path.root_balances.iter().enumerate() {
+                self.expose_public(
+                    layouter.namespace(|| format!("public root balance {}", currency)),
+                    balance,
+                    BATCH_SIZE + 1 + currency,
+                    config.instance,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
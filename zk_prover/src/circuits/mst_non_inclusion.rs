@@ -0,0 +1,413 @@
+use crate::chips::range::range_check::{RangeCheckChip, RangeCheckConfig};
+use crate::circuits::merkle_sum_tree::{assign_path, MstInclusionConfig};
+use crate::circuits::traits::CircuitBase;
+use crate::merkle_sum_tree::utils::{big_intify_username, big_uint_to_fp, MAX_RAW_USERNAME_BYTES};
+use crate::merkle_sum_tree::{Entry, MerkleNonInclusionProof, MerkleProof, Node};
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error, Expression, Selector};
+use halo2_proofs::poly::Rotation;
+use snark_verifier_sdk::CircuitExt;
+
+/// Circuit for verifying that a username is not in a sorted merkle sum tree with a given root.
+///
+/// The circuit verifies the inclusion of the two adjacent leaves between which the username would be inserted, as in [`MstInclusionCircuit`](crate::circuits::merkle_sum_tree::MstInclusionCircuit), and that the username lies strictly between the usernames of the two leaves.
+/// The leaves are adjacent if the index of the upper leaf, recomposed from its path indices, is the index of the lower leaf plus one.
+///
+/// Only the non-inclusion of a username surrounded by two entries is supported, namely both leaves must be unsalted entries. The usernames lower than the first entry or greater than the last entry
+/// can be checked with [`verify_non_inclusion_proof`](crate::merkle_sum_tree::verifier::verify_non_inclusion_proof).
+/// The usernames of both leaves and the gaps between them and the username are range checked to be lower than `2^248`, so that the comparisons don't wrap around the field even for a tree that wasn't built with [`big_intify_username`].
+///
+/// # Type Parameters
+///
+/// * `LEVELS`: The number of levels of the merkle sum tree
+/// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
+/// * `N_BYTES`: The number of bytes in which the balances should lie
+///
+/// # Fields
+///
+/// * `username`: The username whose absence is proven
+/// * `lower`: The merkle proof of the leaf preceding the username
+/// * `upper`: The merkle proof of the leaf following the username
+#[derive(Clone)]
+pub struct MstNonInclusionCircuit<
+    const LEVELS: usize,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+> where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub username: String,
+    pub lower: MerkleProof<N_CURRENCIES, N_BYTES>,
+    pub upper: MerkleProof<N_CURRENCIES, N_BYTES>,
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> CircuitExt<Fp>
+    for MstNonInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    /// Returns the number of public inputs of the circuit. It is {2 + N_CURRENCIES}, namely the username whose absence is proven, the root hash of the merkle sum tree and the root balances of the merkle sum tree.
+    fn num_instance(&self) -> Vec<usize> {
+        vec![{ 2 + N_CURRENCIES }]
+    }
+    /// Returns the values of the public inputs of the circuit. Namely the username whose absence is proven, the root hash and the root balances of the merkle sum tree.
+    fn instances(&self) -> Vec<Vec<Fp>> {
+        let mut instance = vec![
+            big_uint_to_fp(&big_intify_username(&self.username)),
+            self.lower.root.hash,
+        ];
+        instance.extend_from_slice(&self.lower.root.balances);
+        vec![instance]
+    }
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> CircuitBase
+    for MstNonInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
+    MstNonInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub fn init_empty() -> Self {
+        let empty_proof = MerkleProof {
+            entry: Entry::init_empty(),
+            root: Node::init_empty(),
+            sibling_leaf_node_hash_preimage: [Fp::zero(); N_CURRENCIES + 1],
+            sibling_middle_node_hash_preimages: vec![[Fp::zero(); N_CURRENCIES + 2]; LEVELS - 1],
+            path_indices: vec![Fp::zero(); LEVELS],
+        };
+
+        Self {
+            username: "".to_string(),
+            lower: empty_proof.clone(),
+            upper: empty_proof,
+        }
+    }
+
+    /// Initializes the circuit with a non-inclusion proof generated by [`MerkleSumTree::generate_non_inclusion_proof`](crate::merkle_sum_tree::MerkleSumTree::generate_non_inclusion_proof).
+    /// The proof must contain both leaves, which must be unsalted entries.
+    pub fn init(
+        proof: MerkleNonInclusionProof<N_CURRENCIES, N_BYTES>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let (lower, upper) = match (proof.lower, proof.upper) {
+            (Some(lower), Some(upper)) => (lower, upper),
+            _ => {
+                return Err(Box::from(
+                    "The circuit only supports usernames between two entries of the tree",
+                ))
+            }
+        };
+
+        for neighbour in [&lower, &upper] {
+            if neighbour.entry.username().is_empty() {
+                return Err(Box::from(
+                    "The circuit only supports usernames between two entries of the tree",
+                ));
+            }
+            if neighbour.entry.salt().is_some() {
+                return Err(Box::from("The circuit doesn't support salted entries"));
+            }
+            if neighbour.path_indices.len() != LEVELS
+                || neighbour.sibling_middle_node_hash_preimages.len() != LEVELS - 1
+            {
+                return Err(format!("The proofs must have {} levels", LEVELS).into());
+            }
+        }
+
+        Ok(Self {
+            username: proof.username,
+            lower,
+            upper,
+        })
+    }
+}
+
+/// Configuration for the Mst Non-Inclusion circuit
+/// # Type Parameters
+///
+/// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
+/// * `N_BYTES`: The number of bytes in which the balances should lie
+///
+/// # Fields
+///
+/// * `inclusion_config`: Configuration of the [`MstInclusionCircuit`](crate::circuits::merkle_sum_tree::MstInclusionCircuit), which verifies the merkle paths of both leaves
+/// * `username_range_check_config`: Configuration for the range check chip of the usernames of the leaves and of the gaps between the usernames
+/// * `gap_selector`: Selector enabling the constraint `upper - lower - 1 - gap = 0`
+/// * `leaf_index_selector`: Selector enabling the constraint `next_index - 2 * index - swap_bit = 0`, which recomposes the index of a leaf from its path indices
+#[derive(Debug, Clone)]
+pub struct MstNonInclusionConfig<const N_CURRENCIES: usize, const N_BYTES: usize>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    inclusion_config: MstInclusionConfig<N_CURRENCIES, N_BYTES>,
+    username_range_check_config: RangeCheckConfig,
+    gap_selector: Selector,
+    leaf_index_selector: Selector,
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> MstNonInclusionConfig<N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub fn configure(meta: &mut ConstraintSystem<Fp>) -> Self {
+        // the merkle paths are verified with the columns and gates of the inclusion circuit, whose fixed_column[2] is enabled for constants, as required by the initial leaf index
+        let inclusion_config = MstInclusionConfig::<N_CURRENCIES, N_BYTES>::configure(meta);
+        let advices = inclusion_config.advices;
+
+        // we need 1 selector for the gap gate and 1 for the leaf index gate
+        let selectors: [Selector; 2] = std::array::from_fn(|_| meta.selector());
+
+        // we need 1 complex selector for the lookup check of the username range check chip, which shares the lookup table of the balances range check chip
+        let enable_username_lookup_selector = meta.complex_selector();
+
+        let username_range_check_config = RangeCheckChip::configure(
+            meta,
+            advices[0],
+            inclusion_config.fixed_columns[4],
+            enable_username_lookup_selector,
+            8 * MAX_RAW_USERNAME_BYTES,
+        );
+
+        let gap_selector = selectors[0];
+
+        // The gap is range checked, so that `lower < upper` holds for any `lower` and `upper` lower than `2^248`
+        meta.create_gate("gap constraint", |meta| {
+            let s = meta.query_selector(gap_selector);
+            let lower = meta.query_advice(advices[0], Rotation::cur());
+            let upper = meta.query_advice(advices[1], Rotation::cur());
+            let gap = meta.query_advice(advices[2], Rotation::cur());
+            vec![s * (upper - lower - Expression::Constant(Fp::one()) - gap)]
+        });
+
+        let leaf_index_selector = selectors[1];
+
+        meta.create_gate("leaf index constraint", |meta| {
+            let s = meta.query_selector(leaf_index_selector);
+            let index = meta.query_advice(advices[0], Rotation::cur());
+            let swap_bit = meta.query_advice(advices[1], Rotation::cur());
+            let next_index = meta.query_advice(advices[0], Rotation::next());
+            vec![s * (next_index - index * Expression::Constant(Fp::from(2)) - swap_bit)]
+        });
+
+        Self {
+            inclusion_config,
+            username_range_check_config,
+            gap_selector,
+            leaf_index_selector,
+        }
+    }
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
+    MstNonInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    /// Assigns `upper - lower - 1`, the number of values strictly between `lower` and `upper`
+    fn assign_gap(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        config: &MstNonInclusionConfig<N_CURRENCIES, N_BYTES>,
+        lower: &AssignedCell<Fp, Fp>,
+        upper: &AssignedCell<Fp, Fp>,
+    ) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let advices = config.inclusion_config.advices;
+
+        layouter.assign_region(
+            || "assign gap",
+            |mut region| {
+                config.gap_selector.enable(&mut region, 0)?;
+
+                lower.copy_advice(|| "lower", &mut region, advices[0], 0)?;
+                upper.copy_advice(|| "upper", &mut region, advices[1], 0)?;
+
+                let gap = upper.value().copied() - lower.value().copied() - Value::known(Fp::one());
+                region.assign_advice(|| "gap", advices[2], 0, || gap)
+            },
+        )
+    }
+
+    /// Recomposes the index of a leaf from the swap bits of its path, from the leaf to the root
+    fn assign_leaf_index(
+        &self,
+        mut layouter: impl Layouter<Fp>,
+        config: &MstNonInclusionConfig<N_CURRENCIES, N_BYTES>,
+        swap_bits: &[AssignedCell<Fp, Fp>],
+    ) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let advices = config.inclusion_config.advices;
+
+        layouter.assign_region(
+            || "assign leaf index",
+            |mut region| {
+                let mut index = region.assign_advice_from_constant(
+                    || "initial index",
+                    advices[0],
+                    0,
+                    Fp::zero(),
+                )?;
+
+                // The bit of the root level is the most significant one
+                for (offset, swap_bit) in swap_bits.iter().rev().enumerate() {
+                    config.leaf_index_selector.enable(&mut region, offset)?;
+                    swap_bit.copy_advice(|| "swap bit", &mut region, advices[1], offset)?;
+
+                    let next_index = index.value().copied() * Value::known(Fp::from(2))
+                        + swap_bit.value().copied();
+                    index =
+                        region.assign_advice(|| "index", advices[0], offset + 1, || next_index)?;
+                }
+
+                Ok(index)
+            },
+        )
+    }
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> Circuit<Fp>
+    for MstNonInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    type Config = MstNonInclusionConfig<N_CURRENCIES, N_BYTES>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::init_empty()
+    }
+
+    /// Configures the circuit
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MstNonInclusionConfig::<N_CURRENCIES, N_BYTES>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let username_range_check_chip =
+            RangeCheckChip::construct(config.username_range_check_config);
+
+        // load lookup table for range check
+        self.load::<8>(&mut layouter, config.inclusion_config.fixed_columns[4])?;
+
+        let lower_path = assign_path::<LEVELS, N_CURRENCIES, N_BYTES, 8>(
+            self,
+            layouter.namespace(|| "lower path"),
+            &config.inclusion_config,
+            &self.lower,
+        )?;
+        let upper_path = assign_path::<LEVELS, N_CURRENCIES, N_BYTES, 8>(
+            self,
+            layouter.namespace(|| "upper path"),
+            &config.inclusion_config,
+            &self.upper,
+        )?;
+
+        // Both paths must lead to the public root
+        for path in [&lower_path, &upper_path] {
+            self.expose_public(
+                layouter.namespace(|| "public root hash"),
+                &path.root_hash,
+                1,
+                config.inclusion_config.instance,
+            )?;
+
+            for (i, balance) in path.root_balances.iter().enumerate() {
+                self.expose_public(
+                    layouter.namespace(|| format!("public root balance {}", i)),
+                    balance,
+                    2 + i,
+                    config.inclusion_config.instance,
+                )?;
+            }
+        }
+
+        // Assign the username whose absence is proven and expose it as public input
+        let username = self.assign_value_to_witness(
+            layouter.namespace(|| "assign username"),
+            big_uint_to_fp(&big_intify_username(&self.username)),
+            "username",
+            config.inclusion_config.advices[0],
+        )?;
+
+        self.expose_public(
+            layouter.namespace(|| "public username"),
+            &username,
+            0,
+            config.inclusion_config.instance,
+        )?;
+
+        // The usernames of both leaves must be lower than 2^248, otherwise the gaps could wrap around the field
+        username_range_check_chip.assign(
+            layouter.namespace(|| "range check lower username"),
+            &lower_path.username,
+        )?;
+        username_range_check_chip.assign(
+            layouter.namespace(|| "range check upper username"),
+            &upper_path.username,
+        )?;
+
+        // The username must lie strictly between the usernames of the two leaves
+        let lower_gap = self.assign_gap(
+            layouter.namespace(|| "lower username gap"),
+            &config,
+            &lower_path.username,
+            &username,
+        )?;
+        username_range_check_chip.assign(
+            layouter.namespace(|| "range check lower username gap"),
+            &lower_gap,
+        )?;
+
+        let upper_gap = self.assign_gap(
+            layouter.namespace(|| "upper username gap"),
+            &config,
+            &username,
+            &upper_path.username,
+        )?;
+        username_range_check_chip.assign(
+            layouter.namespace(|| "range check upper username gap"),
+            &upper_gap,
+        )?;
+
+        // The leaves must be adjacent, namely there is no leaf between them
+        let lower_index = self.assign_leaf_index(
+            layouter.namespace(|| "lower leaf index"),
+            &config,
+            &lower_path.swap_bits,
+        )?;
+        let upper_index = self.assign_leaf_index(
+            layouter.namespace(|| "upper leaf index"),
+            &config,
+            &upper_path.swap_bits,
+        )?;
+
+        let index_gap = self.assign_gap(
+            layouter.namespace(|| "leaf index gap"),
+            &config,
+            &lower_index,
+            &upper_index,
+        )?;
+        layouter.assign_region(
+            || "constrain adjacent leaves",
+            |mut region| region.constrain_constant(index_gap.cell(), Fp::zero()),
+        )?;
+
+        Ok(())
+    }
+}
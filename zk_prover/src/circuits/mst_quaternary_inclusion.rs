@@ -0,0 +1,438 @@
+use crate::chips::merkle_sum_tree::{MerkleSumTreeChip, MerkleSumTreeConfig};
+use crate::chips::poseidon::hash::{PoseidonChip, PoseidonConfig};
+use crate::chips::poseidon::poseidon_spec::PoseidonSpec;
+use crate::chips::range::range_check::{RangeCheckChip, RangeCheckConfig};
+use crate::circuits::traits::CircuitBase;
+use crate::merkle_sum_tree::utils::big_uint_to_fp;
+use crate::merkle_sum_tree::{Entry, Node, QuaternaryMerkleProof};
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use halo2_proofs::plonk::{
+    Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance, Selector,
+};
+use snark_verifier_sdk::CircuitExt;
+
+/// Circuit for verifying inclusion of an entry (username, balances) inside a 4-ary merkle sum tree with a given root, see [`QuaternaryMerkleSumTree`](crate::merkle_sum_tree::QuaternaryMerkleSumTree).
+///
+/// The circuit follows [`MstInclusionCircuit`](crate::circuits::merkle_sum_tree::MstInclusionCircuit), except that at each level the current node is placed among its 3 siblings according to 2 swap bits.
+/// It exposes the same public inputs, namely the leaf hash, the root hash and the root balances.
+///
+/// # Type Parameters
+///
+/// * `LEVELS`: The number of levels of the 4-ary merkle sum tree. For example a tree with 16 entries has 2 levels.
+/// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
+/// * `N_BYTES`: The number of bytes in which the balances should lie
+///
+/// # Fields
+///
+/// * `entry`: The entry to be verified inclusion of. For a salted entry, the leaf commits to `H(username, salt)` instead of the username.
+/// * `path_indices`: The two swap bits of each level from the leaf to the root, from the least significant one. The position of the node among its siblings is `path_indices[2 * level] + 2 * path_indices[2 * level + 1]`. The length of this vector is 2 * LEVELS
+/// * `sibling_leaf_node_hash_preimages`: The preimages of the hashes of the 3 Sibling Leaf Nodes (part of the Merkle Proof).
+/// * `sibling_middle_node_hash_preimages`: The preimages of the hashes of the 3 Sibling Middle Nodes of each level (part of the Merkle Proof).
+/// * `root`: The root of the Merkle Sum Tree
+#[derive(Clone)]
+pub struct QuaternaryMstInclusionCircuit<
+    const LEVELS: usize,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+> where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 4]: Sized,
+{
+    pub entry: Entry<N_CURRENCIES>,
+    pub path_indices: Vec<Fp>,
+    pub sibling_leaf_node_hash_preimages: Vec<[Fp; N_CURRENCIES + 1]>,
+    pub sibling_middle_node_hash_preimages: Vec<[Fp; N_CURRENCIES + 4]>,
+    pub root: Node<N_CURRENCIES>,
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> CircuitExt<Fp>
+    for QuaternaryMstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 4]: Sized,
+{
+    /// Returns the number of public inputs of the circuit. It is {2 + N_CURRENCIES}, namely the leaf hash to be verified inclusion of, the root hash of the merkle sum tree and the root balances of the merkle sum tree.
+    fn num_instance(&self) -> Vec<usize> {
+        vec![{ 2 + N_CURRENCIES }]
+    }
+    /// Returns the values of the public inputs of the circuit. Namely the leaf hash to be verified inclusion of, the root hash and the root balances of the merkle sum tree.
+    fn instances(&self) -> Vec<Vec<Fp>> {
+        let mut instance = vec![self.entry.compute_leaf().hash, self.root.hash];
+        instance.extend_from_slice(&self.root.balances);
+        vec![instance]
+    }
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> CircuitBase
+    for QuaternaryMstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 4]: Sized,
+{
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
+    QuaternaryMstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 4]: Sized,
+{
+    pub fn init_empty() -> Self {
+        Self {
+            entry: Entry::init_empty(),
+            path_indices: vec![Fp::zero(); 2 * LEVELS],
+            sibling_leaf_node_hash_preimages: vec![[Fp::zero(); N_CURRENCIES + 1]; 3],
+            sibling_middle_node_hash_preimages: vec![
+                [Fp::zero(); N_CURRENCIES + 4];
+                3 * (LEVELS - 1)
+            ],
+            root: Node::init_empty(),
+        }
+    }
+
+    /// Initializes the circuit with the merkle proof, generated by [`QuaternaryMerkleSumTree::generate_proof`](crate::merkle_sum_tree::QuaternaryMerkleSumTree::generate_proof), of the user of which the inclusion is to be verified.
+    pub fn init(merkle_proof: QuaternaryMerkleProof<N_CURRENCIES, N_BYTES>) -> Self {
+        assert_eq!(merkle_proof.path_indices.len(), 2 * LEVELS);
+        assert_eq!(merkle_proof.sibling_leaf_node_hash_preimages.len(), 3);
+        assert_eq!(
+            merkle_proof.sibling_middle_node_hash_preimages.len(),
+            3 * (LEVELS - 1)
+        );
+        Self {
+            entry: merkle_proof.entry,
+            path_indices: merkle_proof.path_indices,
+            sibling_leaf_node_hash_preimages: merkle_proof.sibling_leaf_node_hash_preimages,
+            sibling_middle_node_hash_preimages: merkle_proof.sibling_middle_node_hash_preimages,
+            root: merkle_proof.root,
+        }
+    }
+}
+
+/// Configuration for the 4-ary Mst Inclusion circuit
+/// # Type Parameters
+///
+/// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
+/// * `N_BYTES`: The number of bytes in which the balances should lie
+///
+/// # Fields
+///
+/// * `merkle_sum_tree_config`: Configuration for the 4-ary merkle sum tree chip
+/// * `poseidon_entry_config`: Configuration for the poseidon hash function with WIDTH = 2 and RATE = 1 and input length of N_CURRENCIES + 1. Needed to perform the hashing from the entries to the leaves.
+/// * `poseidon_middle_config`: Configuration for the poseidon hash function with WIDTH = 2 and RATE = 1 and input length of N_CURRENCIES + 4. Needed to perform hashings from the leaf to the root.
+/// * `range_check_config`: Configuration for the range check chip
+/// * `instance`: Instance column used to store the public inputs
+/// * `advices`: Advice columns used to store the private inputs
+#[derive(Debug, Clone)]
+pub struct QuaternaryMstInclusionConfig<const N_CURRENCIES: usize, const N_BYTES: usize>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 4]: Sized,
+{
+    merkle_sum_tree_config: MerkleSumTreeConfig,
+    poseidon_entry_config: PoseidonConfig<2, 1, { N_CURRENCIES + 1 }>,
+    poseidon_middle_config: PoseidonConfig<2, 1, { N_CURRENCIES + 4 }>,
+    range_check_config: RangeCheckConfig,
+    instance: Column<Instance>,
+    advices: [Column<Advice>; 3],
+    fixed_columns: [Column<Fixed>; 5],
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize>
+    QuaternaryMstInclusionConfig<N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 4]: Sized,
+{
+    pub fn configure(meta: &mut ConstraintSystem<Fp>) -> Self {
+        // the columns are the same as the ones of the binary MstInclusionConfig
+        let advices: [Column<Advice>; 3] = std::array::from_fn(|_| meta.advice_column());
+        let fixed_columns: [Column<Fixed>; 5] = std::array::from_fn(|_| meta.fixed_column());
+        let selectors: [Selector; 2] = std::array::from_fn(|_| meta.selector());
+        let enable_lookup_selector = meta.complex_selector();
+
+        meta.enable_constant(fixed_columns[2]);
+
+        let poseidon_entry_config =
+            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 1 }>::configure(
+                meta,
+                advices[0..2].try_into().unwrap(),
+                advices[2],
+                fixed_columns[0..2].try_into().unwrap(),
+                fixed_columns[2..4].try_into().unwrap(),
+            );
+
+        let poseidon_middle_config =
+            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 4 }>::configure(
+                meta,
+                advices[0..2].try_into().unwrap(),
+                advices[2],
+                fixed_columns[0..2].try_into().unwrap(),
+                fixed_columns[2..4].try_into().unwrap(),
+            );
+
+        for col in &advices {
+            meta.enable_equality(*col);
+        }
+
+        let merkle_sum_tree_config = MerkleSumTreeChip::<N_CURRENCIES, 4>::configure(
+            meta,
+            advices[0..3].try_into().unwrap(),
+            selectors[0..2].try_into().unwrap(),
+        );
+
+        let range_check_config = RangeCheckChip::configure(
+            meta,
+            advices[0],
+            fixed_columns[4],
+            enable_lookup_selector,
+            8 * N_BYTES,
+        );
+
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        Self {
+            merkle_sum_tree_config,
+            poseidon_entry_config,
+            poseidon_middle_config,
+            range_check_config,
+            instance,
+            advices,
+            fixed_columns,
+        }
+    }
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> Circuit<Fp>
+    for QuaternaryMstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 4]: Sized,
+{
+    type Config = QuaternaryMstInclusionConfig<N_CURRENCIES, N_BYTES>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::init_empty()
+    }
+
+    /// Configures the circuit
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        QuaternaryMstInclusionConfig::<N_CURRENCIES, N_BYTES>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        // build auxiliary chips
+        let merkle_sum_tree_chip =
+            MerkleSumTreeChip::<N_CURRENCIES, 4>::construct(config.merkle_sum_tree_config);
+
+        let poseidon_entry_chip =
+            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 1 }>::construct(
+                config.poseidon_entry_config,
+            );
+
+        let poseidon_middle_chip =
+            PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 4 }>::construct(
+                config.poseidon_middle_config,
+            );
+
+        let range_check_chip = RangeCheckChip::construct(config.range_check_config);
+
+        // Assign the entry username to the witness. For a salted entry, the leaf commits to `H(username, salt)` instead of the username
+        let mut entry_preimage = [Fp::zero(); N_CURRENCIES + 1];
+        entry_preimage[0] = self.entry.username_commitment();
+        for (i, balance) in entry_preimage.iter_mut().enumerate().skip(1) {
+            *balance = big_uint_to_fp(&self.entry.balances()[i - 1]);
+        }
+        let entry_cells =
+            self.assign_hash_preimage(&mut layouter, &entry_preimage, "entry", &config)?;
+        let mut current_balances = entry_cells[1..].to_vec();
+
+        // compute the entry hash
+        let mut current_hash = poseidon_entry_chip.hash(
+            layouter.namespace(|| "perform poseidon entry hash"),
+            entry_cells.try_into().unwrap(),
+        )?;
+
+        // expose the first current hash, namely the leaf hash, as public input
+        self.expose_public(
+            layouter.namespace(|| "public leaf hash"),
+            &current_hash,
+            0,
+            config.instance,
+        )?;
+
+        // load lookup table for range check
+        self.load::<8>(&mut layouter, config.fixed_columns[4])?;
+
+        // For level 0, perform range check on the leaf node balances. The balances of the siblings are range checked at every level
+        for (currency, balance) in current_balances.iter().enumerate() {
+            range_check_chip.assign(
+                layouter.namespace(|| format!("currency {}: range check leaf balance", currency)),
+                balance,
+            )?;
+        }
+
+        for level in 0..LEVELS {
+            let namespace_prefix = format!("level {}", level);
+
+            let mut sibling_hashes = Vec::with_capacity(3);
+            let mut sibling_balances = Vec::with_capacity(3);
+
+            // Assign the hash preimages of the 3 siblings and hash them
+            for sibling in 0..3 {
+                let name = format!("{}: sibling {}", namespace_prefix, sibling);
+                let (hash, balances) = if level == 0 {
+                    let cells = self.assign_hash_preimage(
+                        &mut layouter,
+                        &self.sibling_leaf_node_hash_preimages[sibling],
+                        &name,
+                        &config,
+                    )?;
+                    let balances = cells[1..].to_vec();
+                    let hash = poseidon_entry_chip.hash(
+                        layouter.namespace(|| format!("{}: perform poseidon hash", name)),
+                        cells.try_into().unwrap(),
+                    )?;
+                    (hash, balances)
+                } else {
+                    let cells = self.assign_hash_preimage(
+                        &mut layouter,
+                        &self.sibling_middle_node_hash_preimages[3 * (level - 1) + sibling],
+                        &name,
+                        &config,
+                    )?;
+                    let balances = cells[..N_CURRENCIES].to_vec();
+                    let hash = poseidon_middle_chip.hash(
+                        layouter.namespace(|| format!("{}: perform poseidon hash", name)),
+                        cells.try_into().unwrap(),
+                    )?;
+                    (hash, balances)
+                };
+
+                // Each sibling balance cell is constrained to be within the range defined by N_BYTES. Any risk of overflow of the `current_balances` will be checked during verification
+                for (currency, balance) in balances.iter().enumerate() {
+                    range_check_chip.assign(
+                        layouter.namespace(|| {
+                            format!("{}: currency {}: range check balance", name, currency)
+                        }),
+                        balance,
+                    )?;
+                }
+
+                sibling_hashes.push(hash);
+                sibling_balances.push(balances);
+            }
+
+            // For each level assign the two swap bits to the circuit
+            let swap_bits: [AssignedCell<Fp, Fp>; 2] = [
+                self.assign_value_to_witness(
+                    layouter.namespace(|| format!("{}: assign swap bit 0", namespace_prefix)),
+                    self.path_indices[2 * level],
+                    "swap bit 0",
+                    config.advices[0],
+                )?,
+                self.assign_value_to_witness(
+                    layouter.namespace(|| format!("{}: assign swap bit 1", namespace_prefix)),
+                    self.path_indices[2 * level + 1],
+                    "swap bit 1",
+                    config.advices[0],
+                )?,
+            ];
+
+            // For every level, place the current hash among the sibling hashes according to the swap bits
+            let hashes = merkle_sum_tree_chip.swap_hashes_per_level(
+                layouter.namespace(|| format!("{}: swap hashes", namespace_prefix)),
+                &current_hash,
+                &sibling_hashes.try_into().unwrap(),
+                &swap_bits,
+            )?;
+
+            // For every level, place the current balances among the sibling balances and sum them
+            let mut next_balances = vec![];
+            for (currency, current_balance) in current_balances.iter().enumerate() {
+                let (_, next_balance) = merkle_sum_tree_chip.swap_balances_per_level(
+                    layouter.namespace(|| {
+                        format!(
+                            "{}: currency {}: assign nodes balance",
+                            namespace_prefix, currency
+                        )
+                    }),
+                    current_balance,
+                    &std::array::from_fn(|sibling| sibling_balances[sibling][currency].clone()),
+                    &swap_bits,
+                )?;
+                next_balances.push(next_balance);
+            }
+
+            // create an hash_input array of length N_CURRENCIES + 4 that contains the next balances and the 4 hashes in order
+            let middle_hasher_input: [AssignedCell<Fp, Fp>; N_CURRENCIES + 4] = next_balances
+                .iter()
+                .chain(hashes.iter())
+                .cloned()
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap();
+
+            // compute the next hash
+            current_hash = poseidon_middle_chip.hash(
+                layouter.namespace(|| format!("{}: perform poseidon hash", namespace_prefix)),
+                middle_hasher_input,
+            )?;
+            current_balances = next_balances;
+        }
+
+        // expose the last current hash, namely the root hash, as public input
+        self.expose_public(
+            layouter.namespace(|| "public root hash"),
+            &current_hash,
+            1,
+            config.instance,
+        )?;
+
+        // expose the last current balances, namely the root balances, as public input
+        for (i, balance) in current_balances.iter().enumerate() {
+            self.expose_public(
+                layouter.namespace(|| format!("public root balance {}", i)),
+                balance,
+                2 + i,
+                config.instance,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
+    QuaternaryMstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 4]: Sized,
+{
+    /// Assigns the elements of a hash preimage to the witness, one cell per element
+    fn assign_hash_preimage(
+        &self,
+        layouter: &mut impl Layouter<Fp>,
+        preimage: &[Fp],
+        name: &str,
+        config: &QuaternaryMstInclusionConfig<N_CURRENCIES, N_BYTES>,
+    ) -> Result<Vec<AssignedCell<Fp, Fp>>, Error> {
+        preimage
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                self.assign_value_to_witness(
+                    layouter.namespace(|| format!("{}: assign preimage element {}", name, i)),
+                    *value,
+                    "hash preimage element",
+                    config.advices[i % 2],
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,325 @@
+use crate::chips::poseidon::hash::{PoseidonChip, PoseidonConfig};
+use crate::chips::poseidon::poseidon_spec::PoseidonSpec;
+use crate::chips::range::less_than_or_equal::{LessThanOrEqualChip, LessThanOrEqualConfig};
+use crate::chips::range::range_check::{RangeCheckChip, RangeCheckConfig};
+use crate::circuits::traits::CircuitBase;
+use crate::merkle_sum_tree::utils::big_uint_to_fp;
+use crate::merkle_sum_tree::{BalanceKind, Node, Tree};
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use halo2_proofs::plonk::{
+    Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance, Selector,
+};
+use num_bigint::BigUint;
+use snark_verifier_sdk::CircuitExt;
+
+/// Circuit for verifying that the liabilities committed in a merkle sum tree do not exceed the assets of the exchange, without revealing the liabilities.
+///
+/// The liabilities are the root balances of the tree, which must not have debt columns. They are private witnesses, bound to the public root hash by hashing them together with the hashes of the two children of the root.
+/// For each currency, the circuit proves that `liabilities[i] <= assets[i]` using a [`LessThanOrEqualChip`]. The liabilities are range checked to `N_BYTES`, as required by the chip.
+///
+/// # Type Parameters
+///
+/// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
+/// * `N_BYTES`: The number of bytes in which the liabilities and the assets should lie
+///
+/// # Fields
+///
+/// * `root_hash_preimage`: The preimage of the root hash, namely the root balances followed by the hashes of the left and right children of the root
+/// * `assets`: The total assets of the exchange for each currency
+#[derive(Clone)]
+pub struct SolvencyCircuit<const N_CURRENCIES: usize, const N_BYTES: usize>
+where
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub root_hash_preimage: [Fp; N_CURRENCIES + 2],
+    pub assets: [Fp; N_CURRENCIES],
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> CircuitExt<Fp>
+    for SolvencyCircuit<N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    /// Returns the number of public inputs of the circuit. It is {1 + N_CURRENCIES}, namely the root hash of the merkle sum tree and the assets of the exchange.
+    fn num_instance(&self) -> Vec<usize> {
+        vec![{ 1 + N_CURRENCIES }]
+    }
+    /// Returns the values of the public inputs of the circuit. Namely the root hash of the merkle sum tree, followed by the assets of the exchange for each currency.
+    fn instances(&self) -> Vec<Vec<Fp>> {
+        let root = Node::<N_CURRENCIES>::middle_node_from_preimage(&self.root_hash_preimage);
+        let mut instance = vec![root.hash];
+        instance.extend_from_slice(&self.assets);
+        vec![instance]
+    }
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> CircuitBase
+    for SolvencyCircuit<N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> SolvencyCircuit<N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub fn init_empty() -> Self {
+        Self {
+            root_hash_preimage: [Fp::zero(); N_CURRENCIES + 2],
+            assets: [Fp::zero(); N_CURRENCIES],
+        }
+    }
+
+    /// Initializes the circuit with the root of the merkle sum tree and the assets of the exchange for each currency.
+    /// The assets must lie in `N_BYTES`. Note that the circuit is not satisfied if the liabilities of any currency exceed its assets.
+    ///
+    /// Every column of the tree must hold liabilities. The root sums of [`BalanceKind::Debt`] columns are owed by the users to the custodian, so a tree with debt columns is rejected
+    /// instead of comparing its debts with assets.
+    pub fn init<T: Tree<N_CURRENCIES, N_BYTES>>(
+        merkle_sum_tree: &T,
+        assets: &[BigUint; N_CURRENCIES],
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if let Some(debt) = merkle_sum_tree
+            .cryptocurrencies()
+            .iter()
+            .find(|cryptocurrency| cryptocurrency.kind == BalanceKind::Debt)
+        {
+            return Err(format!(
+                "The solvency circuit doesn't support debt columns, found {}",
+                debt.column_name()
+            )
+            .into());
+        }
+
+        let root_hash_preimage =
+            merkle_sum_tree.get_middle_node_hash_preimage(*merkle_sum_tree.depth(), 0)?;
+
+        for (currency, asset) in assets.iter().enumerate() {
+            if asset.bits() > 8 * N_BYTES as u64 {
+                return Err(format!(
+                    "The assets of currency {} exceed the range of {} bytes",
+                    currency, N_BYTES
+                )
+                .into());
+            }
+        }
+
+        Ok(Self {
+            root_hash_preimage,
+            assets: assets
+                .iter()
+                .map(big_uint_to_fp)
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap(),
+        })
+    }
+}
+
+/// Configuration for the Solvency circuit
+/// # Type Parameters
+///
+/// * `N_CURRENCIES`: The number of currencies for which the solvency is verified.
+/// * `N_BYTES`: The number of bytes in which the liabilities and the assets should lie
+///
+/// # Fields
+///
+/// * `poseidon_config`: Configuration for the poseidon hash function with WIDTH = 2 and RATE = 1 and input length of N_CURRENCIES + 2. Needed to compute the root hash.
+/// * `range_check_config`: Configuration for the range check chip
+/// * `less_than_or_equal_config`: Configuration for the less than or equal chip
+/// * `instance`: Instance column used to store the public inputs
+/// * `advices`: Advice columns used to store the private inputs
+/// * `fixed_columns`: Fixed columns used by the poseidon chip and the lookup table of the range check chip
+#[derive(Debug, Clone)]
+pub struct SolvencyConfig<const N_CURRENCIES: usize, const N_BYTES: usize>
+where
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    poseidon_config: PoseidonConfig<2, 1, { N_CURRENCIES + 2 }>,
+    range_check_config: RangeCheckConfig,
+    less_than_or_equal_config: LessThanOrEqualConfig,
+    instance: Column<Instance>,
+    advices: [Column<Advice>; 3],
+    fixed_columns: [Column<Fixed>; 5],
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> SolvencyConfig<N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub fn configure(meta: &mut ConstraintSystem<Fp>) -> Self {
+        // the max number of advices columns needed is WIDTH + 1 given requirement of the poseidon config
+        let advices: [Column<Advice>; 3] = std::array::from_fn(|_| meta.advice_column());
+
+        // we need 2 * WIDTH fixed columns for poseidon config + 1 for the range check chip
+        let fixed_columns: [Column<Fixed>; 5] = std::array::from_fn(|_| meta.fixed_column());
+
+        // we need 1 selector for the difference constraint of the LessThanOrEqualChip
+        let diff_selector: Selector = meta.selector();
+
+        // we need 1 complex selector for the lookup check in the range check chip
+        let enable_lookup_selector = meta.complex_selector();
+
+        // enable constant for the fixed_column[2], this is required for the poseidon chip and the range check chip
+        meta.enable_constant(fixed_columns[2]);
+
+        let poseidon_config = PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 2 }>::configure(
+            meta,
+            advices[0..2].try_into().unwrap(),
+            advices[2],
+            fixed_columns[0..2].try_into().unwrap(),
+            fixed_columns[2..4].try_into().unwrap(),
+        );
+
+        // enable permutation for all the advice columns
+        for col in &advices {
+            meta.enable_equality(*col);
+        }
+
+        let range_check_config = RangeCheckChip::configure(
+            meta,
+            advices[0],
+            fixed_columns[4],
+            enable_lookup_selector,
+            8 * N_BYTES,
+        );
+
+        let less_than_or_equal_config =
+            LessThanOrEqualChip::configure(meta, advices, diff_selector, range_check_config);
+
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        Self {
+            poseidon_config,
+            range_check_config,
+            less_than_or_equal_config,
+            instance,
+            advices,
+            fixed_columns,
+        }
+    }
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> Circuit<Fp>
+    for SolvencyCircuit<N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    type Config = SolvencyConfig<N_CURRENCIES, N_BYTES>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::init_empty()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        SolvencyConfig::<N_CURRENCIES, N_BYTES>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        // build the chips
+        let poseidon_chip = PoseidonChip::<PoseidonSpec, 2, 1, { N_CURRENCIES + 2 }>::construct(
+            config.poseidon_config,
+        );
+
+        let range_check_chip = RangeCheckChip::construct(config.range_check_config);
+
+        let less_than_or_equal_chip =
+            LessThanOrEqualChip::construct(config.less_than_or_equal_config);
+
+        // load lookup table for range check
+        self.load::<8>(&mut layouter, config.fixed_columns[4])?;
+
+        // Assign the liabilities, namely the root balances, to the witness
+        let mut liabilities = vec![];
+
+        for (currency, balance) in self
+            .root_hash_preimage
+            .iter()
+            .take(N_CURRENCIES)
+            .enumerate()
+        {
+            let liability = self.assign_value_to_witness(
+                layouter.namespace(|| format!("assign liability {}", currency)),
+                *balance,
+                "liability",
+                config.advices[0],
+            )?;
+
+            // Each liability is constrained to be within the range defined by N_BYTES
+            range_check_chip.assign(
+                layouter.namespace(|| format!("currency {}: range check liability", currency)),
+                &liability,
+            )?;
+
+            liabilities.push(liability);
+        }
+
+        let left_child_hash = self.assign_value_to_witness(
+            layouter.namespace(|| "assign left child hash"),
+            self.root_hash_preimage[N_CURRENCIES],
+            "left child hash",
+            config.advices[1],
+        )?;
+
+        let right_child_hash = self.assign_value_to_witness(
+            layouter.namespace(|| "assign right child hash"),
+            self.root_hash_preimage[N_CURRENCIES + 1],
+            "right child hash",
+            config.advices[1],
+        )?;
+
+        let root_hasher_input: [AssignedCell<Fp, Fp>; N_CURRENCIES + 2] = liabilities
+            .iter()
+            .cloned()
+            .chain([left_child_hash, right_child_hash])
+            .collect::<Vec<_>>()
+            .try_into()
+            .expect("Failed to convert Vec to Array");
+
+        // compute the root hash, which binds the liabilities to the public root
+        let root_hash = poseidon_chip.hash(
+            layouter.namespace(|| "perform poseidon root hash"),
+            root_hasher_input,
+        )?;
+
+        self.expose_public(
+            layouter.namespace(|| "public root hash"),
+            &root_hash,
+            0,
+            config.instance,
+        )?;
+
+        for (currency, liability) in liabilities.iter().enumerate() {
+            let asset = self.assign_value_to_witness(
+                layouter.namespace(|| format!("assign asset {}", currency)),
+                self.assets[currency],
+                "asset",
+                config.advices[1],
+            )?;
+
+            self.expose_public(
+                layouter.namespace(|| format!("public asset {}", currency)),
+                &asset,
+                1 + currency,
+                config.instance,
+            )?;
+
+            // enforce that the liability does not exceed the asset
+            less_than_or_equal_chip.assign(
+                layouter.namespace(|| format!("currency {}: liability <= asset", currency)),
+                liability,
+                &asset,
+            )?;
+        }
+
+        Ok(())
+    }
+}
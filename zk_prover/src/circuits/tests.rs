@@ -1,26 +1,42 @@
 #[cfg(test)]
 mod test {
 
-    use crate::merkle_sum_tree::{MerkleSumTree, Tree};
+    use crate::merkle_sum_tree::utils::{fp_to_big_uint, parse_csv_to_entries};
+    use crate::merkle_sum_tree::{
+        AggregationMerkleSumTree, BalanceKind, DynamicMerkleSumTree, MerkleSumTree,
+        QuaternaryMerkleSumTree, Tree,
+    };
     use crate::{
         circuits::{
+            aggregation::{
+                aggregate_inclusion_snarks, gen_inclusion_snark, succinct_verify_snarks,
+                InclusionAggregationCircuit,
+            },
+            dynamic::DynamicMstInclusionCircuit,
             merkle_sum_tree::MstInclusionCircuit,
-            utils::{full_prover, full_verifier, generate_setup_artifacts},
+            mst_batch_inclusion::MstBatchInclusionCircuit,
+            mst_non_inclusion::MstNonInclusionCircuit,
+            mst_quaternary_inclusion::QuaternaryMstInclusionCircuit,
+            solvency::SolvencyCircuit,
+            utils::{full_prover, full_verifier, generate_setup_artifacts, measure_k},
         },
         merkle_sum_tree::Entry,
     };
     use halo2_proofs::{
         dev::{FailureLocation, MockProver, VerifyFailure},
-        halo2curves::bn256::Fr as Fp,
-        plonk::Any,
+        halo2curves::bn256::{Bn256, Fr as Fp, G1Affine},
+        plonk::{Any, ProvingKey},
+        poly::kzg::commitment::ParamsKZG,
     };
     use num_bigint::ToBigUint;
-    use snark_verifier_sdk::CircuitExt;
+    use snark_verifier_sdk::{CircuitExt, Snark, LIMBS};
 
     const N_CURRENCIES: usize = 2;
     const LEVELS: usize = 4;
     const N_BYTES: usize = 14;
     const K: u32 = 11;
+    // The size of the circuit aggregating the inclusion snarks
+    const AGGREGATION_K: u32 = 22;
 
     #[test]
     fn test_valid_merkle_sum_tree() {
@@ -42,6 +58,564 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_valid_merkle_sum_tree_with_limb_bits() {
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        // The balances lie in 8 * N_BYTES = 112 bits, namely 11 limbs of 10 bits and a top limb of 2 bits
+        for user_index in [0, 15] {
+            let merkle_proof = merkle_sum_tree.generate_proof(user_index).unwrap();
+
+            let circuit =
+                MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES, 10>::init(merkle_proof);
+
+            let valid_prover = MockProver::run(K, &circuit, circuit.instances()).unwrap();
+            valid_prover.assert_satisfied();
+        }
+
+        // The lookup table of 2^10 rows doesn't fit in a circuit of 2^10 rows
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES, 10>::init(
+            merkle_sum_tree.generate_proof(0).unwrap(),
+        );
+        assert!(MockProver::run(10, &circuit, circuit.instances()).is_err());
+    }
+
+    #[test]
+    fn test_valid_merkle_sum_tree_with_padding() {
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+
+        // 11 entries are padded up to 16 leaves, so the tree still has 4 levels
+        let merkle_sum_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries[..11].to_vec(),
+            cryptocurrencies,
+            false,
+        )
+        .unwrap();
+
+        for user_index in 0..11 {
+            let merkle_proof = merkle_sum_tree.generate_proof(user_index).unwrap();
+
+            let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+
+            let valid_prover = MockProver::run(K, &circuit, circuit.instances()).unwrap();
+
+            valid_prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_valid_aggregation_merkle_sum_tree() {
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+
+        // 4 mini-trees of 2 levels are aggregated into a tree of 4 levels
+        let mini_trees = entries
+            .chunks(4)
+            .map(|shard| {
+                MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+                    shard.to_vec(),
+                    cryptocurrencies.clone(),
+                    false,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let aggregation_mst =
+            AggregationMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(mini_trees).unwrap();
+
+        for user_index in 0..16 {
+            let merkle_proof = aggregation_mst.generate_proof(user_index).unwrap();
+
+            let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+
+            let valid_prover = MockProver::run(K, &circuit, circuit.instances()).unwrap();
+
+            valid_prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_valid_salted_merkle_sum_tree() {
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+
+        let salted_entries = entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                Entry::new_salted(
+                    entry.username().to_string(),
+                    entry.balances().clone(),
+                    Fp::from(1000 + i as u64),
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let merkle_sum_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            salted_entries,
+            cryptocurrencies,
+            false,
+        )
+        .unwrap();
+
+        let merkle_proof = merkle_sum_tree.generate_proof(0).unwrap();
+
+        // The public leaf hash commits to the salted username
+        let leaf_hash = merkle_proof.entry.compute_leaf().hash;
+
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+        assert_eq!(circuit.instances()[0][0], leaf_hash);
+
+        let valid_prover = MockProver::run(K, &circuit, circuit.instances()).unwrap();
+
+        valid_prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_valid_merkle_sum_tree_with_debt_column() {
+        // The second balance column of the fixture holds the amounts owed by the users
+        let csv = std::fs::read_to_string("../csv/entry_16.csv").unwrap();
+        let (_, rows) = csv.split_once('\n').unwrap();
+        let path = std::env::temp_dir().join("summa_test_circuit_debt_column.csv");
+        std::fs::write(
+            &path,
+            format!("username,balance_ETH_ETH,debt_ETH_ETH\n{}", rows),
+        )
+        .unwrap();
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            merkle_sum_tree.cryptocurrencies()[0].kind,
+            BalanceKind::Liability
+        );
+        assert_eq!(
+            merkle_sum_tree.cryptocurrencies()[1].kind,
+            BalanceKind::Debt
+        );
+
+        // The liabilities and the debts are summed in separate root balances, rather than netted
+        let mut total_liabilities = 0.to_biguint().unwrap();
+        let mut total_debt = 0.to_biguint().unwrap();
+        for index in 0..merkle_sum_tree.entries_count() {
+            let entry = merkle_sum_tree.get_entry(index).unwrap();
+            total_liabilities += &entry.balances()[0];
+            total_debt += &entry.balances()[1];
+        }
+        let root_balances = merkle_sum_tree.root().balances;
+        assert_eq!(fp_to_big_uint(root_balances[0]), total_liabilities);
+        assert_eq!(fp_to_big_uint(root_balances[1]), total_debt);
+
+        // The user holds a liability and owes a debt at the same time
+        let merkle_proof = merkle_sum_tree.generate_proof(0).unwrap();
+        assert!(merkle_proof.entry.balances()[0] > 0.to_biguint().unwrap());
+        assert!(merkle_proof.entry.balances()[1] > 0.to_biguint().unwrap());
+
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+
+        // Both root balances are exposed as separate public inputs
+        assert_eq!(circuit.instances()[0][2..], root_balances[..]);
+
+        let valid_prover = MockProver::run(K, &circuit, circuit.instances()).unwrap();
+
+        valid_prover.assert_satisfied();
+
+        // Netting the debt against the liabilities in the public inputs should fail
+        let mut netted_instances = circuit.instances();
+        netted_instances[0][2] = root_balances[0] - root_balances[1];
+        netted_instances[0][3] = Fp::zero();
+        let invalid_prover = MockProver::run(K, &circuit, netted_instances).unwrap();
+        assert!(invalid_prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_valid_dynamic_merkle_sum_tree() {
+        let dynamic_tree = DynamicMerkleSumTree::<N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        // The circuit shape is selected from the number of currencies of the tree
+        let circuit =
+            DynamicMstInclusionCircuit::<LEVELS, N_BYTES>::init(&dynamic_tree, 0).unwrap();
+        assert_eq!(circuit.n_currencies(), N_CURRENCIES);
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+        let expected_circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(
+            merkle_sum_tree.generate_proof(0).unwrap(),
+        );
+        assert_eq!(circuit.instances(), expected_circuit.instances());
+
+        match circuit {
+            DynamicMstInclusionCircuit::Currencies2(circuit) => {
+                let valid_prover = MockProver::run(K, &circuit, circuit.instances()).unwrap();
+                valid_prover.assert_satisfied();
+            }
+            _ => panic!("Unexpected circuit shape"),
+        }
+
+        // The depth of the tree must match the levels of the circuit
+        assert!(DynamicMstInclusionCircuit::<3, N_BYTES>::init(&dynamic_tree, 0).is_err());
+        assert!(DynamicMstInclusionCircuit::<LEVELS, N_BYTES>::init_empty(9).is_err());
+    }
+
+    #[test]
+    fn test_valid_non_inclusion() {
+        // The non-inclusion circuit verifies two merkle paths, so it needs twice as many rows
+        let k = K + 1;
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new_sorted("../csv/entry_16.csv").unwrap();
+
+        let proof = merkle_sum_tree
+            .generate_non_inclusion_proof("Pablo123")
+            .unwrap();
+        let circuit = MstNonInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(proof).unwrap();
+
+        assert_eq!(circuit.instances()[0].len(), circuit.num_instance()[0]);
+        assert_eq!(circuit.instances()[0][1], merkle_sum_tree.root().hash);
+
+        let valid_prover = MockProver::run(k, &circuit, circuit.instances()).unwrap();
+        valid_prover.assert_satisfied();
+
+        // The proof can't be used for a username outside of the two leaves
+        let mut invalid_circuit = circuit.clone();
+        invalid_circuit.username = "RkLzkDun".to_string();
+        let invalid_prover =
+            MockProver::run(k, &invalid_circuit, invalid_circuit.instances()).unwrap();
+        assert!(invalid_prover.verify().is_err());
+
+        // The leaves must be adjacent
+        let mut invalid_circuit = circuit.clone();
+        invalid_circuit.upper = merkle_sum_tree.generate_proof(6).unwrap();
+        let invalid_prover =
+            MockProver::run(k, &invalid_circuit, invalid_circuit.instances()).unwrap();
+        assert!(invalid_prover.verify().is_err());
+
+        // The usernames lower than the first entry or greater than the last entry are not supported
+        for username in ["AAAAAAAA", "zzzzzzzz"] {
+            let proof = merkle_sum_tree
+                .generate_non_inclusion_proof(username)
+                .unwrap();
+            assert!(MstNonInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(proof).is_err());
+        }
+    }
+
+    #[test]
+    fn test_valid_batch_inclusion() {
+        // The batch circuit verifies 4 merkle paths, so it needs 4 times as many rows
+        const BATCH_SIZE: usize = 4;
+        let k = K + 2;
+
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        let proofs: Vec<_> = [0, 5, 6, 15]
+            .iter()
+            .map(|index| merkle_sum_tree.generate_proof(*index).unwrap())
+            .collect();
+
+        let circuit = MstBatchInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES, BATCH_SIZE>::init(
+            proofs.clone(),
+        )
+        .unwrap();
+
+        assert_eq!(circuit.instances()[0].len(), circuit.num_instance()[0]);
+        assert_eq!(circuit.instances()[0].len(), BATCH_SIZE + 1 + N_CURRENCIES);
+        assert_eq!(circuit.instances()[0][1], merkle_sum_tree.leaves()[5].hash);
+        assert_eq!(
+            circuit.instances()[0][BATCH_SIZE],
+            merkle_sum_tree.root().hash
+        );
+
+        let valid_prover = MockProver::run(k, &circuit, circuit.instances()).unwrap();
+        valid_prover.assert_satisfied();
+
+        // A user can be repeated to fill the batch
+        let mut repeated_proofs = proofs.clone();
+        repeated_proofs[3] = repeated_proofs[2].clone();
+        let repeated_circuit =
+            MstBatchInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES, BATCH_SIZE>::init(
+                repeated_proofs,
+            )
+            .unwrap();
+        let valid_prover =
+            MockProver::run(k, &repeated_circuit, repeated_circuit.instances()).unwrap();
+        valid_prover.assert_satisfied();
+
+        // Every leaf hash must match its public input
+        let mut invalid_instances = circuit.instances();
+        invalid_instances[0][2] = Fp::from(1000u64);
+        let invalid_prover = MockProver::run(k, &circuit, invalid_instances).unwrap();
+        assert!(invalid_prover.verify().is_err());
+
+        // Every path must lead to the same root
+        let mut invalid_circuit = circuit.clone();
+        invalid_circuit.proofs[1].sibling_leaf_node_hash_preimage[1] += Fp::one();
+        let invalid_prover = MockProver::run(k, &invalid_circuit, circuit.instances()).unwrap();
+        assert!(invalid_prover.verify().is_err());
+
+        // The batch must have exactly BATCH_SIZE proofs against the same root
+        assert!(
+            MstBatchInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES, BATCH_SIZE>::init(
+                proofs[..3].to_vec()
+            )
+            .is_err()
+        );
+
+        let other_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16_modified.csv").unwrap();
+        let mut mixed_proofs = proofs;
+        mixed_proofs[3] = other_tree.generate_proof(15).unwrap();
+        assert!(
+            MstBatchInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES, BATCH_SIZE>::init(
+                mixed_proofs
+            )
+            .is_err()
+        );
+
+        // An empty batch has no root to expose
+        assert!(
+            MstBatchInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES, 0>::init(vec![]).is_err()
+        );
+    }
+
+    // Generates the snarks of the inclusion proofs of the users at `user_indices`
+    fn gen_inclusion_snarks(
+        merkle_sum_tree: &MerkleSumTree<N_CURRENCIES, N_BYTES>,
+        params: &ParamsKZG<Bn256>,
+        pk: &ProvingKey<G1Affine>,
+        user_indices: &[usize],
+    ) -> Vec<Snark> {
+        user_indices
+            .iter()
+            .map(|user_index| {
+                let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(
+                    merkle_sum_tree.generate_proof(*user_index).unwrap(),
+                );
+                gen_inclusion_snark(params, pk, circuit)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_aggregate_inclusion_snarks() {
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        let (params, pk, _) = generate_setup_artifacts(
+            K,
+            None,
+            MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty(),
+        )
+        .unwrap();
+        let snarks = gen_inclusion_snarks(&merkle_sum_tree, &params, &pk, &[0, 3]);
+
+        let aggregation_circuit =
+            aggregate_inclusion_snarks::<N_CURRENCIES>(&params, snarks.clone()).unwrap();
+        assert_eq!(
+            aggregation_circuit.instances()[0].len(),
+            aggregation_circuit.num_instance()[0]
+        );
+
+        // The accumulator is followed by the leaf hashes, the root hash and the root balances
+        let instances = aggregation_circuit.instances()[0].clone();
+        let public_inputs = &instances[4 * LIMBS..];
+        assert_eq!(public_inputs[0], merkle_sum_tree.leaves()[0].hash);
+        assert_eq!(public_inputs[1], merkle_sum_tree.leaves()[3].hash);
+        assert_eq!(public_inputs[2], merkle_sum_tree.root().hash);
+        assert_eq!(public_inputs[3..], merkle_sum_tree.root().balances);
+
+        // The snarks must be of the same round
+        let other_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16_modified.csv").unwrap();
+        let mut mixed_snarks = snarks.clone();
+        mixed_snarks.extend(gen_inclusion_snarks(&other_tree, &params, &pk, &[0]));
+        assert!(aggregate_inclusion_snarks::<N_CURRENCIES>(&params, mixed_snarks).is_err());
+
+        // A malformed proof is reported as an error by the native verification
+        let mut truncated_snarks = snarks;
+        let proof_len = truncated_snarks[1].proof.len();
+        truncated_snarks[1].proof.truncate(proof_len / 2);
+        assert!(aggregate_inclusion_snarks::<N_CURRENCIES>(&params, truncated_snarks).is_err());
+
+        assert!(aggregate_inclusion_snarks::<N_CURRENCIES>(&params, vec![]).is_err());
+    }
+
+    // The aggregation circuit needs `2^AGGREGATION_K` rows, so its MockProver runs are too slow and memory hungry to run by default
+    #[test]
+    #[ignore]
+    fn test_inclusion_aggregation_circuit() {
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        let (params, pk, _) = generate_setup_artifacts(
+            K,
+            None,
+            MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty(),
+        )
+        .unwrap();
+        let snarks = gen_inclusion_snarks(&merkle_sum_tree, &params, &pk, &[0, 3]);
+
+        let aggregation_circuit =
+            aggregate_inclusion_snarks::<N_CURRENCIES>(&params, snarks.clone()).unwrap();
+        let valid_prover = MockProver::run(
+            AGGREGATION_K,
+            &aggregation_circuit,
+            aggregation_circuit.instances(),
+        )
+        .unwrap();
+        valid_prover.assert_satisfied();
+
+        // Skipping the check of `aggregate_inclusion_snarks`, the circuit itself rejects snarks of different rounds
+        let other_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16_modified.csv").unwrap();
+        let mut mixed_snarks = snarks;
+        mixed_snarks.extend(gen_inclusion_snarks(&other_tree, &params, &pk, &[0]));
+        let accumulators = succinct_verify_snarks(&params, &mixed_snarks).unwrap();
+        let mixed_circuit =
+            InclusionAggregationCircuit::<N_CURRENCIES>::new(&params, mixed_snarks, accumulators)
+                .unwrap();
+        let invalid_prover =
+            MockProver::run(AGGREGATION_K, &mixed_circuit, mixed_circuit.instances()).unwrap();
+        assert!(invalid_prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_valid_solvency() {
+        let merkle_sum_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        let liabilities: [_; N_CURRENCIES] =
+            std::array::from_fn(|i| fp_to_big_uint(merkle_sum_tree.root().balances[i]));
+
+        // The assets can be exactly equal to the liabilities
+        let circuit =
+            SolvencyCircuit::<N_CURRENCIES, N_BYTES>::init(&merkle_sum_tree, &liabilities).unwrap();
+
+        // The liabilities are not part of the public inputs
+        assert_eq!(circuit.instances()[0].len(), circuit.num_instance()[0]);
+        assert_eq!(circuit.instances()[0].len(), 1 + N_CURRENCIES);
+        assert_eq!(circuit.instances()[0][0], merkle_sum_tree.root().hash);
+
+        let valid_prover = MockProver::run(K, &circuit, circuit.instances()).unwrap();
+        valid_prover.assert_satisfied();
+
+        let assets: [_; N_CURRENCIES] =
+            std::array::from_fn(|i| &liabilities[i] + 1000.to_biguint().unwrap());
+        let circuit =
+            SolvencyCircuit::<N_CURRENCIES, N_BYTES>::init(&merkle_sum_tree, &assets).unwrap();
+        let valid_prover = MockProver::run(K, &circuit, circuit.instances()).unwrap();
+        valid_prover.assert_satisfied();
+
+        // The root hash must match the public input
+        let mut invalid_instances = circuit.instances();
+        invalid_instances[0][0] = Fp::from(1000u64);
+        let invalid_prover = MockProver::run(K, &circuit, invalid_instances).unwrap();
+        assert!(invalid_prover.verify().is_err());
+
+        // The liabilities are bound to the root hash
+        let mut invalid_circuit = circuit.clone();
+        invalid_circuit.root_hash_preimage[0] -= Fp::one();
+        let invalid_prover = MockProver::run(K, &invalid_circuit, circuit.instances()).unwrap();
+        assert!(invalid_prover.verify().is_err());
+
+        // The liabilities of a currency exceed its assets
+        let mut insolvent_assets = liabilities.clone();
+        insolvent_assets[1] -= 1.to_biguint().unwrap();
+        let insolvent_circuit =
+            SolvencyCircuit::<N_CURRENCIES, N_BYTES>::init(&merkle_sum_tree, &insolvent_assets)
+                .unwrap();
+        let invalid_prover =
+            MockProver::run(K, &insolvent_circuit, insolvent_circuit.instances()).unwrap();
+        assert!(invalid_prover.verify().is_err());
+
+        // The assets must lie in N_BYTES
+        let mut overflowing_assets = liabilities;
+        overflowing_assets[0] = 1.to_biguint().unwrap() << (8 * N_BYTES);
+        assert!(SolvencyCircuit::<N_CURRENCIES, N_BYTES>::init(
+            &merkle_sum_tree,
+            &overflowing_assets
+        )
+        .is_err());
+
+        // The root sums of a debt column are not liabilities
+        let mut cryptocurrencies = merkle_sum_tree.cryptocurrencies().to_vec();
+        cryptocurrencies[1].kind = BalanceKind::Debt;
+        let tree_with_debt = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            merkle_sum_tree.entries().to_vec(),
+            cryptocurrencies,
+            false,
+        )
+        .unwrap();
+        assert!(
+            SolvencyCircuit::<N_CURRENCIES, N_BYTES>::init(&tree_with_debt, &liabilities).is_err()
+        );
+    }
+
+    #[test]
+    fn test_valid_quaternary_merkle_sum_tree() {
+        // A 4-ary tree of 16 entries has half the levels of the binary tree, but each level hashes 3 siblings with longer preimages
+        const QUATERNARY_LEVELS: usize = LEVELS / 2;
+        let k = K + 1;
+
+        let merkle_sum_tree =
+            QuaternaryMerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        for user_index in 0..16 {
+            let merkle_proof = merkle_sum_tree.generate_proof(user_index).unwrap();
+
+            let circuit =
+                QuaternaryMstInclusionCircuit::<QUATERNARY_LEVELS, N_CURRENCIES, N_BYTES>::init(
+                    merkle_proof,
+                );
+
+            let valid_prover = MockProver::run(k, &circuit, circuit.instances()).unwrap();
+            valid_prover.assert_satisfied();
+        }
+
+        // The 4-ary circuit doesn't take fewer rows than the binary one for the same entries
+        let binary_circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+        let quaternary_circuit =
+            QuaternaryMstInclusionCircuit::<QUATERNARY_LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
+        let binary_k = measure_k(&binary_circuit, k).unwrap();
+        assert!(binary_k <= K);
+        assert!(measure_k(&quaternary_circuit, k).unwrap() >= binary_k);
+        // The range check table alone takes 2^8 rows
+        assert!(measure_k(&binary_circuit, 8).is_err());
+
+        let merkle_proof = merkle_sum_tree.generate_proof(6).unwrap();
+        let circuit =
+            QuaternaryMstInclusionCircuit::<QUATERNARY_LEVELS, N_CURRENCIES, N_BYTES>::init(
+                merkle_proof,
+            );
+
+        // Moving the node to another position among its siblings changes the root
+        let mut invalid_circuit = circuit.clone();
+        invalid_circuit.path_indices[0] = Fp::one();
+        let invalid_prover =
+            MockProver::run(k, &invalid_circuit, invalid_circuit.instances()).unwrap();
+        assert!(invalid_prover.verify().is_err());
+
+        // The swap bits must be binary
+        let mut invalid_circuit = circuit.clone();
+        invalid_circuit.path_indices[1] = Fp::from(2);
+        let invalid_prover =
+            MockProver::run(k, &invalid_circuit, invalid_circuit.instances()).unwrap();
+        assert!(invalid_prover.verify().is_err());
+
+        // The root balances must be the sum of the balances
+        let mut invalid_circuit = circuit.clone();
+        invalid_circuit.sibling_leaf_node_hash_preimages[0][1] += Fp::one();
+        let invalid_prover =
+            MockProver::run(k, &invalid_circuit, invalid_circuit.instances()).unwrap();
+        assert!(invalid_prover.verify().is_err());
+    }
+
     #[test]
     fn test_valid_merkle_sum_tree_with_full_prover() {
         let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init_empty();
@@ -60,7 +634,7 @@ mod test {
         let user_index = 0;
 
         let merkle_proof = merkle_sum_tree.generate_proof(user_index).unwrap();
-        let user_entry = merkle_sum_tree.get_entry(user_index);
+        let user_entry = merkle_sum_tree.get_entry(user_index).unwrap();
 
         // Only now we can instantiate the circuit with the actual inputs
         let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
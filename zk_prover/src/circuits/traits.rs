@@ -1,3 +1,4 @@
+use crate::chips::range::range_check::RangeCheckChip;
 use halo2_proofs::circuit::{Layouter, Value};
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
 use halo2_proofs::plonk::{Advice, Column, Error, Fixed};
@@ -31,23 +32,13 @@ pub trait CircuitBase {
         )
     }
 
-    /// Loads the lookup table with values from `0` to `2^8 - 1`
-    fn load(&self, layouter: &mut impl Layouter<Fp>, column: Column<Fixed>) -> Result<(), Error> {
-        let range = 1 << 8;
-
-        layouter.assign_region(
-            || format!("load range check table of {} bits", 8),
-            |mut region| {
-                for i in 0..range {
-                    region.assign_fixed(
-                        || "assign cell in fixed column",
-                        column,
-                        i,
-                        || Value::known(Fp::from(i as u64)),
-                    )?;
-                }
-                Ok(())
-            },
-        )
+    /// Loads the lookup table with values from `0` to `2^LIMB_BITS - 1`, as required by a [`RangeCheckChip`] with limbs of `LIMB_BITS` bits.
+    /// `LIMB_BITS` must match the limb width of the range check configs using the table, otherwise the range checks will fail.
+    fn load<const LIMB_BITS: usize>(
+        &self,
+        layouter: &mut impl Layouter<Fp>,
+        column: Column<Fixed>,
+    ) -> Result<(), Error> {
+        RangeCheckChip::<LIMB_BITS>::load(layouter, column)
     }
 }
@@ -7,6 +7,7 @@ use std::{
 use ark_std::{end_timer, start_timer};
 use ethers::types::{Bytes, U256};
 use halo2_proofs::{
+    dev::MockProver,
     halo2curves::{
         bn256::{Bn256, Fr as Fp, G1Affine},
         ff::PrimeField,
@@ -83,6 +84,19 @@ pub fn generate_setup_artifacts<C: Circuit<Fp> + CircuitExt<Fp>>(
     Ok((params, pk, vk))
 }
 
+/// Measures the smallest `k` for which `circuit` fits in 2^k rows, by running the `MockProver` for increasing values of `k` up to `max_k`.
+///
+/// A few of the 2^k rows are reserved for the blinding factors, so the circuit uses more rows than are usable out of 2^(k - 1) and at most the usable rows out of 2^k.
+/// Only the assignment is checked, not the constraints, so the circuit can be built out of dummy witnesses such as the ones of `init_empty`.
+pub fn measure_k<C: Circuit<Fp> + CircuitExt<Fp>>(
+    circuit: &C,
+    max_k: u32,
+) -> Result<u32, &'static str> {
+    (4..=max_k)
+        .find(|k| MockProver::run(*k, circuit, circuit.instances()).is_ok())
+        .ok_or("The circuit doesn't fit in 2^max_k rows")
+}
+
 /// Generates a proof given the public setup, the proving key, the initiated circuit and its public inputs.
 pub fn full_prover<C: Circuit<Fp> + CircuitExt<Fp>>(
     params: &ParamsKZG<Bn256>,
@@ -5,7 +5,8 @@
 
 /// Zk circuit subcomponents aka chips.
 pub mod chips;
-/// Zk circuits with a full prover and verifier. A circuit can be viewed as an assembly of chips.
+/// Zk circuits with a full prover and verifier. A circuit can be viewed as an assembly of chips. Requires the `prover` feature, enabled by default.
+#[cfg(feature = "prover")]
 pub mod circuits;
 /// Utilities to build the merkle sum tree data structure. No zk proof in here.
 pub mod merkle_sum_tree;
\ No newline at end of file
@@ -0,0 +1,179 @@
+use crate::merkle_sum_tree::utils::{build_merkle_tree_from_leaves_with_hasher, fp_to_big_uint};
+use crate::merkle_sum_tree::{
+    Cryptocurrency, Entry, MerkleSumTree, Node, NodeHasher, PoseidonHasher, Tree,
+};
+use num_bigint::BigUint;
+
+/// Aggregation Merkle Sum Tree Data Structure.
+///
+/// An Aggregation Merkle Sum Tree combines 2^m mini-trees of equal depth, each built independently (for example by a worker out of a separate CSV shard), into a single Merkle Sum Tree.
+/// The roots of the mini-trees are used as the leaves of a top tree of depth m, so that the aggregated tree has depth `mini_tree_depth + m` and the same shape as a [`MerkleSumTree`] built out of the concatenation of the shards.
+/// The entry at index `i` of the aggregated tree is the entry at index `i % 2^mini_tree_depth` of the mini-tree `i / 2^mini_tree_depth`.
+///
+/// All the mini-trees but the last one must be full, such that padding leaves only appear after the last entry. Under this condition, the root of the aggregated tree is identical to the one of a [`MerkleSumTree`] built out of all the entries,
+/// and the proofs generated by the aggregated tree span the mini-tree and top-tree levels and can be verified by a `MstInclusionCircuit` with `LEVELS` equal to the depth of the aggregated tree.
+///
+/// # Type Parameters
+///
+/// * `N_CURRENCIES`: The number of cryptocurrencies for each user account
+/// * `N_BYTES`: Range in which each node balance should lie
+/// * `H`: The hasher of the nodes of the mini-trees and of the top tree
+#[derive(Debug, Clone)]
+pub struct AggregationMerkleSumTree<
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+    H: NodeHasher = PoseidonHasher,
+> {
+    root: Node<N_CURRENCIES>,
+    nodes: Vec<Vec<Node<N_CURRENCIES>>>,
+    depth: usize,
+    cryptocurrencies: Vec<Cryptocurrency>,
+    mini_trees: Vec<MerkleSumTree<N_CURRENCIES, N_BYTES, H>>,
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize, H: NodeHasher> Tree<N_CURRENCIES, N_BYTES, H>
+    for AggregationMerkleSumTree<N_CURRENCIES, N_BYTES, H>
+{
+    fn root(&self) -> &Node<N_CURRENCIES> {
+        &self.root
+    }
+
+    fn depth(&self) -> &usize {
+        &self.depth
+    }
+
+    fn cryptocurrencies(&self) -> &[Cryptocurrency] {
+        &self.cryptocurrencies
+    }
+
+    fn entries_count(&self) -> usize {
+        self.mini_trees
+            .iter()
+            .map(|mini_tree| mini_tree.entries_count())
+            .sum()
+    }
+
+    fn get_node(
+        &self,
+        level: usize,
+        index: usize,
+    ) -> Result<Node<N_CURRENCIES>, Box<dyn std::error::Error>> {
+        let mini_tree_depth = self.mini_tree_depth();
+
+        // The levels above the roots of the mini-trees belong to the top tree
+        if level >= mini_tree_depth {
+            return self
+                .nodes
+                .get(level - mini_tree_depth)
+                .and_then(|layer| layer.get(index))
+                .cloned()
+                .ok_or_else(|| Box::from("Node not found"));
+        }
+
+        let mini_tree_index = index >> (mini_tree_depth - level);
+        let mini_tree = self
+            .mini_trees
+            .get(mini_tree_index)
+            .ok_or("Node not found")?;
+        mini_tree.get_node(level, index & ((1 << (mini_tree_depth - level)) - 1))
+    }
+
+    fn get_entry(&self, index: usize) -> Result<Entry<N_CURRENCIES>, Box<dyn std::error::Error>> {
+        let mini_tree_depth = self.mini_tree_depth();
+
+        let mini_tree = self
+            .mini_trees
+            .get(index >> mini_tree_depth)
+            .ok_or("Entry not found")?;
+        mini_tree.get_entry(index & ((1 << mini_tree_depth) - 1))
+    }
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize, H: NodeHasher>
+    AggregationMerkleSumTree<N_CURRENCIES, N_BYTES, H>
+{
+    /// Builds an Aggregation Merkle Sum Tree out of 2^m mini-trees of equal depth and cryptocurrencies.
+    /// All the mini-trees but the last one must be full, namely they can't contain padding leaves.
+    pub fn new(
+        mini_trees: Vec<MerkleSumTree<N_CURRENCIES, N_BYTES, H>>,
+    ) -> Result<AggregationMerkleSumTree<N_CURRENCIES, N_BYTES, H>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        if mini_trees.is_empty() {
+            return Err(Box::from("Cannot aggregate an empty set of mini-trees"));
+        }
+
+        if !mini_trees.len().is_power_of_two() {
+            return Err(Box::from("The number of mini-trees must be a power of two"));
+        }
+
+        let first_mini_tree = &mini_trees[0];
+        let mini_tree_depth = *first_mini_tree.depth();
+
+        for (index, mini_tree) in mini_trees.iter().enumerate() {
+            if *mini_tree.depth() != mini_tree_depth {
+                return Err(Box::from("All the mini-trees must have the same depth"));
+            }
+
+            if mini_tree.cryptocurrencies() != first_mini_tree.cryptocurrencies() {
+                return Err(Box::from(
+                    "All the mini-trees must have the same cryptocurrencies",
+                ));
+            }
+
+            // Padding leaves may only appear after the last entry of the aggregated tree
+            if index != mini_trees.len() - 1 && mini_tree.entries_count() != 1 << mini_tree_depth {
+                return Err(Box::from(
+                    "All the mini-trees but the last one must be full",
+                ));
+            }
+        }
+
+        let roots: Vec<Node<N_CURRENCIES>> = mini_trees
+            .iter()
+            .map(|mini_tree| mini_tree.root().clone())
+            .collect();
+
+        let top_tree_depth = mini_trees.len().trailing_zeros() as usize;
+        let mut nodes = vec![];
+        let root = build_merkle_tree_from_leaves_with_hasher::<N_CURRENCIES, H>(
+            &roots,
+            top_tree_depth,
+            &mut nodes,
+        )?;
+
+        // The root balances must lie in the range of the circuit, as any other node balance
+        for balance in root.balances.iter() {
+            if fp_to_big_uint(*balance) >= BigUint::from(2_usize).pow(8 * N_BYTES as u32) {
+                return Err(Box::from(
+                    "Accumulated balance is not in the expected range, proof generation will fail!",
+                ));
+            }
+        }
+
+        Ok(AggregationMerkleSumTree {
+            root,
+            nodes,
+            depth: mini_tree_depth + top_tree_depth,
+            cryptocurrencies: first_mini_tree.cryptocurrencies().to_vec(),
+            mini_trees,
+        })
+    }
+
+    /// Returns the mini-tree at the given index
+    pub fn mini_tree(
+        &self,
+        mini_tree_index: usize,
+    ) -> Result<&MerkleSumTree<N_CURRENCIES, N_BYTES, H>, Box<dyn std::error::Error>> {
+        self.mini_trees
+            .get(mini_tree_index)
+            .ok_or_else(|| Box::from("Mini-tree not found"))
+    }
+
+    /// Returns the depth of the mini-trees
+    pub fn mini_tree_depth(&self) -> usize {
+        *self.mini_trees[0].depth()
+    }
+}
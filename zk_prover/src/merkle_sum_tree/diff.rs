@@ -0,0 +1,205 @@
+use crate::merkle_sum_tree::utils::fp_to_big_uint;
+use crate::merkle_sum_tree::{Cryptocurrency, Entry, Tree};
+use num_bigint::{BigInt, BigUint};
+use std::collections::{HashMap, HashSet};
+
+/// A change of the balances of an entry present in both trees
+#[derive(Debug, Clone)]
+pub struct EntryChange<const N_CURRENCIES: usize> {
+    pub previous: Entry<N_CURRENCIES>,
+    pub current: Entry<N_CURRENCIES>,
+}
+
+impl<const N_CURRENCIES: usize> EntryChange<N_CURRENCIES> {
+    /// Returns the username of the changed entry
+    pub fn username(&self) -> &str {
+        self.current.username()
+    }
+
+    /// Returns the per-currency deltas, namely the current balances minus the previous balances
+    pub fn deltas(&self) -> [BigInt; N_CURRENCIES] {
+        std::array::from_fn(|i| {
+            BigInt::from(self.current.balances()[i].clone())
+                - BigInt::from(self.previous.balances()[i].clone())
+        })
+    }
+}
+
+/// Difference between the entries of two trees, typically the trees of two consecutive rounds, keyed by username.
+///
+/// The entries are reported in the order of the tree they belong to: the added and changed entries in the order of the current tree, the removed entries in the order of the previous tree.
+#[derive(Debug, Clone)]
+pub struct TreeDiff<const N_CURRENCIES: usize> {
+    /// The cryptocurrencies of both trees
+    pub cryptocurrencies: Vec<Cryptocurrency>,
+    /// Entries of the current tree whose username is not in the previous tree
+    pub added: Vec<Entry<N_CURRENCIES>>,
+    /// Entries of the previous tree whose username is not in the current tree
+    pub removed: Vec<Entry<N_CURRENCIES>>,
+    /// Entries of both trees whose balances differ
+    pub changed: Vec<EntryChange<N_CURRENCIES>>,
+    /// The root balances of the previous tree
+    pub previous_root_balances: [BigUint; N_CURRENCIES],
+    /// The root balances of the current tree
+    pub current_root_balances: [BigUint; N_CURRENCIES],
+    /// The current root balances minus the previous root balances
+    pub root_deltas: [BigInt; N_CURRENCIES],
+}
+
+impl<const N_CURRENCIES: usize> TreeDiff<N_CURRENCIES> {
+    /// Returns true if no entry was added, removed or changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Returns the per-currency deltas accumulated over the added, removed and changed entries
+    pub fn entry_deltas(&self) -> [BigInt; N_CURRENCIES] {
+        let mut deltas: [BigInt; N_CURRENCIES] = std::array::from_fn(|_| BigInt::from(0));
+
+        for entry in self.added.iter() {
+            for (delta, balance) in deltas.iter_mut().zip(entry.balances().iter()) {
+                *delta += BigInt::from(balance.clone());
+            }
+        }
+        for entry in self.removed.iter() {
+            for (delta, balance) in deltas.iter_mut().zip(entry.balances().iter()) {
+                *delta -= BigInt::from(balance.clone());
+            }
+        }
+        for change in self.changed.iter() {
+            for (delta, entry_delta) in deltas.iter_mut().zip(change.deltas()) {
+                *delta += entry_delta;
+            }
+        }
+
+        deltas
+    }
+
+    /// Reconciles the diff against `published_previous_root_balances`, the root balances committed for the previous round, typically read back from the Summa contract rather than from the previous tree.
+    ///
+    /// Checks that the previous tree is the one whose root balances were published, and that the published root balances plus the deltas of the entries add up to the root balances of the current tree for each cryptocurrency.
+    /// A mismatch means that the trees don't commit to the reported entries or that the previous tree isn't the published one, for example because of a bug in the data pipeline, and the current root shouldn't be published.
+    pub fn reconcile(
+        &self,
+        published_previous_root_balances: &[BigUint; N_CURRENCIES],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for (i, (cryptocurrency, entry_delta)) in self
+            .cryptocurrencies
+            .iter()
+            .zip(self.entry_deltas().iter())
+            .enumerate()
+        {
+            if self.previous_root_balances[i] != published_previous_root_balances[i] {
+                return Err(format!(
+                    "Root balance of the previous tree for {} on {} is {}, but {} was published",
+                    cryptocurrency.name,
+                    cryptocurrency.chain,
+                    self.previous_root_balances[i],
+                    published_previous_root_balances[i]
+                )
+                .into());
+            }
+
+            let expected_balance =
+                BigInt::from(published_previous_root_balances[i].clone()) + entry_delta;
+            if expected_balance != BigInt::from(self.current_root_balances[i].clone()) {
+                return Err(format!(
+                    "Deltas of the entries for {} on {} add up to {}, but the root balance changed from the published {} to {}",
+                    cryptocurrency.name,
+                    cryptocurrency.chain,
+                    entry_delta,
+                    published_previous_root_balances[i],
+                    self.current_root_balances[i]
+                )
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Computes the difference between the entries of the `previous` and the `current` trees, keyed by username.
+///
+/// The trees must have the same cryptocurrencies. The usernames of each tree must be unique. Empty entries are ignored.
+pub fn diff_trees<
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+    P: Tree<N_CURRENCIES, N_BYTES> + ?Sized,
+    C: Tree<N_CURRENCIES, N_BYTES> + ?Sized,
+>(
+    previous: &P,
+    current: &C,
+) -> Result<TreeDiff<N_CURRENCIES>, Box<dyn std::error::Error>> {
+    if previous.cryptocurrencies() != current.cryptocurrencies() {
+        return Err(Box::from("The trees must have the same cryptocurrencies"));
+    }
+
+    let mut previous_entries = HashMap::new();
+    let mut previous_usernames = Vec::new();
+    for index in 0..previous.entries_count() {
+        let entry = previous.get_entry(index)?;
+        if entry.username().is_empty() {
+            continue;
+        }
+        let username = entry.username().to_string();
+        if previous_entries.insert(username.clone(), entry).is_some() {
+            return Err(format!("Duplicate username {} in the previous tree", username).into());
+        }
+        previous_usernames.push(username);
+    }
+
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+    let mut current_usernames = HashSet::new();
+    for index in 0..current.entries_count() {
+        let entry = current.get_entry(index)?;
+        if entry.username().is_empty() {
+            continue;
+        }
+        if !current_usernames.insert(entry.username().to_string()) {
+            return Err(format!(
+                "Duplicate username {} in the current tree",
+                entry.username()
+            )
+            .into());
+        }
+
+        match previous_entries.remove(entry.username()) {
+            Some(previous_entry) => {
+                if previous_entry.balances() != entry.balances() {
+                    changed.push(EntryChange {
+                        previous: previous_entry,
+                        current: entry,
+                    });
+                }
+            }
+            None => added.push(entry),
+        }
+    }
+
+    // The entries left are the ones missing from the current tree
+    let removed = previous_usernames
+        .iter()
+        .filter_map(|username| previous_entries.remove(username))
+        .collect();
+
+    let previous_root_balances: [BigUint; N_CURRENCIES] =
+        std::array::from_fn(|i| fp_to_big_uint(previous.root().balances[i]));
+    let current_root_balances: [BigUint; N_CURRENCIES] =
+        std::array::from_fn(|i| fp_to_big_uint(current.root().balances[i]));
+    let root_deltas = std::array::from_fn(|i| {
+        BigInt::from(current_root_balances[i].clone())
+            - BigInt::from(previous_root_balances[i].clone())
+    });
+
+    Ok(TreeDiff {
+        cryptocurrencies: current.cryptocurrencies().to_vec(),
+        added,
+        removed,
+        changed,
+        previous_root_balances,
+        current_root_balances,
+        root_deltas,
+    })
+}
@@ -0,0 +1,260 @@
+use crate::merkle_sum_tree::utils::{parse_cryptocurrencies_from_headers, CurrencyManifest};
+use crate::merkle_sum_tree::verifier::{verify_merkle_proof_json, VerificationError};
+use crate::merkle_sum_tree::{Cryptocurrency, Entry, MerkleSumTree, Node, Tree};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use num_bigint::BigUint;
+use std::any::Any;
+use std::fs::File;
+
+/// Maximum number of currencies supported by the dynamically sized types, namely the number of precompiled `N_CURRENCIES` shapes
+pub const MAX_DYNAMIC_CURRENCIES: usize = 8;
+
+/// Binds the const item `$n` to the runtime number of currencies `$n_currencies` and wraps `$build` into the matching variant of `$dynamic`.
+/// Returns an error from the enclosing function if the number of currencies is not supported.
+macro_rules! dispatch_currencies {
+    ($dynamic:ident, $n_currencies:expr, $n:ident => $build:expr) => {
+        match $n_currencies {
+            1 => {
+                const $n: usize = 1;
+                $dynamic::Currencies1($build)
+            }
+            2 => {
+                const $n: usize = 2;
+                $dynamic::Currencies2($build)
+            }
+            3 => {
+                const $n: usize = 3;
+                $dynamic::Currencies3($build)
+            }
+            4 => {
+                const $n: usize = 4;
+                $dynamic::Currencies4($build)
+            }
+            5 => {
+                const $n: usize = 5;
+                $dynamic::Currencies5($build)
+            }
+            6 => {
+                const $n: usize = 6;
+                $dynamic::Currencies6($build)
+            }
+            7 => {
+                const $n: usize = 7;
+                $dynamic::Currencies7($build)
+            }
+            8 => {
+                const $n: usize = 8;
+                $dynamic::Currencies8($build)
+            }
+            n => {
+                return Err(format!(
+                    "Unsupported number of currencies: {}, at most {} are supported",
+                    n,
+                    $crate::merkle_sum_tree::MAX_DYNAMIC_CURRENCIES
+                )
+                .into())
+            }
+        }
+    };
+}
+
+/// Evaluates `$body` with `$inner` bound to the value wrapped by the variant of `$value`, an instance of `$dynamic`
+macro_rules! with_currencies {
+    ($dynamic:ident, $value:expr, $inner:ident => $body:expr) => {
+        match $value {
+            $dynamic::Currencies1($inner) => $body,
+            $dynamic::Currencies2($inner) => $body,
+            $dynamic::Currencies3($inner) => $body,
+            $dynamic::Currencies4($inner) => $body,
+            $dynamic::Currencies5($inner) => $body,
+            $dynamic::Currencies6($inner) => $body,
+            $dynamic::Currencies7($inner) => $body,
+            $dynamic::Currencies8($inner) => $body,
+        }
+    };
+}
+
+pub(crate) use dispatch_currencies;
+pub(crate) use with_currencies;
+
+/// A node whose number of balances is only known at runtime
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DynamicNode {
+    pub hash: Fp,
+    pub balances: Vec<Fp>,
+}
+
+impl<const N_CURRENCIES: usize> From<Node<N_CURRENCIES>> for DynamicNode {
+    fn from(node: Node<N_CURRENCIES>) -> Self {
+        DynamicNode {
+            hash: node.hash,
+            balances: node.balances.to_vec(),
+        }
+    }
+}
+
+/// An entry whose number of balances is only known at runtime
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DynamicEntry {
+    pub username: String,
+    pub balances: Vec<BigUint>,
+    pub salt: Option<Fp>,
+}
+
+impl DynamicEntry {
+    /// Converts the entry into an entry with `N_CURRENCIES` balances
+    pub fn to_entry<const N_CURRENCIES: usize>(
+        &self,
+    ) -> Result<Entry<N_CURRENCIES>, Box<dyn std::error::Error>> {
+        let balances: [BigUint; N_CURRENCIES] = self.balances.clone().try_into().map_err(|_| {
+            format!(
+                "Entry {} has {} balances, expected {}",
+                self.username,
+                self.balances.len(),
+                N_CURRENCIES
+            )
+        })?;
+
+        match self.salt {
+            Some(salt) => Ok(Entry::new_salted(self.username.clone(), balances, salt)?),
+            None => Ok(Entry::new(self.username.clone(), balances)?),
+        }
+    }
+}
+
+impl<const N_CURRENCIES: usize> From<Entry<N_CURRENCIES>> for DynamicEntry {
+    fn from(entry: Entry<N_CURRENCIES>) -> Self {
+        DynamicEntry {
+            username: entry.username().to_string(),
+            balances: entry.balances().to_vec(),
+            salt: entry.salt(),
+        }
+    }
+}
+
+/// Merkle Sum Tree whose number of currencies is read at runtime, for example from the header of the CSV file.
+///
+/// Each variant wraps a [`MerkleSumTree`] precompiled for a given `N_CURRENCIES`, from 1 to [`MAX_DYNAMIC_CURRENCIES`], so that the hashes are identical to the ones of the const-generic tree.
+/// The matching circuit shape can be selected with [`DynamicMstInclusionCircuit`](crate::circuits::dynamic::DynamicMstInclusionCircuit).
+///
+/// # Type Parameters
+///
+/// * `N_BYTES`: Range in which each node balance should lie
+#[derive(Debug, Clone)]
+pub enum DynamicMerkleSumTree<const N_BYTES: usize> {
+    Currencies1(MerkleSumTree<1, N_BYTES>),
+    Currencies2(MerkleSumTree<2, N_BYTES>),
+    Currencies3(MerkleSumTree<3, N_BYTES>),
+    Currencies4(MerkleSumTree<4, N_BYTES>),
+    Currencies5(MerkleSumTree<5, N_BYTES>),
+    Currencies6(MerkleSumTree<6, N_BYTES>),
+    Currencies7(MerkleSumTree<7, N_BYTES>),
+    Currencies8(MerkleSumTree<8, N_BYTES>),
+}
+
+impl<const N_BYTES: usize> DynamicMerkleSumTree<N_BYTES> {
+    /// Builds a Merkle Sum Tree from a CSV file stored at `path`, formatted as for [`MerkleSumTree::new`]. The number of currencies is the number of balance columns of the header.
+    pub fn new(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::new_with_manifest(path, &CurrencyManifest::default())
+    }
+
+    /// Builds a Merkle Sum Tree from a CSV file stored at `path` whose balances are decimal amounts, as for [`MerkleSumTree::new_with_manifest`]
+    pub fn new_with_manifest(
+        path: &str,
+        manifest: &CurrencyManifest,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut rdr = csv::ReaderBuilder::new().from_reader(File::open(path)?);
+        let n_currencies = parse_cryptocurrencies_from_headers(rdr.headers()?, manifest)?.len();
+
+        let tree = dispatch_currencies!(DynamicMerkleSumTree, n_currencies, N =>
+            MerkleSumTree::<N, N_BYTES>::new_with_manifest(path, manifest)?
+        );
+        Ok(tree)
+    }
+
+    /// Builds a Merkle Sum Tree from a vector of entries, each holding one balance per cryptocurrency
+    pub fn from_entries(
+        entries: Vec<DynamicEntry>,
+        cryptocurrencies: Vec<Cryptocurrency>,
+        is_sorted: bool,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let tree = dispatch_currencies!(DynamicMerkleSumTree, cryptocurrencies.len(), N =>
+            MerkleSumTree::<N, N_BYTES>::from_entries(
+                entries
+                    .iter()
+                    .map(|entry| entry.to_entry::<N>())
+                    .collect::<Result<Vec<_>, _>>()?,
+                cryptocurrencies,
+                is_sorted,
+            )?
+        );
+        Ok(tree)
+    }
+
+    /// Returns the wrapped tree if it has `N_CURRENCIES` currencies
+    pub fn as_tree<const N_CURRENCIES: usize>(
+        &self,
+    ) -> Option<&MerkleSumTree<N_CURRENCIES, N_BYTES>> {
+        with_currencies!(DynamicMerkleSumTree, self, tree => (tree as &dyn Any).downcast_ref())
+    }
+
+    /// Returns the number of currencies of the tree
+    pub fn n_currencies(&self) -> usize {
+        self.cryptocurrencies().len()
+    }
+
+    pub fn root(&self) -> DynamicNode {
+        with_currencies!(DynamicMerkleSumTree, self, tree => tree.root().clone().into())
+    }
+
+    pub fn depth(&self) -> usize {
+        with_currencies!(DynamicMerkleSumTree, self, tree => *tree.depth())
+    }
+
+    pub fn cryptocurrencies(&self) -> &[Cryptocurrency] {
+        with_currencies!(DynamicMerkleSumTree, self, tree => tree.cryptocurrencies())
+    }
+
+    pub fn entries_count(&self) -> usize {
+        with_currencies!(DynamicMerkleSumTree, self, tree => tree.entries_count())
+    }
+
+    pub fn get_node(
+        &self,
+        level: usize,
+        index: usize,
+    ) -> Result<DynamicNode, Box<dyn std::error::Error>> {
+        with_currencies!(DynamicMerkleSumTree, self, tree => Ok(tree.get_node(level, index)?.into()))
+    }
+
+    pub fn get_entry(&self, index: usize) -> Result<DynamicEntry, Box<dyn std::error::Error>> {
+        with_currencies!(DynamicMerkleSumTree, self, tree => Ok(tree.get_entry(index)?.into()))
+    }
+
+    /// Generates the proof of inclusion of the entry at the given index, serialized as by [`MerkleProof::to_json`](crate::merkle_sum_tree::MerkleProof::to_json)
+    pub fn generate_proof_json(&self, index: usize) -> Result<String, Box<dyn std::error::Error>> {
+        with_currencies!(DynamicMerkleSumTree, self, tree => tree.generate_proof(index)?.to_json())
+    }
+
+    /// Verifies a proof serialized as by [`DynamicMerkleSumTree::generate_proof_json`] against the root of the tree
+    pub fn verify_proof_json(&self, json: &str) -> Result<(), VerificationError> {
+        with_currencies!(DynamicMerkleSumTree, self, tree => verify_proof_json(tree, json))
+    }
+}
+
+/// Verifies a serialized proof against the root of a const-generic tree
+fn verify_proof_json<const N_CURRENCIES: usize, const N_BYTES: usize>(
+    tree: &MerkleSumTree<N_CURRENCIES, N_BYTES>,
+    json: &str,
+) -> Result<(), VerificationError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    verify_merkle_proof_json::<N_CURRENCIES, N_BYTES>(
+        json,
+        tree.root().hash,
+        &tree.root().balances,
+    )?;
+    Ok(())
+}
@@ -1,25 +1,58 @@
-use crate::merkle_sum_tree::utils::big_intify_username;
-use crate::merkle_sum_tree::Node;
+use crate::merkle_sum_tree::serialization::SerializedEntry;
+use crate::merkle_sum_tree::utils::{big_intify_username, big_uint_to_fp, check_username};
+use crate::merkle_sum_tree::{Node, NodeHasher, PoseidonHasher};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
 use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
 
 /// An entry in the Merkle Sum Tree from the database of the CEX.
 /// It contains the username and the balances of the user.
-#[derive(Clone, Debug)]
+/// It is serialized as its username, its balances and its salt.
+///
+/// A salted entry commits to `H(username, salt)` instead of the username, so that the username can't be learnt from the leaf hash preimage, for example by a neighbouring user receiving it as a sibling in a proof.
+/// The salt is delivered privately to the user, alongside the proof.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(
+    into = "SerializedEntry<N_CURRENCIES>",
+    try_from = "SerializedEntry<N_CURRENCIES>"
+)]
 pub struct Entry<const N_CURRENCIES: usize> {
     username_as_big_uint: BigUint,
     balances: [BigUint; N_CURRENCIES],
     username: String,
+    salt: Option<Fp>,
 }
 
 impl<const N_CURRENCIES: usize> Entry<N_CURRENCIES> {
+    /// Builds an entry whose leaf commits to the username. The username must not be empty, see [`check_username`].
     pub fn new(username: String, balances: [BigUint; N_CURRENCIES]) -> Result<Self, &'static str> {
+        check_username(&username)?;
+
         Ok(Entry {
             username_as_big_uint: big_intify_username(&username),
             balances,
             username,
+            salt: None,
         })
     }
 
+    /// Builds an entry whose leaf commits to `H(username, salt)` instead of the username. The username must not be empty, see [`check_username`].
+    pub fn new_salted(
+        username: String,
+        balances: [BigUint; N_CURRENCIES],
+        salt: Fp,
+    ) -> Result<Self, &'static str> {
+        check_username(&username)?;
+
+        Ok(Entry {
+            username_as_big_uint: big_intify_username(&username),
+            balances,
+            username,
+            salt: Some(salt),
+        })
+    }
+
+    /// Returns the entry of a padding leaf, namely the entry with an empty username and all-zero balances. It can't be built by [`Entry::new`].
     pub fn init_empty() -> Self {
         let empty_balances: [BigUint; N_CURRENCIES] = std::array::from_fn(|_| BigUint::from(0u32));
 
@@ -27,6 +60,7 @@ impl<const N_CURRENCIES: usize> Entry<N_CURRENCIES> {
             username_as_big_uint: BigUint::from(0u32),
             balances: empty_balances,
             username: "".to_string(),
+            salt: None,
         }
     }
 
@@ -34,18 +68,34 @@ impl<const N_CURRENCIES: usize> Entry<N_CURRENCIES> {
     where
         [usize; N_CURRENCIES + 1]: Sized,
     {
-        Node::leaf(&self.username_as_big_uint, &self.balances)
+        match self.salt {
+            Some(salt) => Node::salted_leaf(&self.username_as_big_uint, salt, &self.balances),
+            None => Node::leaf(&self.username_as_big_uint, &self.balances),
+        }
     }
 
     /// Stores the new balance values
     ///
     /// Returns the updated node
     pub fn recompute_leaf(&mut self, updated_balances: &[BigUint; N_CURRENCIES]) -> Node<N_CURRENCIES>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+    {
+        self.recompute_leaf_with_hasher::<PoseidonHasher>(updated_balances)
+    }
+
+    /// Stores the new balance values
+    ///
+    /// Returns the updated node, hashed with `H`
+    pub fn recompute_leaf_with_hasher<H: NodeHasher>(
+        &mut self,
+        updated_balances: &[BigUint; N_CURRENCIES],
+    ) -> Node<N_CURRENCIES>
     where
         [usize; N_CURRENCIES + 1]: Sized,
     {
         self.balances = updated_balances.clone();
-        Node::leaf(&self.username_as_big_uint, updated_balances)
+        H::compute_leaf(self)
     }
 
     pub fn balances(&self) -> &[BigUint; N_CURRENCIES] {
@@ -59,4 +109,18 @@ impl<const N_CURRENCIES: usize> Entry<N_CURRENCIES> {
     pub fn username(&self) -> &str {
         &self.username
     }
+
+    pub fn salt(&self) -> Option<Fp> {
+        self.salt
+    }
+
+    /// Returns the first element of the leaf hash preimage, namely the username as a field element or, for a salted entry, `H(username, salt)`
+    pub fn username_commitment(&self) -> Fp {
+        match self.salt {
+            Some(salt) => {
+                Node::<N_CURRENCIES>::username_commitment(&self.username_as_big_uint, salt)
+            }
+            None => big_uint_to_fp(&self.username_as_big_uint),
+        }
+    }
 }
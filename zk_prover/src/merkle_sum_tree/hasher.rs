@@ -0,0 +1,260 @@
+//! Hash functions used to compute the nodes of a Merkle Sum Tree.
+//!
+//! The hash function is pluggable, and so is the way a middle node commits to its children through [`NodeHasher::middle`]:
+//! * [`PoseidonHasher`] is the halo2 Poseidon used by the circuits of this crate, and the default hasher of [`MerkleSumTree`](crate::merkle_sum_tree::MerkleSumTree) and [`Tree`](crate::merkle_sum_tree::Tree)
+//! * [`CircomPoseidonHasher`] is the circomlib Poseidon, as implemented by `poseidon-rs`, over the same preimages as [`PoseidonHasher`]
+//! * [`NovaPoseidonHasher`] is the circomlib Poseidon over the middle node preimages of the Nova incremental verifier, namely `H(LeftChild.hash, LeftChild.balances, RightChild.hash, RightChild.balances)`
+use crate::chips::poseidon::poseidon_spec::PoseidonSpec;
+use crate::merkle_sum_tree::utils::{big_uint_to_fp, fp_to_big_uint};
+use crate::merkle_sum_tree::{Entry, Node};
+use ff::{PrimeField, PrimeFieldRepr};
+use halo2_gadgets::poseidon::primitives::{self as poseidon, ConstantLength};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use num_bigint::BigUint;
+use poseidon_rs::{Fr, Poseidon};
+use std::fmt::Debug;
+use std::sync::OnceLock;
+
+/// A hash function over the BN254 scalar field, used to compute the hashes of the nodes of a Merkle Sum Tree.
+///
+/// Implementors only provide the three hashes of the tree, the nodes are built by the provided methods.
+pub trait NodeHasher: Clone + Debug + Default + Send + Sync + 'static {
+    /// Hashes the preimage of a leaf node, namely `[username, balance[0], balance[1], ... balance[N_CURRENCIES - 1]]`
+    fn hash_leaf<const N_CURRENCIES: usize>(preimage: &[Fp; N_CURRENCIES + 1]) -> Fp
+    where
+        [usize; N_CURRENCIES + 1]: Sized;
+
+    /// Hashes the preimage of a middle node, namely `[balances_sum[0], balances_sum[1], ..., balances_sum[N_CURRENCIES - 1], LeftChild.hash, RightChild.hash]`
+    fn hash_middle<const N_CURRENCIES: usize>(preimage: &[Fp; N_CURRENCIES + 2]) -> Fp
+    where
+        [usize; N_CURRENCIES + 2]: Sized;
+
+    /// Hashes a username and its salt into the commitment `H(username, salt)`, which takes the place of the username in the leaf hash preimage of a salted entry
+    fn hash_username(username: Fp, salt: Fp) -> Fp;
+
+    /// Builds a leaf node from its hash preimage, see [`Node::leaf_node_from_preimage`]
+    fn leaf_node_from_preimage<const N_CURRENCIES: usize>(
+        preimage: &[Fp; N_CURRENCIES + 1],
+    ) -> Node<N_CURRENCIES>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+    {
+        Node {
+            hash: Self::hash_leaf::<N_CURRENCIES>(preimage),
+            balances: preimage[1..].try_into().unwrap(),
+        }
+    }
+
+    /// Builds a middle node from its hash preimage, see [`Node::middle_node_from_preimage`]
+    fn middle_node_from_preimage<const N_CURRENCIES: usize>(
+        preimage: &[Fp; N_CURRENCIES + 2],
+    ) -> Node<N_CURRENCIES>
+    where
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        Node {
+            hash: Self::hash_middle::<N_CURRENCIES>(preimage),
+            balances: preimage[0..N_CURRENCIES].try_into().unwrap(),
+        }
+    }
+
+    /// Builds the parent of two nodes, see [`Node::middle`].
+    ///
+    /// By default, the parent is hashed out of [`Node::middle_node_hash_preimage`] with [`NodeHasher::hash_middle`]. A hasher whose middle nodes commit to their children differently overrides this method,
+    /// in which case the sibling middle node hash preimages of the proofs of its trees can't be hashed back into the sibling nodes, see [`NovaPoseidonHasher`].
+    fn middle<const N_CURRENCIES: usize>(
+        child_l: &Node<N_CURRENCIES>,
+        child_r: &Node<N_CURRENCIES>,
+    ) -> Node<N_CURRENCIES>
+    where
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        Self::middle_node_from_preimage(&Node::middle_node_hash_preimage(child_l, child_r))
+    }
+
+    /// Builds the padding leaf, see [`Node::padding_leaf`]
+    fn padding_leaf<const N_CURRENCIES: usize>() -> Node<N_CURRENCIES>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+    {
+        Self::leaf_node_from_preimage(&[Fp::zero(); N_CURRENCIES + 1])
+    }
+
+    /// Returns the first element of the leaf hash preimage of an entry, see [`Entry::username_commitment`]
+    fn username_commitment<const N_CURRENCIES: usize>(entry: &Entry<N_CURRENCIES>) -> Fp {
+        let username = big_uint_to_fp(entry.username_as_big_uint());
+        match entry.salt() {
+            Some(salt) => Self::hash_username(username, salt),
+            None => username,
+        }
+    }
+
+    /// Builds the leaf node of an entry, see [`Entry::compute_leaf`]
+    fn compute_leaf<const N_CURRENCIES: usize>(entry: &Entry<N_CURRENCIES>) -> Node<N_CURRENCIES>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+    {
+        let mut preimage = [Fp::zero(); N_CURRENCIES + 1];
+        preimage[0] = Self::username_commitment(entry);
+        for (i, balance) in preimage.iter_mut().enumerate().skip(1) {
+            *balance = big_uint_to_fp(&entry.balances()[i - 1]);
+        }
+
+        Self::leaf_node_from_preimage(&preimage)
+    }
+}
+
+/// The halo2 Poseidon hash with `PoseidonSpec`, WIDTH = 2 and RATE = 1, as constrained by the circuits of this crate
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoseidonHasher;
+
+impl NodeHasher for PoseidonHasher {
+    fn hash_leaf<const N_CURRENCIES: usize>(preimage: &[Fp; N_CURRENCIES + 1]) -> Fp
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+    {
+        poseidon::Hash::<Fp, PoseidonSpec, ConstantLength<{ N_CURRENCIES + 1 }>, 2, 1>::init()
+            .hash(*preimage)
+    }
+
+    fn hash_middle<const N_CURRENCIES: usize>(preimage: &[Fp; N_CURRENCIES + 2]) -> Fp
+    where
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        poseidon::Hash::<Fp, PoseidonSpec, ConstantLength<{ N_CURRENCIES + 2 }>, 2, 1>::init()
+            .hash(*preimage)
+    }
+
+    fn hash_username(username: Fp, salt: Fp) -> Fp {
+        poseidon::Hash::<Fp, PoseidonSpec, ConstantLength<2>, 2, 1>::init().hash([username, salt])
+    }
+}
+
+/// The maximum number of inputs hashed by [`CircomPoseidonHasher`], as supported by circomlib
+pub const MAX_CIRCOM_POSEIDON_INPUTS: usize = 16;
+
+/// The circomlib Poseidon hash, as implemented by `poseidon-rs`, whose width is the number of inputs plus one.
+///
+/// Both hashes are over the BN254 scalar field, so the field elements are converted without loss.
+/// A preimage can't have more than [`MAX_CIRCOM_POSEIDON_INPUTS`] elements, so a tree can't have more than `MAX_CIRCOM_POSEIDON_INPUTS - 2` cryptocurrencies. Hashing the nodes of a larger tree fails to compile.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CircomPoseidonHasher;
+
+impl CircomPoseidonHasher {
+    /// Hashes the inputs, whose number is checked against [`MAX_CIRCOM_POSEIDON_INPUTS`] at compile time by [`CircomPoseidonPreimage`]
+    fn hash(inputs: &[Fp]) -> Fp {
+        // The round constants are loaded once, as it is by far the most expensive part of the hash
+        static POSEIDON: OnceLock<Poseidon> = OnceLock::new();
+
+        let inputs = inputs.iter().map(|input| fp_to_fr(*input)).collect();
+        let hash = POSEIDON
+            .get_or_init(Poseidon::new)
+            .hash(inputs)
+            .expect("circom Poseidon failed to hash the inputs");
+        fr_to_fp(hash)
+    }
+}
+
+/// Compile-time checks of the length of the preimages hashed by the circomlib Poseidon for a tree of `N_CURRENCIES` cryptocurrencies.
+///
+/// Referencing one of the constants fails to compile, rather than panicking at runtime, if the preimages are too long.
+struct CircomPoseidonPreimage<const N_CURRENCIES: usize>;
+
+impl<const N_CURRENCIES: usize> CircomPoseidonPreimage<N_CURRENCIES> {
+    /// The leaf and middle node preimages of [`CircomPoseidonHasher`] have at most `N_CURRENCIES + 2` elements
+    const FITS: () = assert!(
+        N_CURRENCIES + 2 <= MAX_CIRCOM_POSEIDON_INPUTS,
+        "circom Poseidon can't hash the nodes of a tree of more than 14 cryptocurrencies"
+    );
+
+    /// The middle node preimages of [`NovaPoseidonHasher`] have `2 * N_CURRENCIES + 2` elements
+    const FITS_NOVA: () = assert!(
+        2 * N_CURRENCIES + 2 <= MAX_CIRCOM_POSEIDON_INPUTS,
+        "circom Poseidon can't hash the Nova middle nodes of a tree of more than 7 cryptocurrencies"
+    );
+}
+
+impl NodeHasher for CircomPoseidonHasher {
+    fn hash_leaf<const N_CURRENCIES: usize>(preimage: &[Fp; N_CURRENCIES + 1]) -> Fp
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+    {
+        let () = CircomPoseidonPreimage::<N_CURRENCIES>::FITS;
+        Self::hash(preimage)
+    }
+
+    fn hash_middle<const N_CURRENCIES: usize>(preimage: &[Fp; N_CURRENCIES + 2]) -> Fp
+    where
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let () = CircomPoseidonPreimage::<N_CURRENCIES>::FITS;
+        Self::hash(preimage)
+    }
+
+    fn hash_username(username: Fp, salt: Fp) -> Fp {
+        Self::hash(&[username, salt])
+    }
+}
+
+/// The circomlib Poseidon hash over the layout of the Nova incremental verifier, `src/circom/incremental_mst_inclusion.circom`.
+///
+/// The leaves are hashed as by [`CircomPoseidonHasher`], namely `H(username, balance[0], ..., balance[N_CURRENCIES - 1])`, while a middle node is hashed as `H(LeftChild.hash, LeftChild.balances, RightChild.hash, RightChild.balances)`,
+/// so that it commits to the balances of both children. A tree can't have more than `(MAX_CIRCOM_POSEIDON_INPUTS - 2) / 2` cryptocurrencies, hashing the nodes of a larger tree fails to compile.
+///
+/// The proofs generated by a tree built with this hasher carry the default middle node hash preimages, which don't determine the Nova hashes of the sibling nodes, so they can't be checked with
+/// [`verify_merkle_proof_with_hasher`](crate::merkle_sum_tree::verifier::verify_merkle_proof_with_hasher). The Nova circuit takes the sibling nodes themselves, as returned by [`Tree::get_node`](crate::merkle_sum_tree::Tree::get_node).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NovaPoseidonHasher;
+
+impl NodeHasher for NovaPoseidonHasher {
+    fn hash_leaf<const N_CURRENCIES: usize>(preimage: &[Fp; N_CURRENCIES + 1]) -> Fp
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+    {
+        CircomPoseidonHasher::hash_leaf::<N_CURRENCIES>(preimage)
+    }
+
+    fn hash_middle<const N_CURRENCIES: usize>(preimage: &[Fp; N_CURRENCIES + 2]) -> Fp
+    where
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        CircomPoseidonHasher::hash_middle::<N_CURRENCIES>(preimage)
+    }
+
+    fn hash_username(username: Fp, salt: Fp) -> Fp {
+        CircomPoseidonHasher::hash_username(username, salt)
+    }
+
+    fn middle<const N_CURRENCIES: usize>(
+        child_l: &Node<N_CURRENCIES>,
+        child_r: &Node<N_CURRENCIES>,
+    ) -> Node<N_CURRENCIES>
+    where
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let () = CircomPoseidonPreimage::<N_CURRENCIES>::FITS_NOVA;
+
+        let preimage: Vec<Fp> = std::iter::once(child_l.hash)
+            .chain(child_l.balances)
+            .chain(std::iter::once(child_r.hash))
+            .chain(child_r.balances)
+            .collect();
+
+        Node {
+            hash: CircomPoseidonHasher::hash(&preimage),
+            balances: std::array::from_fn(|i| child_l.balances[i] + child_r.balances[i]),
+        }
+    }
+}
+
+/// Converts a halo2 field element into a `poseidon-rs` field element
+pub fn fp_to_fr(fp: Fp) -> Fr {
+    Fr::from_str(&fp_to_big_uint(fp).to_string()).unwrap()
+}
+
+/// Converts a `poseidon-rs` field element into a halo2 field element
+pub fn fr_to_fp(fr: Fr) -> Fp {
+    let mut bytes = vec![];
+    fr.into_repr().write_be(&mut bytes).unwrap();
+    big_uint_to_fp(&BigUint::from_bytes_be(&bytes))
+}
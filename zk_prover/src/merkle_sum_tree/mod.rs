@@ -1,10 +1,19 @@
+mod aggregation_mst;
+mod diff;
+mod dynamic_mst;
 mod entry;
+mod hasher;
 mod mst;
 mod node;
+mod quaternary_mst;
+mod serialization;
+mod store;
 mod tests;
 mod tree;
 pub mod utils;
+pub mod verifier;
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use serde::{Deserialize, Serialize};
 
 /// A struct representing a Merkle Proof.
 /// 
@@ -13,7 +22,9 @@ use halo2_proofs::halo2curves::bn256::Fr as Fp;
 /// * `root`: The root of the Merkle Sum Tree
 /// * `sibling_leaf_node_hash_preimage`: The hash preimage of the sibling leaf node. The hash preimage is equal to `[sibling_username, sibling.balance[0], sibling.balance[1], ... sibling.balance[N_CURRENCIES - 1]]`
 /// * `sibling_middle_node_hash_preimages`: The hash preimages of the sibling middle nodes. The hash preimage is equal to `[sibling_left_child.balance[0] + sibling_right_child.balance[0], sibling_left_child.balance[1] + sibling_right_child.balance[1], ..., sibling_left_child.balance[N_CURRENCIES - 1] + sibling_right_child.balance[N_CURRENCIES - 1], sibling_left_child.hash, sibling_right_child.hash]`
-#[derive(Clone, Debug)]
+///
+/// The proof can be serialized to JSON with [`MerkleProof::to_json`] or to a compact binary encoding with [`MerkleProof::to_bytes`]. Both encodings are tagged with [`MERKLE_PROOF_FORMAT_VERSION`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MerkleProof<const N_CURRENCIES: usize, const N_BYTES: usize>
 where
     [usize; N_CURRENCIES + 1]: Sized,
@@ -21,13 +32,95 @@ where
 {
     pub entry: Entry<N_CURRENCIES>,
     pub root: Node<N_CURRENCIES>,
+    #[serde(with = "serialization::fp_array")]
     pub sibling_leaf_node_hash_preimage: [Fp; N_CURRENCIES + 1],
+    #[serde(with = "serialization::fp_array_vec")]
     pub sibling_middle_node_hash_preimages: Vec<[Fp; N_CURRENCIES + 2]>,
+    #[serde(with = "serialization::fp_vec")]
     pub path_indices: Vec<Fp>,
 }
 
+/// A struct representing a Merkle Multiproof, namely a proof of inclusion of multiple entries into the same Merkle Sum Tree.
+/// The sibling hash preimages that can be computed from the included entries are omitted, so that each preimage is included at most once.
+///
+/// Fields:
+/// * `entries`: The entries for which the proof is generated, sorted by their index in the tree
+/// * `indices`: The indices of the entries in the tree, in strictly increasing order
+/// * `root`: The root of the Merkle Sum Tree
+/// * `depth`: The depth of the Merkle Sum Tree
+/// * `sibling_leaf_node_hash_preimages`: The hash preimages of the sibling leaf nodes that are not included in the proof, in increasing order of index
+/// * `sibling_middle_node_hash_preimages`: The hash preimages of the sibling middle nodes that can't be computed from the included entries, level by level starting from the lowest one and in increasing order of index within a level
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MerkleMultiProof<const N_CURRENCIES: usize, const N_BYTES: usize>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub entries: Vec<Entry<N_CURRENCIES>>,
+    pub indices: Vec<usize>,
+    pub root: Node<N_CURRENCIES>,
+    pub depth: usize,
+    #[serde(with = "serialization::fp_array_vec")]
+    pub sibling_leaf_node_hash_preimages: Vec<[Fp; N_CURRENCIES + 1]>,
+    #[serde(with = "serialization::fp_array_vec")]
+    pub sibling_middle_node_hash_preimages: Vec<[Fp; N_CURRENCIES + 2]>,
+}
+
+/// A struct representing a proof that a username is not in a sorted Merkle Sum Tree, namely a proof of inclusion of the two adjacent leaves between which the username would be inserted.
+///
+/// Fields:
+/// * `username`: The username whose absence is proven
+/// * `lower`: The proof of the leaf preceding the username, whose username is lower. It is `None` if the username would be the first leaf of the tree
+/// * `upper`: The proof of the leaf following the username, whose username is greater, or of the first padding leaf if the username would follow the last entry. It is `None` if the username would follow the last leaf of a full tree
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MerkleNonInclusionProof<const N_CURRENCIES: usize, const N_BYTES: usize>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub username: String,
+    pub lower: Option<MerkleProof<N_CURRENCIES, N_BYTES>>,
+    pub upper: Option<MerkleProof<N_CURRENCIES, N_BYTES>>,
+}
+
+/// A struct representing a Merkle Proof of a [`QuaternaryMerkleSumTree`], whose middle nodes have 4 children.
+///
+/// Fields:
+/// * `entry`: The entry for which the proof is generated
+/// * `root`: The root of the Merkle Sum Tree
+/// * `sibling_leaf_node_hash_preimages`: The hash preimages of the 3 sibling leaf nodes, in increasing order of position. Each hash preimage is equal to `[sibling_username, sibling.balance[0], sibling.balance[1], ... sibling.balance[N_CURRENCIES - 1]]`
+/// * `sibling_middle_node_hash_preimages`: The hash preimages of the 3 sibling middle nodes of each level above the leaves, level by level starting from the lowest one and in increasing order of position within a level. Each hash preimage is equal to `[balances_sum[0], ..., balances_sum[N_CURRENCIES - 1], sibling_child[0].hash, sibling_child[1].hash, sibling_child[2].hash, sibling_child[3].hash]`
+/// * `path_indices`: The position of the node among its 3 siblings at each level, split in two bits from the least significant one. The bits are therefore the bits of the leaf index, and their number is twice the depth of the tree
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuaternaryMerkleProof<const N_CURRENCIES: usize, const N_BYTES: usize>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 4]: Sized,
+{
+    pub entry: Entry<N_CURRENCIES>,
+    pub root: Node<N_CURRENCIES>,
+    #[serde(with = "serialization::fp_array_vec")]
+    pub sibling_leaf_node_hash_preimages: Vec<[Fp; N_CURRENCIES + 1]>,
+    #[serde(with = "serialization::fp_array_vec")]
+    pub sibling_middle_node_hash_preimages: Vec<[Fp; N_CURRENCIES + 4]>,
+    #[serde(with = "serialization::fp_vec")]
+    pub path_indices: Vec<Fp>,
+}
+
+pub use aggregation_mst::AggregationMerkleSumTree;
+pub use diff::{diff_trees, EntryChange, TreeDiff};
+pub(crate) use dynamic_mst::{dispatch_currencies, with_currencies};
+pub use dynamic_mst::{DynamicEntry, DynamicMerkleSumTree, DynamicNode, MAX_DYNAMIC_CURRENCIES};
 pub use entry::Entry;
+pub use hasher::{
+    fp_to_fr, fr_to_fp, CircomPoseidonHasher, NodeHasher, NovaPoseidonHasher, PoseidonHasher,
+    MAX_CIRCOM_POSEIDON_INPUTS,
+};
+pub use mst::BalanceKind;
 pub use mst::Cryptocurrency;
 pub use mst::MerkleSumTree;
 pub use node::Node;
+pub use quaternary_mst::QuaternaryMerkleSumTree;
+pub use serialization::{fp_to_hex, hex_to_fp, MERKLE_PROOF_FORMAT_VERSION};
+pub use store::MerkleSumTreeStore;
 pub use tree::Tree;
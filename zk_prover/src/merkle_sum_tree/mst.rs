@@ -1,9 +1,17 @@
 use crate::merkle_sum_tree::utils::{
-    build_leaves_from_entries, build_merkle_tree_from_leaves, parse_csv_to_entries,
+    big_intify_username, build_leaves_from_entries_with_hasher,
+    build_merkle_tree_from_leaves_with_hasher, check_accumulated_balances, check_username,
+    format_decimal_balance, fp_to_big_uint, parse_csv_to_entries,
+    parse_csv_to_entries_with_manifest, parse_entries_from_source, CurrencyManifest, EntrySource,
+};
+use crate::merkle_sum_tree::{
+    Entry, MerkleNonInclusionProof, Node, NodeHasher, PoseidonHasher, Tree,
 };
-use crate::merkle_sum_tree::{Entry, Node, Tree};
-use halo2_proofs::halo2curves::bn256::Fr as Fp;
 use num_bigint::BigUint;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::marker::PhantomData;
 
 /// Merkle Sum Tree Data Structure.
 ///
@@ -13,22 +21,35 @@ use num_bigint::BigUint;
 /// * Each Middle Node contains a hash and #N_CURRENCIES balances. The hash is equal to `H(LeftChild.balance[0] + RightChild.balance[0], LeftChild.balance[1] + RightChild.balance[1], ..., LeftChild.balance[N_CURRENCIES - 1] + RightChild.balance[N_CURRENCIES - 1], LeftChild.hash, RightChild.hash)`. The balances are equal to the sum of the balances of the child nodes per each cryptocurrency.
 /// * The Root Node represents the committed state of the Tree and contains the sum of all the entries' balances per each cryptocurrency.
 ///
+/// # Padding
+///
+/// When the number of entries is not a power of two, the leaves are padded up to the next power of two (and at least 2 leaves) with padding leaves.
+/// A padding leaf is the leaf of an entry with username `0` and all-zero balances, namely its hash is `H(0, 0, ..., 0)` and its balances are all `0`.
+/// Padding leaves are appended after the entries, and the middle nodes above them are computed following the same rules as any other middle node.
+/// Padding does not change the root balances. Padding leaves are not part of the entries, so no proof can be generated for them.
+///
 /// # Type Parameters
 ///
 /// * `N_CURRENCIES`: The number of cryptocurrencies for each user account
 /// * `N_BYTES`: Range in which each node balance should lie
+/// * `H`: The hash function of the nodes. Only trees hashed with the default [`PoseidonHasher`] can be proven by the circuits of this crate
 #[derive(Debug, Clone)]
-pub struct MerkleSumTree<const N_CURRENCIES: usize, const N_BYTES: usize> {
+pub struct MerkleSumTree<
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+    H: NodeHasher = PoseidonHasher,
+> {
     root: Node<N_CURRENCIES>,
     nodes: Vec<Vec<Node<N_CURRENCIES>>>,
     depth: usize,
     entries: Vec<Entry<N_CURRENCIES>>,
     cryptocurrencies: Vec<Cryptocurrency>,
     is_sorted: bool,
+    hasher: PhantomData<H>,
 }
 
-impl<const N_CURRENCIES: usize, const N_BYTES: usize> Tree<N_CURRENCIES, N_BYTES>
-    for MerkleSumTree<N_CURRENCIES, N_BYTES>
+impl<const N_CURRENCIES: usize, const N_BYTES: usize, H: NodeHasher> Tree<N_CURRENCIES, N_BYTES, H>
+    for MerkleSumTree<N_CURRENCIES, N_BYTES, H>
 {
     fn root(&self) -> &Node<N_CURRENCIES> {
         &self.root
@@ -38,38 +59,91 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> Tree<N_CURRENCIES, N_BYTES
         &self.depth
     }
 
-    fn leaves(&self) -> &[Node<N_CURRENCIES>] {
-        &self.nodes[0]
+    fn cryptocurrencies(&self) -> &[Cryptocurrency] {
+        &self.cryptocurrencies
     }
 
-    fn nodes(&self) -> &[Vec<Node<N_CURRENCIES>>] {
-        &self.nodes
+    fn entries_count(&self) -> usize {
+        self.entries.len()
     }
 
-    fn get_entry(&self, index: usize) -> &Entry<N_CURRENCIES> {
-        &self.entries[index]
+    fn get_node(
+        &self,
+        level: usize,
+        index: usize,
+    ) -> Result<Node<N_CURRENCIES>, Box<dyn std::error::Error>> {
+        self.nodes
+            .get(level)
+            .and_then(|layer| layer.get(index))
+            .cloned()
+            .ok_or_else(|| Box::from("Node not found"))
     }
 
-    fn entries(&self) -> &[Entry<N_CURRENCIES>] {
-        &self.entries
-    }
-    fn cryptocurrencies(&self) -> &[Cryptocurrency] {
-        &self.cryptocurrencies
+    fn get_entry(&self, index: usize) -> Result<Entry<N_CURRENCIES>, Box<dyn std::error::Error>> {
+        self.entries
+            .get(index)
+            .cloned()
+            .ok_or_else(|| Box::from("Entry not found"))
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Cryptocurrency {
     pub name: String,
     pub chain: String,
+    /// Whether the balances of this column are owed by the custodian to the users or by the users to the custodian
+    #[serde(default)]
+    pub kind: BalanceKind,
+    /// Number of decimals of the cryptocurrency, namely the balances are expressed in units of `10^-decimals`
+    #[serde(default)]
+    pub decimals: u32,
 }
 
-impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES, N_BYTES> {
+/// Kind of a balance column of the tree.
+///
+/// Negative positions are never netted against the liabilities. A user holding a negative position on a currency gets a non-negative balance in a separate `Debt` column,
+/// so that every balance, and therefore every root sum, is still range checked by the circuit, while the root sums give both the total liabilities and the total debt for each currency.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BalanceKind {
+    /// Balance owed by the custodian to the user, parsed from a `balance_<cryptocurrency>_<chain>` column
+    #[default]
+    Liability,
+    /// Balance owed by the user to the custodian, such as a margin or lending debt, parsed from a `debt_<cryptocurrency>_<chain>` column
+    Debt,
+}
+
+impl BalanceKind {
+    /// Returns the prefix of the CSV column holding balances of this kind
+    pub fn column_prefix(&self) -> &'static str {
+        match self {
+            BalanceKind::Liability => "balance",
+            BalanceKind::Debt => "debt",
+        }
+    }
+}
+
+impl Cryptocurrency {
+    /// Returns the header of the CSV column holding the balances of this cryptocurrency, e.g. `balance_ETH_ETH` or `debt_ETH_ETH`
+    pub fn column_name(&self) -> String {
+        format!("{}_{}_{}", self.kind.column_prefix(), self.name, self.chain)
+    }
+
+    /// Formats a balance of this cryptocurrency, expressed in base units, as a human-readable decimal amount
+    pub fn format_balance(&self, balance: &BigUint) -> String {
+        format_decimal_balance(balance, self.decimals)
+    }
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize, H: NodeHasher>
+    MerkleSumTree<N_CURRENCIES, N_BYTES, H>
+{
     /// Builds a Merkle Sum Tree from a CSV file stored at `path`. The CSV file must be formatted as follows:
     ///
     /// `username,balance_<cryptocurrency>_<chain>,balance_<cryptocurrency>_<chain>,...`
     ///
     /// `dxGaEAii,11888,41163`
+    ///
+    /// Negative positions are represented by `debt_<cryptocurrency>_<chain>` columns holding non-negative amounts, see [`BalanceKind`].
     pub fn new(path: &str) -> Result<Self, Box<dyn std::error::Error>>
     where
         [usize; N_CURRENCIES + 1]: Sized,
@@ -79,7 +153,25 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
         Self::from_entries(entries, cryptocurrencies, false)
     }
 
-    /// Builds a Merkle Sum Tree from a CSV file stored at `path`. The MST leaves are sorted by the username byte values. The CSV file must be formatted as follows:
+    /// Builds a Merkle Sum Tree from a CSV file stored at `path`, whose balances are decimal amounts with the precision declared for each column by `manifest`, e.g.
+    ///
+    /// `dxGaEAii,1.5,41163.25`
+    ///
+    /// The balances are scaled into integer base units, and the decimals are carried by the cryptocurrencies of the tree.
+    pub fn new_with_manifest(
+        path: &str,
+        manifest: &CurrencyManifest,
+    ) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries_with_manifest::<&str, N_CURRENCIES, N_BYTES>(path, manifest)?;
+        Self::from_entries(entries, cryptocurrencies, false)
+    }
+
+    /// Builds a Merkle Sum Tree from a CSV file stored at `path`. The MST leaves are sorted by the integer representation of the usernames, as returned by [`Entry::username_as_big_uint`], so that the absence of a username can be proven with [`MerkleSumTree::generate_non_inclusion_proof`]. The CSV file must be formatted as follows:
     ///
     /// `username,balance_<cryptocurrency>_<chain>,balance_<cryptocurrency>_<chain>,...`
     ///
@@ -92,27 +184,82 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
         let (cryptocurrencies, mut entries) =
             parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>(path)?;
 
-        entries.sort_by(|a, b| a.username().cmp(b.username()));
+        entries.sort_by(|a, b| a.username_as_big_uint().cmp(b.username_as_big_uint()));
 
         Self::from_entries(entries, cryptocurrencies, true)
     }
 
+    /// Builds a Merkle Sum Tree out of the entries of `source`, such as a [`JsonLinesSource`](crate::merkle_sum_tree::utils::JsonLinesSource), whose balances are decimal amounts with the precision declared for each column by `manifest`.
+    /// The source is validated as a CSV file is by [`MerkleSumTree::new_with_manifest`], and the leaves are in the order of the records.
+    pub fn from_source<S: EntrySource>(
+        source: S,
+        manifest: &CurrencyManifest,
+    ) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let (cryptocurrencies, entries) =
+            parse_entries_from_source::<S, N_CURRENCIES, N_BYTES>(source, manifest)?;
+        Self::from_entries(entries, cryptocurrencies, false)
+    }
+
+    /// Builds a Merkle Sum Tree out of the entries of `source`, as [`MerkleSumTree::from_source`], with the leaves sorted as in [`MerkleSumTree::new_sorted`]
+    pub fn from_source_sorted<S: EntrySource>(
+        source: S,
+        manifest: &CurrencyManifest,
+    ) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let (cryptocurrencies, mut entries) =
+            parse_entries_from_source::<S, N_CURRENCIES, N_BYTES>(source, manifest)?;
+
+        entries.sort_by(|a, b| a.username_as_big_uint().cmp(b.username_as_big_uint()));
+
+        Self::from_entries(entries, cryptocurrencies, true)
+    }
+
+    /// Builds a Merkle Sum Tree from a vector of entries. The leaves are padded up to the next power of two with padding leaves.
+    /// If `is_sorted` is true, the entries must be in strictly increasing order of [`Entry::username_as_big_uint`].
     pub fn from_entries(
         entries: Vec<Entry<N_CURRENCIES>>,
         cryptocurrencies: Vec<Cryptocurrency>,
         is_sorted: bool,
-    ) -> Result<MerkleSumTree<N_CURRENCIES, N_BYTES>, Box<dyn std::error::Error>>
+    ) -> Result<MerkleSumTree<N_CURRENCIES, N_BYTES, H>, Box<dyn std::error::Error>>
     where
         [usize; N_CURRENCIES + 1]: Sized,
         [usize; N_CURRENCIES + 2]: Sized,
     {
-        let depth = (entries.len() as f64).log2().ceil() as usize;
+        if entries.is_empty() {
+            return Err(Box::from("Cannot build a Merkle Sum Tree without entries"));
+        }
+
+        if is_sorted
+            && entries
+                .windows(2)
+                .any(|pair| pair[0].username_as_big_uint() >= pair[1].username_as_big_uint())
+        {
+            return Err(Box::from(
+                "Entries of a sorted tree must be in strictly increasing order of username",
+            ));
+        }
+
+        // A tree must have at least one level, so a single entry is paired with a padding leaf
+        let depth = std::cmp::max(
+            entries.len().next_power_of_two().trailing_zeros() as usize,
+            1,
+        );
 
         let mut nodes = vec![];
 
-        let leaves = build_leaves_from_entries(&entries);
+        let mut leaves = build_leaves_from_entries_with_hasher::<N_CURRENCIES, H>(&entries);
+        leaves.resize(1 << depth, H::padding_leaf());
 
-        let root = build_merkle_tree_from_leaves(&leaves, depth, &mut nodes)?;
+        let root = build_merkle_tree_from_leaves_with_hasher::<N_CURRENCIES, H>(
+            &leaves, depth, &mut nodes,
+        )?;
 
         Ok(MerkleSumTree {
             root,
@@ -121,9 +268,25 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
             entries,
             cryptocurrencies,
             is_sorted,
+            hasher: PhantomData,
         })
     }
 
+    /// Returns a slice of the leaf nodes.
+    pub fn leaves(&self) -> &[Node<N_CURRENCIES>] {
+        &self.nodes[0]
+    }
+
+    /// Returns a slice of the nodes, level by level, from the leaves to the root.
+    pub fn nodes(&self) -> &[Vec<Node<N_CURRENCIES>>] {
+        &self.nodes
+    }
+
+    /// Returns a slice of the entries.
+    pub fn entries(&self) -> &[Entry<N_CURRENCIES>] {
+        &self.entries
+    }
+
     /// Updates the balances of the entry with the given username and returns the new root of the tree.
     ///
     /// # Arguments
@@ -146,30 +309,296 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
         let index = self.index_of_username(username)?;
 
         // Update the leaf node.
-        let updated_leaf = self.entries[index].recompute_leaf(new_balances);
+        let updated_leaf = self.entries[index].recompute_leaf_with_hasher::<H>(new_balances);
         self.nodes[0][index] = updated_leaf;
 
         // Recompute the hashes and balances up the tree.
-        let mut current_index = index;
-        for depth in 1..=self.depth {
-            let parent_index = current_index / 2;
-            let left_child = &self.nodes[depth - 1][2 * parent_index];
-            let right_child = &self.nodes[depth - 1][2 * parent_index + 1];
-
-            let mut hash_preimage = [Fp::zero(); N_CURRENCIES + 2];
-            for (i, balance) in hash_preimage.iter_mut().enumerate().take(N_CURRENCIES) {
-                *balance = left_child.balances[i] + right_child.balances[i];
+        self.recompute_ancestors(index, index);
+
+        Ok(self.root.clone())
+    }
+
+    /// Updates the balances of a batch of entries and returns the new root of the tree together with the positions of the nodes that changed.
+    ///
+    /// The updated leaves are recomputed in parallel. Then, for each level, the ancestors shared by the updated leaves are deduplicated and recomputed only once, in parallel.
+    /// If any username is not found or appears more than once in the batch, or if the updated root balances don't fit in `N_BYTES`, an error is returned and the tree is left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `updates`: The usernames of the entries to update, each paired with the new balances of the entry
+    ///
+    /// # Returns
+    ///
+    /// The new root of the tree and the `(level, index)` positions of the changed nodes, sorted by level and then by index. Level 0 is the leaves level.
+    pub fn update_leaves(
+        &mut self,
+        updates: &[(&str, [BigUint; N_CURRENCIES])],
+    ) -> Result<(Node<N_CURRENCIES>, Vec<(usize, usize)>), Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        // Resolve the indices first, so that the tree is not modified if any username is invalid
+        let mut indexed_updates = if self.is_sorted {
+            updates
+                .iter()
+                .map(|(username, balances)| Ok((self.index_of_username(username)?, balances)))
+                .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?
+        } else {
+            let indices: HashMap<&str, usize> = self
+                .entries
+                .iter()
+                .enumerate()
+                .map(|(index, entry)| (entry.username(), index))
+                .collect();
+            updates
+                .iter()
+                .map(|(username, balances)| {
+                    indices
+                        .get(username)
+                        .map(|index| (*index, balances))
+                        .ok_or_else(|| Box::from("Username not found"))
+                })
+                .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?
+        };
+
+        indexed_updates.sort_by_key(|(index, _)| *index);
+        if indexed_updates
+            .windows(2)
+            .any(|pair| pair[0].0 == pair[1].0)
+        {
+            return Err(Box::from("Duplicate username in updates"));
+        }
+
+        // The root balances must stay in the expected range after the updates
+        let mut root_balances: Vec<BigUint> = self
+            .root
+            .balances
+            .iter()
+            .copied()
+            .map(fp_to_big_uint)
+            .collect();
+        for (index, balances) in &indexed_updates {
+            for (currency, root_balance) in root_balances.iter_mut().enumerate() {
+                *root_balance -= &self.entries[*index].balances()[currency];
+                *root_balance += &balances[currency];
+            }
+        }
+        check_accumulated_balances::<N_BYTES>(&root_balances)?;
+
+        let mut touched: Vec<usize> = indexed_updates.iter().map(|(index, _)| *index).collect();
+
+        // Collect mutable references to the entries to update, so that they can be updated in parallel
+        let mut touched_iter = touched.iter().peekable();
+        let entries_to_update: Vec<&mut Entry<N_CURRENCIES>> = self
+            .entries
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                if touched_iter.peek() == Some(&&index) {
+                    touched_iter.next();
+                    Some(entry)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let updated_leaves: Vec<Node<N_CURRENCIES>> = entries_to_update
+            .into_par_iter()
+            .zip(indexed_updates.par_iter())
+            .map(|(entry, (_, balances))| entry.recompute_leaf_with_hasher::<H>(balances))
+            .collect();
+
+        let mut changed_positions = Vec::new();
+
+        for (index, leaf) in touched.iter().zip(updated_leaves.into_iter()) {
+            self.nodes[0][*index] = leaf;
+            changed_positions.push((0, *index));
+        }
+
+        // Recompute each touched ancestor only once per level
+        for level in 1..=self.depth {
+            touched = touched.iter().map(|index| index / 2).collect();
+            touched.dedup();
+
+            let previous_level = &self.nodes[level - 1];
+            let updated_nodes: Vec<Node<N_CURRENCIES>> = touched
+                .par_iter()
+                .map(|index| H::middle(&previous_level[2 * index], &previous_level[2 * index + 1]))
+                .collect();
+
+            for (index, node) in touched.iter().zip(updated_nodes.into_iter()) {
+                self.nodes[level][*index] = node;
+                changed_positions.push((level, *index));
             }
-            hash_preimage[N_CURRENCIES] = left_child.hash;
-            hash_preimage[N_CURRENCIES + 1] = right_child.hash;
+        }
+
+        self.root = self.nodes[self.depth][0].clone();
 
-            self.nodes[depth][parent_index] = Node::middle_node_from_preimage(&hash_preimage);
-            current_index = parent_index;
+        Ok((self.root.clone(), changed_positions))
+    }
+
+    /// Inserts a new entry in the tree and returns the new root of the tree.
+    ///
+    /// If the tree is sorted, the entry is inserted at the position that keeps the leaves sorted by [`Entry::username_as_big_uint`], shifting the following entries by one position.
+    /// Otherwise, the entry is appended after the last entry.
+    /// If the tree is already full, its depth is increased by one and the new leaves are padding leaves.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry`: The entry to insert
+    ///
+    /// # Returns
+    ///
+    /// The new root of the tree
+    pub fn insert_entry(
+        &mut self,
+        entry: Entry<N_CURRENCIES>,
+    ) -> Result<Node<N_CURRENCIES>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        if self.index_of_username(entry.username()).is_ok() {
+            return Err(Box::from("Username already exists"));
+        }
+
+        // The root balances must stay in the expected range after the insertion
+        for (root_balance, balance) in self.root.balances.iter().zip(entry.balances().iter()) {
+            if fp_to_big_uint(*root_balance) + balance
+                >= BigUint::from(2_usize).pow(8 * N_BYTES as u32)
+            {
+                return Err(Box::from(
+                    "Accumulated balance is not in the expected range, proof generation will fail!",
+                ));
+            }
         }
 
-        let root = self.nodes[self.depth][0].clone();
+        let index = if self.is_sorted {
+            // A different username may still map to the same integer as an existing entry
+            match self.search_username(entry.username()) {
+                Ok(_) => return Err(Box::from("Username collides with an existing entry")),
+                Err(index) => index,
+            }
+        } else {
+            self.entries.len()
+        };
 
-        Ok(root)
+        self.entries.insert(index, entry);
+
+        if self.entries.len() > self.nodes[0].len() {
+            self.grow();
+        }
+
+        // Every leaf from `index` onwards has been shifted (or added)
+        let last_index = self.entries.len() - 1;
+        self.recompute_leaves(index, last_index);
+        self.recompute_ancestors(index, last_index);
+
+        Ok(self.root.clone())
+    }
+
+    /// Removes the entry with the given username from the tree and returns the new root of the tree.
+    ///
+    /// If the tree is sorted, the following entries are shifted back by one position so that the leaves stay sorted and a padding leaf takes the place of the last leaf.
+    /// Otherwise, the last entry is moved into the freed position and a padding leaf takes the place of the last leaf, so that only the positions of the removed and the last entries change.
+    /// In both cases the freed leaf is reused by the next [`MerkleSumTree::insert_entry`]. The depth of the tree is never decreased.
+    ///
+    /// # Arguments
+    ///
+    /// * `username`: The username of the entry to remove
+    ///
+    /// # Returns
+    ///
+    /// The new root of the tree
+    pub fn remove_entry(
+        &mut self,
+        username: &str,
+    ) -> Result<Node<N_CURRENCIES>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let index = self.index_of_username(username)?;
+
+        if self.is_sorted {
+            let last_index = self.entries.len() - 1;
+            self.entries.remove(index);
+            self.recompute_leaves(index, last_index);
+            self.recompute_ancestors(index, last_index);
+        } else {
+            let last_index = self.entries.len() - 1;
+            self.entries.swap_remove(index);
+            for index in [index, last_index] {
+                self.recompute_leaves(index, index);
+                self.recompute_ancestors(index, index);
+            }
+        }
+
+        Ok(self.root.clone())
+    }
+
+    /// Recomputes the leaves in the range `[start, end]` from the entries. The leaves with no matching entry are set to padding leaves.
+    fn recompute_leaves(&mut self, start: usize, end: usize)
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+    {
+        let leaves: Vec<Node<N_CURRENCIES>> = (start..=end)
+            .into_par_iter()
+            .map(|index| match self.entries.get(index) {
+                Some(entry) => H::compute_leaf(entry),
+                None => H::padding_leaf(),
+            })
+            .collect();
+
+        self.nodes[0][start..=end].clone_from_slice(&leaves);
+    }
+
+    /// Recomputes the hashes and balances of the middle nodes that are ancestors of the leaves in the range `[start, end]`, up to the root.
+    fn recompute_ancestors(&mut self, start: usize, end: usize)
+    where
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let (mut start, mut end) = (start, end);
+
+        for level in 1..=self.depth {
+            start /= 2;
+            end /= 2;
+
+            for index in start..=end {
+                self.nodes[level][index] = H::middle(
+                    &self.nodes[level - 1][2 * index],
+                    &self.nodes[level - 1][2 * index + 1],
+                );
+            }
+        }
+
+        self.root = self.nodes[self.depth][0].clone();
+    }
+
+    /// Doubles the number of leaves of the tree by adding a new level on top of the current root.
+    /// The new leaves are padding leaves, so the new right subtree of the root is a subtree of padding leaves.
+    fn grow(&mut self)
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        // All the nodes of a subtree of padding leaves are equal at each level
+        let mut padding_node = H::padding_leaf();
+
+        for level in 0..=self.depth {
+            let nodes_in_level = self.nodes[level].len();
+            self.nodes[level].resize(2 * nodes_in_level, padding_node.clone());
+            padding_node = H::middle(&padding_node, &padding_node);
+        }
+
+        self.nodes.push(vec![H::middle(
+            &self.nodes[self.depth][0],
+            &self.nodes[self.depth][1],
+        )]);
+        self.depth += 1;
+        self.root = self.nodes[self.depth][0].clone();
     }
 
     /// Returns the index of the leaf with the matching username
@@ -185,9 +614,60 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
                 .map(|(index, _)| index)
                 .ok_or_else(|| Box::from("Username not found"))
         } else {
-            self.entries
-                .binary_search_by_key(&username, |entry| entry.username())
-                .map_err(|_| Box::from("Username not found"))
+            self.search_username(username)
+                .ok()
+                .filter(|index| self.entries[*index].username() == username)
+                .ok_or_else(|| Box::from("Username not found"))
+        }
+    }
+
+    /// Binary searches the entries of a sorted tree for the integer representation of `username`.
+    /// Returns the index of the matching entry, or the index at which an entry with this username would be inserted.
+    fn search_username(&self, username: &str) -> Result<usize, usize> {
+        let username_as_big_uint = big_intify_username(username);
+        self.entries
+            .binary_search_by(|entry| entry.username_as_big_uint().cmp(&username_as_big_uint))
+    }
+
+    /// Generates a proof that `username` is not in the tree, made of the proofs of the two adjacent leaves between which the username would be inserted.
+    /// The tree must be sorted.
+    ///
+    /// If the username would be inserted before the first entry, the lower proof is omitted. If it would be inserted after the last entry, the upper proof is the proof of the first padding leaf, or is omitted if the tree is full.
+    pub fn generate_non_inclusion_proof(
+        &self,
+        username: &str,
+    ) -> Result<MerkleNonInclusionProof<N_CURRENCIES, N_BYTES>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        if !self.is_sorted {
+            return Err(Box::from(
+                "Non-inclusion proofs can only be generated for sorted trees",
+            ));
         }
+        check_username(username)?;
+
+        let index = match self.search_username(username) {
+            Ok(_) => return Err(Box::from("Username is in the tree")),
+            Err(index) => index,
+        };
+
+        let lower = if index > 0 {
+            Some(self.generate_leaf_proof(index - 1)?)
+        } else {
+            None
+        };
+        let upper = if index < 1 << self.depth {
+            Some(self.generate_leaf_proof(index)?)
+        } else {
+            None
+        };
+
+        Ok(MerkleNonInclusionProof {
+            username: username.to_string(),
+            lower,
+            upper,
+        })
     }
 }
@@ -1,12 +1,17 @@
 use crate::chips::poseidon::poseidon_spec::PoseidonSpec;
 use crate::merkle_sum_tree::utils::big_uint_to_fp;
+use crate::merkle_sum_tree::{NodeHasher, PoseidonHasher};
 use halo2_gadgets::poseidon::primitives::{self as poseidon, ConstantLength};
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
 use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+/// A node of the MST. The constructors of this type hash with [`PoseidonHasher`], the ones of [`NodeHasher`] hash with any hasher.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Node<const N_CURRENCIES: usize> {
+    #[serde(with = "crate::merkle_sum_tree::serialization::fp")]
     pub hash: Fp,
+    #[serde(with = "crate::merkle_sum_tree::serialization::fp_array")]
     pub balances: [Fp; N_CURRENCIES],
 }
 impl<const N_CURRENCIES: usize> Node<N_CURRENCIES> {
@@ -25,12 +30,48 @@ impl<const N_CURRENCIES: usize> Node<N_CURRENCIES> {
 
         Node::leaf_node_from_preimage(&hash_preimage)
     }
+    /// Builds a salted leaf-level node of the MST
+    /// The leaf node hash is equal to `H(H(username, salt), balance[0], balance[1], ... balance[N_CURRENCIES - 1])`
+    /// The balances are equal to `balance[0], balance[1], ... balance[N_CURRENCIES - 1]`
+    pub fn salted_leaf(
+        username: &BigUint,
+        salt: Fp,
+        balances: &[BigUint; N_CURRENCIES],
+    ) -> Node<N_CURRENCIES>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+    {
+        let mut hash_preimage = [Fp::zero(); N_CURRENCIES + 1];
+        hash_preimage[0] = Self::username_commitment(username, salt);
+        for (i, balance) in hash_preimage.iter_mut().enumerate().skip(1) {
+            *balance = big_uint_to_fp(&balances[i - 1]);
+        }
+
+        Node::leaf_node_from_preimage(&hash_preimage)
+    }
+
+    /// Computes the commitment `H(username, salt)` to a salted username, which takes the place of the username in the leaf hash preimage
+    pub fn username_commitment(username: &BigUint, salt: Fp) -> Fp {
+        PoseidonHasher::hash_username(big_uint_to_fp(username), salt)
+    }
+
     /// Builds a "middle" (non-leaf-level) node of the MST
     /// The middle node hash is equal to `H(LeftChild.balance[0] + RightChild.balance[0], LeftChild.balance[1] + RightChild.balance[1], ..., LeftChild.balance[N_CURRENCIES - 1] + RightChild.balance[N_CURRENCIES - 1], LeftChild.hash, RightChild.hash)`
     /// The balances are equal to `LeftChild.balance[0] + RightChild.balance[0], LeftChild.balance[1] + RightChild.balance[1], ..., LeftChild.balance[N_CURRENCIES - 1] + RightChild.balance[N_CURRENCIES - 1]`
     pub fn middle(child_l: &Node<N_CURRENCIES>, child_r: &Node<N_CURRENCIES>) -> Node<N_CURRENCIES>
     where
         [(); N_CURRENCIES + 2]: Sized,
+    {
+        PoseidonHasher::middle(child_l, child_r)
+    }
+
+    /// Returns the hash preimage of the parent of two nodes, namely `LeftChild.balance[0] + RightChild.balance[0], LeftChild.balance[1] + RightChild.balance[1], ..., LeftChild.balance[N_CURRENCIES - 1] + RightChild.balance[N_CURRENCIES - 1], LeftChild.hash, RightChild.hash`
+    pub fn middle_node_hash_preimage(
+        child_l: &Node<N_CURRENCIES>,
+        child_r: &Node<N_CURRENCIES>,
+    ) -> [Fp; N_CURRENCIES + 2]
+    where
+        [usize; N_CURRENCIES + 2]: Sized,
     {
         let mut hash_preimage = [Fp::zero(); N_CURRENCIES + 2];
         for (i, balance) in hash_preimage.iter_mut().enumerate().take(N_CURRENCIES) {
@@ -39,74 +80,75 @@ impl<const N_CURRENCIES: usize> Node<N_CURRENCIES> {
         hash_preimage[N_CURRENCIES] = child_l.hash;
         hash_preimage[N_CURRENCIES + 1] = child_r.hash;
 
-        Node::middle_node_from_preimage(&hash_preimage)
+        hash_preimage
     }
 
-    pub fn init_empty() -> Node<N_CURRENCIES>
+    /// Builds a middle node of a 4-ary MST, see [`QuaternaryMerkleSumTree`](crate::merkle_sum_tree::QuaternaryMerkleSumTree)
+    /// The middle node hash is equal to `H(Child[0].balance[0] + ... + Child[3].balance[0], ..., Child[0].balance[N_CURRENCIES - 1] + ... + Child[3].balance[N_CURRENCIES - 1], Child[0].hash, Child[1].hash, Child[2].hash, Child[3].hash)`
+    /// The balances are equal to `Child[0].balance[0] + ... + Child[3].balance[0], ..., Child[0].balance[N_CURRENCIES - 1] + ... + Child[3].balance[N_CURRENCIES - 1]`
+    pub fn quaternary_middle(children: &[Node<N_CURRENCIES>; 4]) -> Node<N_CURRENCIES>
     where
-        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 4]: Sized,
     {
-        Node {
-            hash: Fp::zero(),
-            balances: [Fp::zero(); N_CURRENCIES],
+        let mut hash_preimage = [Fp::zero(); N_CURRENCIES + 4];
+        for (i, balance) in hash_preimage.iter_mut().enumerate().take(N_CURRENCIES) {
+            *balance = children.iter().map(|child| child.balances[i]).sum();
+        }
+        for (i, child) in children.iter().enumerate() {
+            hash_preimage[N_CURRENCIES + i] = child.hash;
         }
+
+        Node::quaternary_middle_node_from_preimage(&hash_preimage)
     }
 
-    pub fn leaf_node_from_preimage(preimage: &[Fp; N_CURRENCIES + 1]) -> Node<N_CURRENCIES>
+    /// Builds a middle node of a 4-ary MST from its hash preimage, see [`Node::quaternary_middle`]
+    pub fn quaternary_middle_node_from_preimage(
+        preimage: &[Fp; N_CURRENCIES + 4],
+    ) -> Node<N_CURRENCIES>
     where
-        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 4]: Sized,
     {
         Node {
-            hash: Self::poseidon_hash_leaf(preimage[0], preimage[1..].try_into().unwrap()),
-            balances: preimage[1..].try_into().unwrap(),
+            hash: poseidon::Hash::<Fp, PoseidonSpec, ConstantLength<{ N_CURRENCIES + 4 }>, 2, 1>::init()
+                .hash(*preimage),
+            balances: preimage[0..N_CURRENCIES].try_into().unwrap(),
         }
     }
 
-    /// Builds a middle-level node of the MST
-    /// The hash preimage must be equal to `LeftChild.balance[0] + RightChild.balance[0], LeftChild.balance[1] + RightChild.balance[1], ..., LeftChild.balance[N_CURRENCIES - 1] + RightChild.balance[N_CURRENCIES - 1], LeftChild.hash, RightChild.hash`
-    /// The balances are equal to `LeftChild.balance[0] + RightChild.balance[0], LeftChild.balance[1] + RightChild.balance[1], ..., LeftChild.balance[N_CURRENCIES - 1] + RightChild.balance[N_CURRENCIES - 1]`
-    pub fn middle_node_from_preimage(preimage: &[Fp; N_CURRENCIES + 2]) -> Node<N_CURRENCIES>
+    /// Builds the padding leaf used to fill the MST leaves up to the next power of two
+    /// The padding leaf hash is equal to `H(0, 0, ..., 0)`, namely the leaf hash of an entry with username `0` and all-zero balances
+    /// The balances are equal to `0, 0, ..., 0`
+    pub fn padding_leaf() -> Node<N_CURRENCIES>
     where
-        [usize; N_CURRENCIES + 2]: Sized,
+        [usize; N_CURRENCIES + 1]: Sized,
     {
-        Node {
-            hash: Self::poseidon_hash_middle(
-                preimage[0..N_CURRENCIES].try_into().unwrap(),
-                preimage[N_CURRENCIES],
-                preimage[N_CURRENCIES + 1],
-            ),
-            balances: preimage[0..N_CURRENCIES].try_into().unwrap(),
-        }
+        PoseidonHasher::padding_leaf()
     }
 
-    fn poseidon_hash_middle(
-        balances_sum: [Fp; N_CURRENCIES],
-        hash_child_left: Fp,
-        hash_child_right: Fp,
-    ) -> Fp
+    pub fn init_empty() -> Node<N_CURRENCIES>
     where
-        [usize; N_CURRENCIES + 2]: Sized,
+        [usize; N_CURRENCIES + 1]: Sized,
     {
-        let mut hash_inputs: [Fp; N_CURRENCIES + 2] = [Fp::zero(); N_CURRENCIES + 2];
-
-        hash_inputs[0..N_CURRENCIES].copy_from_slice(&balances_sum);
-        hash_inputs[N_CURRENCIES] = hash_child_left;
-        hash_inputs[N_CURRENCIES + 1] = hash_child_right;
-
-        poseidon::Hash::<Fp, PoseidonSpec, ConstantLength<{ N_CURRENCIES + 2 }>, 2, 1>::init()
-            .hash(hash_inputs)
+        Node {
+            hash: Fp::zero(),
+            balances: [Fp::zero(); N_CURRENCIES],
+        }
     }
 
-    fn poseidon_hash_leaf(username: Fp, balances: [Fp; N_CURRENCIES]) -> Fp
+    pub fn leaf_node_from_preimage(preimage: &[Fp; N_CURRENCIES + 1]) -> Node<N_CURRENCIES>
     where
         [usize; N_CURRENCIES + 1]: Sized,
     {
-        let mut hash_inputs: [Fp; N_CURRENCIES + 1] = [Fp::zero(); N_CURRENCIES + 1];
-
-        hash_inputs[0] = username;
-        hash_inputs[1..N_CURRENCIES + 1].copy_from_slice(&balances);
+        PoseidonHasher::leaf_node_from_preimage(preimage)
+    }
 
-        poseidon::Hash::<Fp, PoseidonSpec, ConstantLength<{ N_CURRENCIES + 1 }>, 2, 1>::init()
-            .hash(hash_inputs)
+    /// Builds a middle-level node of the MST
+    /// The hash preimage must be equal to `LeftChild.balance[0] + RightChild.balance[0], LeftChild.balance[1] + RightChild.balance[1], ..., LeftChild.balance[N_CURRENCIES - 1] + RightChild.balance[N_CURRENCIES - 1], LeftChild.hash, RightChild.hash`
+    /// The balances are equal to `LeftChild.balance[0] + RightChild.balance[0], LeftChild.balance[1] + RightChild.balance[1], ..., LeftChild.balance[N_CURRENCIES - 1] + RightChild.balance[N_CURRENCIES - 1]`
+    pub fn middle_node_from_preimage(preimage: &[Fp; N_CURRENCIES + 2]) -> Node<N_CURRENCIES>
+    where
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        PoseidonHasher::middle_node_from_preimage(preimage)
     }
 }
@@ -0,0 +1,241 @@
+use crate::merkle_sum_tree::utils::{big_uint_to_fp, parse_csv_to_entries};
+use crate::merkle_sum_tree::verifier::verify_quaternary_merkle_proof;
+use crate::merkle_sum_tree::{Cryptocurrency, Entry, Node, QuaternaryMerkleProof};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use rayon::prelude::*;
+
+/// 4-ary Merkle Sum Tree Data Structure.
+///
+/// A 4-ary Merkle Sum Tree follows the same rules as a [`MerkleSumTree`](crate::merkle_sum_tree::MerkleSumTree), except that each middle node has 4 children:
+/// * Each Leaf Node is built from an entry as in the binary tree, namely its hash is equal to `H(username, balance[0], balance[1], ... balance[N_CURRENCIES - 1])`.
+/// * Each Middle Node contains a hash and #N_CURRENCIES balances. The hash is equal to `H(Child[0].balance[0] + ... + Child[3].balance[0], ..., Child[0].balance[N_CURRENCIES - 1] + ... + Child[3].balance[N_CURRENCIES - 1], Child[0].hash, Child[1].hash, Child[2].hash, Child[3].hash)`. The balances are equal to the sum of the balances of the child nodes per each cryptocurrency.
+///
+/// The depth of a tree of 4^d leaves is `d`, half the depth of the binary tree with the same number of leaves. In exchange, a proof carries 3 siblings per level instead of 1.
+/// The leaves are padded up to the next power of four (and at least 4 leaves) with padding leaves, as in the binary tree.
+///
+/// # Type Parameters
+///
+/// * `N_CURRENCIES`: The number of cryptocurrencies for each user account
+/// * `N_BYTES`: Range in which each node balance should lie
+#[derive(Debug, Clone)]
+pub struct QuaternaryMerkleSumTree<const N_CURRENCIES: usize, const N_BYTES: usize> {
+    root: Node<N_CURRENCIES>,
+    nodes: Vec<Vec<Node<N_CURRENCIES>>>,
+    depth: usize,
+    entries: Vec<Entry<N_CURRENCIES>>,
+    cryptocurrencies: Vec<Cryptocurrency>,
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize>
+    QuaternaryMerkleSumTree<N_CURRENCIES, N_BYTES>
+{
+    /// Builds a 4-ary Merkle Sum Tree from a CSV file stored at `path`, formatted as for [`MerkleSumTree::new`](crate::merkle_sum_tree::MerkleSumTree::new).
+    pub fn new(path: &str) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 4]: Sized,
+    {
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>(path)?;
+        Self::from_entries(entries, cryptocurrencies)
+    }
+
+    /// Builds a 4-ary Merkle Sum Tree from a vector of entries. The leaves are padded up to the next power of four with padding leaves.
+    pub fn from_entries(
+        entries: Vec<Entry<N_CURRENCIES>>,
+        cryptocurrencies: Vec<Cryptocurrency>,
+    ) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 4]: Sized,
+    {
+        if entries.is_empty() {
+            return Err(Box::from(
+                "Cannot build a 4-ary Merkle Sum Tree without entries",
+            ));
+        }
+
+        // A tree must have at least one level, so a single entry is grouped with padding leaves
+        let mut depth = 1;
+        while 1 << (2 * depth) < entries.len() {
+            depth += 1;
+        }
+
+        let mut leaves: Vec<Node<N_CURRENCIES>> = entries
+            .par_iter()
+            .map(|entry| entry.compute_leaf())
+            .collect();
+        leaves.resize(1 << (2 * depth), Node::padding_leaf());
+
+        let mut nodes = vec![leaves];
+        for _ in 0..depth {
+            let level = nodes
+                .last()
+                .unwrap()
+                .par_chunks(4)
+                .map(|children| Node::quaternary_middle(children.try_into().unwrap()))
+                .collect();
+            nodes.push(level);
+        }
+
+        Ok(QuaternaryMerkleSumTree {
+            root: nodes[depth][0].clone(),
+            nodes,
+            depth,
+            entries,
+            cryptocurrencies,
+        })
+    }
+
+    /// Returns a reference to the root node.
+    pub fn root(&self) -> &Node<N_CURRENCIES> {
+        &self.root
+    }
+
+    /// Returns the depth of the tree, namely the number of middle levels.
+    pub fn depth(&self) -> &usize {
+        &self.depth
+    }
+
+    /// Returns the cryptocurrencies whose balances are in the tree.
+    pub fn cryptocurrencies(&self) -> &[Cryptocurrency] {
+        &self.cryptocurrencies
+    }
+
+    /// Returns a slice of the leaf nodes.
+    pub fn leaves(&self) -> &[Node<N_CURRENCIES>] {
+        &self.nodes[0]
+    }
+
+    /// Returns a slice of the nodes, level by level. Level 0 is the leaves level and level `depth` is the root level.
+    pub fn nodes(&self) -> &[Vec<Node<N_CURRENCIES>>] {
+        &self.nodes
+    }
+
+    /// Returns a slice of the entries.
+    pub fn entries(&self) -> &[Entry<N_CURRENCIES>] {
+        &self.entries
+    }
+
+    /// Returns the index of the leaf with the matching username
+    pub fn index_of_username(&self, username: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        self.entries
+            .iter()
+            .position(|entry| entry.username() == username)
+            .ok_or_else(|| Box::from("Username not found"))
+    }
+
+    /// Returns the hash preimage of a leaf node. The hash preimage of a padding leaf is `[0, 0, ..., 0]`.
+    pub fn get_leaf_node_hash_preimage(
+        &self,
+        index: usize,
+    ) -> Result<[Fp; N_CURRENCIES + 1], Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+    {
+        if index >= self.nodes[0].len() {
+            return Err(Box::from("Node not found"));
+        }
+
+        let mut preimage = [Fp::zero(); N_CURRENCIES + 1];
+
+        // Leaves past the last entry are padding leaves
+        if let Some(entry) = self.entries.get(index) {
+            preimage[0] = entry.username_commitment();
+            for (i, balance) in preimage.iter_mut().enumerate().skip(1) {
+                *balance = big_uint_to_fp(&entry.balances()[i - 1]);
+            }
+        }
+
+        Ok(preimage)
+    }
+
+    /// Returns the hash preimage of a middle node, namely the sums of the balances of its 4 children followed by their hashes.
+    pub fn get_middle_node_hash_preimage(
+        &self,
+        level: usize,
+        index: usize,
+    ) -> Result<[Fp; N_CURRENCIES + 4], Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 4]: Sized,
+    {
+        if level == 0 || level > self.depth {
+            return Err(Box::from("Invalid depth"));
+        }
+
+        if index >= self.nodes[level].len() {
+            return Err(Box::from("Node not found"));
+        }
+
+        let children = &self.nodes[level - 1][4 * index..4 * index + 4];
+
+        let mut preimage = [Fp::zero(); N_CURRENCIES + 4];
+        for (i, balance) in preimage.iter_mut().enumerate().take(N_CURRENCIES) {
+            *balance = children.iter().map(|child| child.balances[i]).sum();
+        }
+        for (i, child) in children.iter().enumerate() {
+            preimage[N_CURRENCIES + i] = child.hash;
+        }
+
+        Ok(preimage)
+    }
+
+    /// Generates a QuaternaryMerkleProof for the user with the given index. Padding leaves have no user, so no proof can be generated for them.
+    pub fn generate_proof(
+        &self,
+        index: usize,
+    ) -> Result<QuaternaryMerkleProof<N_CURRENCIES, N_BYTES>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 4]: Sized,
+    {
+        if index >= self.entries.len() {
+            return Err(Box::from("Index out of bounds"));
+        }
+
+        let mut sibling_leaf_node_hash_preimages = Vec::with_capacity(3);
+        let mut sibling_middle_node_hash_preimages = Vec::with_capacity(3 * (self.depth - 1));
+        let mut path_indices = Vec::with_capacity(2 * self.depth);
+        let mut current_index = index;
+
+        for level in 0..self.depth {
+            let position = current_index % 4;
+            let first_sibling_index = current_index - position;
+
+            for sibling_index in (first_sibling_index..first_sibling_index + 4)
+                .filter(|sibling_index| *sibling_index != current_index)
+            {
+                if level == 0 {
+                    sibling_leaf_node_hash_preimages
+                        .push(self.get_leaf_node_hash_preimage(sibling_index)?);
+                } else {
+                    sibling_middle_node_hash_preimages
+                        .push(self.get_middle_node_hash_preimage(level, sibling_index)?);
+                }
+            }
+
+            // The position is split in two bits, from the least significant one
+            path_indices.push(Fp::from((position % 2) as u64));
+            path_indices.push(Fp::from((position / 2) as u64));
+            current_index /= 4;
+        }
+
+        Ok(QuaternaryMerkleProof {
+            entry: self.entries[index].clone(),
+            root: self.root.clone(),
+            sibling_leaf_node_hash_preimages,
+            sibling_middle_node_hash_preimages,
+            path_indices,
+        })
+    }
+
+    /// Verifies a QuaternaryMerkleProof against the root embedded in it. A malformed proof is rejected rather than causing a panic.
+    /// Users that only hold a proof can use [`verify_quaternary_merkle_proof`] instead, which doesn't require a tree.
+    pub fn verify_proof(&self, proof: &QuaternaryMerkleProof<N_CURRENCIES, N_BYTES>) -> bool
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 4]: Sized,
+    {
+        verify_quaternary_merkle_proof(proof, proof.root.hash, &proof.root.balances).is_ok()
+    }
+}
@@ -0,0 +1,322 @@
+//! Serialization of the Merkle Sum Tree types.
+//!
+//! In human readable formats, such as JSON, field elements are encoded as canonical `0x`-prefixed big-endian hex strings of 64 digits and balances as decimal strings.
+//! In binary formats, such as bincode, field elements and balances are encoded as 32-byte little-endian integers.
+use crate::merkle_sum_tree::{Entry, MerkleProof};
+use halo2_proofs::halo2curves::{bn256::Fr as Fp, group::ff::PrimeField};
+use num_bigint::BigUint;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Version of the serialization format of a [`MerkleProof`]. It must be bumped whenever the format changes.
+pub const MERKLE_PROOF_FORMAT_VERSION: u32 = 2;
+
+/// Encodes a field element as a canonical `0x`-prefixed big-endian hex string of 64 digits
+pub fn fp_to_hex(value: Fp) -> String {
+    let mut bytes = value.to_repr();
+    bytes.reverse();
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Decodes a field element from a `0x`-prefixed big-endian hex string of 64 digits. Values that are not lower than the field modulus are rejected.
+pub fn hex_to_fp(value: &str) -> Result<Fp, Box<dyn std::error::Error>> {
+    let digits = value
+        .strip_prefix("0x")
+        .ok_or("Field element hex string must start with 0x")?;
+    if digits.len() != 64 {
+        return Err(Box::from("Field element hex string must have 64 digits"));
+    }
+
+    let mut bytes: [u8; 32] = hex::decode(digits)?.try_into().unwrap();
+    bytes.reverse();
+
+    Option::from(Fp::from_repr(bytes)).ok_or_else(|| Box::from("Field element is not canonical"))
+}
+
+/// Serde wrapper of a field element
+struct SerdeFp(Fp);
+
+impl Serialize for SerdeFp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&fp_to_hex(self.0))
+        } else {
+            self.0.to_repr().serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SerdeFp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let value = String::deserialize(deserializer)?;
+            hex_to_fp(&value).map(SerdeFp).map_err(D::Error::custom)
+        } else {
+            let bytes = <[u8; 32]>::deserialize(deserializer)?;
+            Option::from(Fp::from_repr(bytes))
+                .map(SerdeFp)
+                .ok_or_else(|| D::Error::custom("Field element is not canonical"))
+        }
+    }
+}
+
+/// Serde wrapper of a balance
+struct SerdeBigUint(BigUint);
+
+impl Serialize for SerdeBigUint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.0.to_str_radix(10))
+        } else {
+            let mut bytes = self.0.to_bytes_le();
+            if bytes.len() > 32 {
+                return Err(serde::ser::Error::custom(
+                    "Balance does not fit in 32 bytes",
+                ));
+            }
+            bytes.resize(32, 0);
+            <[u8; 32]>::try_from(bytes).unwrap().serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SerdeBigUint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let value = String::deserialize(deserializer)?;
+            BigUint::parse_bytes(value.as_bytes(), 10)
+                .map(SerdeBigUint)
+                .ok_or_else(|| D::Error::custom("Invalid balance"))
+        } else {
+            let bytes = <[u8; 32]>::deserialize(deserializer)?;
+            Ok(SerdeBigUint(BigUint::from_bytes_le(&bytes)))
+        }
+    }
+}
+
+fn vec_to_array<T, E: serde::de::Error, const N: usize>(values: Vec<T>) -> Result<[T; N], E> {
+    let len = values.len();
+    values
+        .try_into()
+        .map_err(|_| E::invalid_length(len, &format!("{} elements", N).as_str()))
+}
+
+/// Serde `with` module for a field element
+pub(crate) mod fp {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Fp, serializer: S) -> Result<S::Ok, S::Error> {
+        SerdeFp(*value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Fp, D::Error> {
+        Ok(SerdeFp::deserialize(deserializer)?.0)
+    }
+}
+
+/// Serde `with` module for an optional field element
+pub(crate) mod option_fp {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Option<Fp>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.map(SerdeFp).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Fp>, D::Error> {
+        Ok(Option::<SerdeFp>::deserialize(deserializer)?.map(|value| value.0))
+    }
+}
+
+/// Serde `with` module for a vector of field elements
+pub(crate) mod fp_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(values: &[Fp], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(|value| SerdeFp(*value)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Fp>, D::Error> {
+        let values = Vec::<SerdeFp>::deserialize(deserializer)?;
+        Ok(values.into_iter().map(|value| value.0).collect())
+    }
+}
+
+/// Serde `with` module for a const-generic array of field elements
+pub(crate) mod fp_array {
+    use super::*;
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        values: &[Fp; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        fp_vec::serialize(values, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[Fp; N], D::Error> {
+        vec_to_array(fp_vec::deserialize(deserializer)?)
+    }
+}
+
+/// Serde `with` module for a vector of const-generic arrays of field elements
+pub(crate) mod fp_array_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        values: &[[Fp; N]],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(|array| {
+            array
+                .iter()
+                .map(|value| SerdeFp(*value))
+                .collect::<Vec<_>>()
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<Vec<[Fp; N]>, D::Error> {
+        Vec::<Vec<SerdeFp>>::deserialize(deserializer)?
+            .into_iter()
+            .map(|values| vec_to_array(values.into_iter().map(|value| value.0).collect()))
+            .collect()
+    }
+}
+
+/// Serde `with` module for a const-generic array of balances
+pub(crate) mod big_uint_array {
+    use super::*;
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        values: &[BigUint; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(|value| SerdeBigUint(value.clone())))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[BigUint; N], D::Error> {
+        let values = Vec::<SerdeBigUint>::deserialize(deserializer)?;
+        vec_to_array(values.into_iter().map(|value| value.0).collect())
+    }
+}
+
+/// Serialized form of an [`Entry`]. The username as big uint is not serialized, as it is derived from the username. The salt is `null` for an entry that is not salted.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SerializedEntry<const N_CURRENCIES: usize> {
+    username: String,
+    #[serde(with = "big_uint_array")]
+    balances: [BigUint; N_CURRENCIES],
+    #[serde(with = "option_fp")]
+    salt: Option<Fp>,
+}
+
+impl<const N_CURRENCIES: usize> From<Entry<N_CURRENCIES>> for SerializedEntry<N_CURRENCIES> {
+    fn from(entry: Entry<N_CURRENCIES>) -> Self {
+        SerializedEntry {
+            username: entry.username().to_string(),
+            balances: entry.balances().clone(),
+            salt: entry.salt(),
+        }
+    }
+}
+
+impl<const N_CURRENCIES: usize> TryFrom<SerializedEntry<N_CURRENCIES>> for Entry<N_CURRENCIES> {
+    type Error = &'static str;
+
+    fn try_from(entry: SerializedEntry<N_CURRENCIES>) -> Result<Self, Self::Error> {
+        // The entry of a padding leaf, as found in the upper proof of a non-inclusion proof, is the only entry with an empty username
+        if entry.username.is_empty()
+            && entry.salt.is_none()
+            && entry
+                .balances
+                .iter()
+                .all(|balance| *balance == BigUint::from(0u32))
+        {
+            return Ok(Entry::init_empty());
+        }
+
+        match entry.salt {
+            Some(salt) => Entry::new_salted(entry.username, entry.balances, salt),
+            None => Entry::new(entry.username, entry.balances),
+        }
+    }
+}
+
+/// Envelope tagging a serialized [`MerkleProof`] with the version of the format
+#[derive(Serialize)]
+struct VersionedMerkleProofRef<'a, const N_CURRENCIES: usize, const N_BYTES: usize>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    version: u32,
+    proof: &'a MerkleProof<N_CURRENCIES, N_BYTES>,
+}
+
+/// Version tag read ahead of a serialized [`MerkleProof`], so that the version is checked before the proof is decoded
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+/// Proof read from a JSON envelope, once its version has been checked
+#[derive(Deserialize)]
+struct Proof<const N_CURRENCIES: usize, const N_BYTES: usize>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    proof: MerkleProof<N_CURRENCIES, N_BYTES>,
+}
+
+fn check_version(version: u32) -> Result<(), Box<dyn std::error::Error>> {
+    if version != MERKLE_PROOF_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported Merkle proof format version {}, expected {}",
+            version, MERKLE_PROOF_FORMAT_VERSION
+        )
+        .into());
+    }
+    Ok(())
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleProof<N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    /// Serializes the proof to JSON, tagged with the format version
+    pub fn to_json(&self) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(serde_json::to_string(&VersionedMerkleProofRef {
+            version: MERKLE_PROOF_FORMAT_VERSION,
+            proof: self,
+        })?)
+    }
+
+    /// Deserializes a proof from JSON. The format version is checked before the proof is decoded.
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        check_version(serde_json::from_str::<Version>(json)?.version)?;
+        let envelope: Proof<N_CURRENCIES, N_BYTES> = serde_json::from_str(json)?;
+        Ok(envelope.proof)
+    }
+
+    /// Serializes the proof to a compact binary encoding, tagged with the format version
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ok(bincode::serialize(&VersionedMerkleProofRef {
+            version: MERKLE_PROOF_FORMAT_VERSION,
+            proof: self,
+        })?)
+    }
+
+    /// Deserializes a proof from its compact binary encoding. The format version is checked before the proof is decoded.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        // The envelope is encoded as the version followed by the proof
+        check_version(bincode::deserialize::<u32>(bytes)?)?;
+        let (_, proof): (u32, MerkleProof<N_CURRENCIES, N_BYTES>) = bincode::deserialize(bytes)?;
+        Ok(proof)
+    }
+}
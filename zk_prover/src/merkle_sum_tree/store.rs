@@ -0,0 +1,424 @@
+use crate::merkle_sum_tree::utils::MAX_DECIMALS;
+use crate::merkle_sum_tree::{
+    BalanceKind, Cryptocurrency, Entry, Node, NodeHasher, PoseidonHasher, Tree,
+};
+use halo2_proofs::halo2curves::{bn256::Fr as Fp, group::ff::PrimeField};
+use num_bigint::BigUint;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Mutex;
+
+const MAGIC: &[u8; 8] = b"SUMMAMST";
+const VERSION: u32 = 4;
+const FIELD_ELEMENT_SIZE: usize = 32;
+/// The maximum depth accepted in the header of a file, so that the number of nodes `2^(depth + 1) - 1` fits in 64 bits
+const MAX_DEPTH: usize = 62;
+
+/// Persistent, file-backed storage of a Merkle Sum Tree.
+///
+/// Only the header of the file, namely the depth, the number of entries, the cryptocurrencies and the root, is kept in memory.
+/// Nodes and entries are read from the file on demand, so that a proof can be generated without loading the whole tree.
+///
+/// The file is laid out as follows. All the integers are little-endian and all the field elements are encoded in their canonical 32-byte little-endian representation.
+///
+/// * Header: `"SUMMAMST"`, version (`u32`), `N_CURRENCIES` (`u32`), depth (`u32`), number of entries (`u64`), number of cryptocurrencies (`u32`), followed by the name and the chain of each cryptocurrency, each as a length (`u32`) and UTF-8 bytes, by its balance kind (`u8`, `0` for liabilities and `1` for debts) and by its decimals (`u32`).
+/// * Nodes: the nodes level by level, from the leaves (2^depth nodes) to the root (1 node). Each node takes `(N_CURRENCIES + 1) * 32` bytes, namely its hash followed by its balances.
+/// * Entries index: the offset (`u64`) of each entry record, relative to the start of the entries records.
+/// * Entries records: for each entry, the username as a length (`u32`) and UTF-8 bytes, followed by its balances as 32-byte little-endian integers and by its salt as a flag (`u8`) followed, if the flag is `1`, by the salt field element.
+///
+/// # Type Parameters
+///
+/// * `N_CURRENCIES`: The number of cryptocurrencies for each user account
+/// * `N_BYTES`: Range in which each node balance should lie
+/// * `H`: The hasher of the nodes, used to check the root when the file is opened and the nodes in [`MerkleSumTreeStore::verify_integrity`]. The file doesn't record it, so a file must be opened with the hasher it was written with.
+#[derive(Debug)]
+pub struct MerkleSumTreeStore<
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+    H: NodeHasher = PoseidonHasher,
+> {
+    root: Node<N_CURRENCIES>,
+    depth: usize,
+    entries_count: usize,
+    cryptocurrencies: Vec<Cryptocurrency>,
+    nodes_offset: u64,
+    entries_index_offset: u64,
+    entries_offset: u64,
+    file: Mutex<File>,
+    hasher: PhantomData<H>,
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize, H: NodeHasher> Tree<N_CURRENCIES, N_BYTES, H>
+    for MerkleSumTreeStore<N_CURRENCIES, N_BYTES, H>
+{
+    fn root(&self) -> &Node<N_CURRENCIES> {
+        &self.root
+    }
+
+    fn depth(&self) -> &usize {
+        &self.depth
+    }
+
+    fn cryptocurrencies(&self) -> &[Cryptocurrency] {
+        &self.cryptocurrencies
+    }
+
+    fn entries_count(&self) -> usize {
+        self.entries_count
+    }
+
+    fn get_node(
+        &self,
+        level: usize,
+        index: usize,
+    ) -> Result<Node<N_CURRENCIES>, Box<dyn std::error::Error>> {
+        if level > self.depth || index >= 1 << (self.depth - level) {
+            return Err(Box::from("Node not found"));
+        }
+
+        let mut file = self.file.lock().map_err(|_| "Failed to lock the file")?;
+        file.seek(SeekFrom::Start(
+            self.nodes_offset
+                + (Self::node_position(self.depth, level, index) * Self::node_size()) as u64,
+        ))?;
+
+        read_node(&mut *file)
+    }
+
+    fn get_entry(&self, index: usize) -> Result<Entry<N_CURRENCIES>, Box<dyn std::error::Error>> {
+        if index >= self.entries_count {
+            return Err(Box::from("Entry not found"));
+        }
+
+        let mut file = self.file.lock().map_err(|_| "Failed to lock the file")?;
+        file.seek(SeekFrom::Start(
+            self.entries_index_offset + 8 * index as u64,
+        ))?;
+        let entry_offset = read_u64(&mut *file)?;
+
+        file.seek(SeekFrom::Start(self.entries_offset + entry_offset))?;
+        read_entry(&mut *file)
+    }
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize, H: NodeHasher>
+    MerkleSumTreeStore<N_CURRENCIES, N_BYTES, H>
+{
+    /// Writes the tree to a file stored at `path`. The file can be reopened with [`MerkleSumTreeStore::open`].
+    pub fn write<T: Tree<N_CURRENCIES, N_BYTES, H>, P: AsRef<Path>>(
+        tree: &T,
+        path: P,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let depth = *tree.depth();
+        if tree.cryptocurrencies().len() != N_CURRENCIES {
+            return Err(Box::from("Number of cryptocurrencies does not match"));
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        // Header
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(N_CURRENCIES as u32).to_le_bytes())?;
+        writer.write_all(&(depth as u32).to_le_bytes())?;
+        writer.write_all(&(tree.entries_count() as u64).to_le_bytes())?;
+        writer.write_all(&(tree.cryptocurrencies().len() as u32).to_le_bytes())?;
+        for cryptocurrency in tree.cryptocurrencies() {
+            write_string(&mut writer, &cryptocurrency.name)?;
+            write_string(&mut writer, &cryptocurrency.chain)?;
+            let kind: u8 = match cryptocurrency.kind {
+                BalanceKind::Liability => 0,
+                BalanceKind::Debt => 1,
+            };
+            writer.write_all(&[kind])?;
+            writer.write_all(&cryptocurrency.decimals.to_le_bytes())?;
+        }
+
+        // Nodes, from the leaves to the root
+        for level in 0..=depth {
+            for index in 0..1 << (depth - level) {
+                let node = tree.get_node(level, index)?;
+                writer.write_all(&node.hash.to_repr())?;
+                for balance in node.balances.iter() {
+                    writer.write_all(&balance.to_repr())?;
+                }
+            }
+        }
+
+        // Entries index
+        let mut entry_offset = 0u64;
+        for index in 0..tree.entries_count() {
+            writer.write_all(&entry_offset.to_le_bytes())?;
+            let entry = tree.get_entry(index)?;
+            let salt_size = if entry.salt().is_some() {
+                1 + FIELD_ELEMENT_SIZE
+            } else {
+                1
+            };
+            entry_offset +=
+                (4 + entry.username().len() + N_CURRENCIES * FIELD_ELEMENT_SIZE + salt_size) as u64;
+        }
+
+        // Entries records
+        for index in 0..tree.entries_count() {
+            let entry = tree.get_entry(index)?;
+            write_string(&mut writer, entry.username())?;
+            for balance in entry.balances().iter() {
+                let mut bytes = balance.to_bytes_le();
+                if bytes.len() > FIELD_ELEMENT_SIZE {
+                    return Err(Box::from("Balance does not fit in 32 bytes"));
+                }
+                bytes.resize(FIELD_ELEMENT_SIZE, 0);
+                writer.write_all(&bytes)?;
+            }
+            match entry.salt() {
+                Some(salt) => {
+                    writer.write_all(&[1])?;
+                    writer.write_all(&salt.to_repr())?;
+                }
+                None => writer.write_all(&[0])?,
+            }
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Opens a tree previously written to the file stored at `path`. Only the header and the root are read.
+    ///
+    /// The root read from the file is checked against the hash preimage made of its children.
+    /// If `expected_root` is provided, for example the root published in a commitment, the root read from the file must match it as well.
+    ///
+    /// # Integrity
+    ///
+    /// The nodes below the children of the root and the entries are not checked when the file is opened, nor when they are read. A file whose other nodes or entries have been corrupted or tampered with
+    /// is opened successfully, and the proofs generated out of it are invalid. Callers must run [`MerkleSumTreeStore::verify_integrity`] before generating proofs out of a file they don't trust.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        expected_root: Option<&Node<N_CURRENCIES>>,
+    ) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let file = File::open(path)?;
+        let file_size = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Box::from("Not a Merkle Sum Tree file"));
+        }
+        if read_u32(&mut reader)? != VERSION {
+            return Err(Box::from("Unsupported Merkle Sum Tree file version"));
+        }
+        if read_u32(&mut reader)? as usize != N_CURRENCIES {
+            return Err(Box::from("Number of currencies does not match"));
+        }
+
+        // The header is untrusted, so the depth is bounded before being used in any shift, and the sizes derived from it are checked against the size of the file
+        let depth = read_u32(&mut reader)? as usize;
+        let entries_count = read_u64(&mut reader)?;
+        if depth == 0 || depth > MAX_DEPTH || entries_count > 1 << depth {
+            return Err(Box::from("Invalid tree dimensions"));
+        }
+        let entries_count = entries_count as usize;
+
+        if read_u32(&mut reader)? as usize != N_CURRENCIES {
+            return Err(Box::from("Number of cryptocurrencies does not match"));
+        }
+        let mut cryptocurrencies = Vec::with_capacity(N_CURRENCIES);
+        for _ in 0..N_CURRENCIES {
+            let name = read_string(&mut reader)?;
+            let chain = read_string(&mut reader)?;
+            let mut kind = [0u8; 1];
+            reader.read_exact(&mut kind)?;
+            let kind = match kind[0] {
+                0 => BalanceKind::Liability,
+                1 => BalanceKind::Debt,
+                _ => return Err(Box::from("Invalid balance kind")),
+            };
+            let decimals = read_u32(&mut reader)?;
+            if decimals > MAX_DECIMALS {
+                return Err(Box::from("Invalid number of decimals"));
+            }
+            cryptocurrencies.push(Cryptocurrency {
+                name,
+                chain,
+                kind,
+                decimals,
+            });
+        }
+
+        let nodes_offset = reader.stream_position()?;
+        let (entries_index_offset, entries_offset) =
+            match Self::sections_offsets(nodes_offset, depth, entries_count) {
+                Some(offsets) if offsets.1 <= file_size => offsets,
+                _ => {
+                    return Err(Box::from(
+                        "The file is too short for the tree dimensions of its header",
+                    ))
+                }
+            };
+
+        let mut store = MerkleSumTreeStore {
+            root: Node::init_empty(),
+            depth,
+            entries_count,
+            cryptocurrencies,
+            nodes_offset,
+            entries_index_offset,
+            entries_offset,
+            file: Mutex::new(reader.into_inner()),
+            hasher: PhantomData,
+        };
+
+        let root = store.get_node(depth, 0)?;
+        let left_child = store.get_node(depth - 1, 0)?;
+        let right_child = store.get_node(depth - 1, 1)?;
+        let computed_root = H::middle(&left_child, &right_child);
+        if computed_root.hash != root.hash || computed_root.balances != root.balances {
+            return Err(Box::from("Root does not match its children"));
+        }
+
+        if let Some(expected_root) = expected_root {
+            if expected_root.hash != root.hash || expected_root.balances != root.balances {
+                return Err(Box::from("Root does not match the expected root"));
+            }
+        }
+
+        store.root = root;
+        Ok(store)
+    }
+
+    /// Recomputes every leaf from its entry and every middle node from its children, level by level, and checks them against the nodes stored in the file.
+    /// This reads the whole file, so it is meant to be run once in a while rather than on every open.
+    pub fn verify_integrity(&self) -> Result<(), Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        for index in 0..1 << self.depth {
+            let expected_leaf = if index < self.entries_count {
+                H::compute_leaf(&self.get_entry(index)?)
+            } else {
+                H::padding_leaf()
+            };
+            if self.get_node(0, index)?.hash != expected_leaf.hash {
+                return Err(format!("Leaf {} does not match its entry", index).into());
+            }
+        }
+
+        for level in 1..=self.depth {
+            for index in 0..1 << (self.depth - level) {
+                let node = self.get_node(level, index)?;
+                let computed_node = H::middle(
+                    &self.get_node(level - 1, 2 * index)?,
+                    &self.get_node(level - 1, 2 * index + 1)?,
+                );
+                if node.hash != computed_node.hash || node.balances != computed_node.balances {
+                    return Err(format!(
+                        "Node {} at level {} does not match its children",
+                        index, level
+                    )
+                    .into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the offsets of the entries index and of the entries records, given the offset of the nodes, or `None` if they overflow 64 bits
+    fn sections_offsets(
+        nodes_offset: u64,
+        depth: usize,
+        entries_count: usize,
+    ) -> Option<(u64, u64)> {
+        let nodes_count: u64 = 1u64.checked_shl(depth as u32 + 1)? - 1;
+        let entries_index_offset =
+            nodes_offset.checked_add(nodes_count.checked_mul(Self::node_size() as u64)?)?;
+        let entries_offset =
+            entries_index_offset.checked_add((entries_count as u64).checked_mul(8)?)?;
+        Some((entries_index_offset, entries_offset))
+    }
+
+    /// Returns the size in bytes of a node record
+    fn node_size() -> usize {
+        (N_CURRENCIES + 1) * FIELD_ELEMENT_SIZE
+    }
+
+    /// Returns the position of the node at the given level and index among all the nodes, counted from the first leaf
+    fn node_position(depth: usize, level: usize, index: usize) -> usize {
+        // Level `l` has 2^(depth - l) nodes, so the levels below `level` have 2^(depth + 1) - 2^(depth - level + 1) nodes in total
+        (1 << (depth + 1)) - (1 << (depth - level + 1)) + index
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, Box<dyn std::error::Error>> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, Box<dyn std::error::Error>> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String, Box<dyn std::error::Error>> {
+    let len = read_u32(reader)? as u64;
+    // The length is untrusted, so the buffer only grows as the bytes are actually read
+    let mut bytes = Vec::new();
+    reader.by_ref().take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(Box::from("Unexpected end of file"));
+    }
+    Ok(String::from_utf8(bytes)?)
+}
+
+fn write_string<W: Write>(writer: &mut W, value: &str) -> Result<(), Box<dyn std::error::Error>> {
+    writer.write_all(&(value.len() as u32).to_le_bytes())?;
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+fn read_fp<R: Read>(reader: &mut R) -> Result<Fp, Box<dyn std::error::Error>> {
+    let mut bytes = [0u8; FIELD_ELEMENT_SIZE];
+    reader.read_exact(&mut bytes)?;
+    Option::from(Fp::from_repr(bytes)).ok_or_else(|| Box::from("Invalid field element"))
+}
+
+fn read_node<R: Read, const N_CURRENCIES: usize>(
+    reader: &mut R,
+) -> Result<Node<N_CURRENCIES>, Box<dyn std::error::Error>> {
+    let hash = read_fp(reader)?;
+    let mut balances = [Fp::zero(); N_CURRENCIES];
+    for balance in balances.iter_mut() {
+        *balance = read_fp(reader)?;
+    }
+    Ok(Node { hash, balances })
+}
+
+fn read_entry<R: Read, const N_CURRENCIES: usize>(
+    reader: &mut R,
+) -> Result<Entry<N_CURRENCIES>, Box<dyn std::error::Error>> {
+    let username = read_string(reader)?;
+    let mut balances: [BigUint; N_CURRENCIES] = std::array::from_fn(|_| BigUint::from(0u32));
+    for balance in balances.iter_mut() {
+        let mut bytes = [0u8; FIELD_ELEMENT_SIZE];
+        reader.read_exact(&mut bytes)?;
+        *balance = BigUint::from_bytes_le(&bytes);
+    }
+
+    let mut salt_flag = [0u8; 1];
+    reader.read_exact(&mut salt_flag)?;
+    match salt_flag[0] {
+        0 => Ok(Entry::new(username, balances)?),
+        1 => Ok(Entry::new_salted(username, balances, read_fp(reader)?)?),
+        _ => Err(Box::from("Invalid salt flag")),
+    }
+}
@@ -1,9 +1,28 @@
 #[cfg(test)]
 mod test {
 
-    use crate::merkle_sum_tree::utils::big_uint_to_fp;
-    use crate::merkle_sum_tree::{Entry, MerkleSumTree, Node, Tree};
-    use num_bigint::{BigUint, ToBigUint};
+    use crate::merkle_sum_tree::utils::{
+        big_intify_username, big_uint_to_fp, build_root_from_csv_in_chunks,
+        build_root_from_csv_in_chunks_with_manifest, build_root_from_source_in_chunks,
+        check_username, format_decimal_balance, fp_to_big_uint, parse_csv_to_entries,
+        parse_csv_to_entries_with_manifest, parse_decimal_balance, parse_entries_from_source,
+        read_entries, validate_csv, CsvIssue, CsvSource, CurrencyManifest, JsonLinesSource,
+        MAX_DECIMALS,
+    };
+    use crate::merkle_sum_tree::verifier::{
+        root_balances_from_big_uints, verify_merkle_multiproof, verify_merkle_proof,
+        verify_merkle_proof_bytes, verify_merkle_proof_json, verify_merkle_proof_with_hasher,
+        verify_non_inclusion_proof, verify_quaternary_merkle_proof, VerificationError,
+    };
+    use crate::merkle_sum_tree::{
+        diff_trees, fp_to_fr, fp_to_hex, fr_to_fp, hex_to_fp, AggregationMerkleSumTree,
+        BalanceKind, CircomPoseidonHasher, Cryptocurrency, DynamicEntry, DynamicMerkleSumTree,
+        DynamicNode, Entry, MerkleNonInclusionProof, MerkleProof, MerkleSumTree,
+        MerkleSumTreeStore, Node, NodeHasher, NovaPoseidonHasher, PoseidonHasher,
+        QuaternaryMerkleSumTree, Tree,
+    };
+    use halo2_proofs::halo2curves::bn256::Fr as Fp;
+    use num_bigint::{BigInt, BigUint, ToBigUint};
     use rand::Rng as _;
 
     const N_CURRENCIES: usize = 2;
@@ -66,44 +85,1831 @@ mod test {
         assert!(!merkle_tree.verify_proof(&proof_invalid_2));
     }
 
+    #[test]
+    fn test_mst_padding() {
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+
+        // build a tree out of 11 entries, which is not a power of two
+        let entries = entries[..11].to_vec();
+        let merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries.clone(),
+            cryptocurrencies.clone(),
+            false,
+        )
+        .unwrap();
+
+        // the leaves should be padded up to 16
+        assert_eq!(*merkle_tree.depth(), 4);
+        assert_eq!(merkle_tree.leaves().len(), 16);
+        assert_eq!(merkle_tree.entries().len(), 11);
+        for leaf in &merkle_tree.leaves()[11..] {
+            assert_eq!(leaf.hash, Node::<N_CURRENCIES>::padding_leaf().hash);
+        }
+
+        // padding should not change the root balances
+        let mut expected_balances = [0.into(); N_CURRENCIES];
+        for entry in &entries {
+            for (i, balance) in entry.balances().iter().enumerate() {
+                expected_balances[i] += big_uint_to_fp(balance);
+            }
+        }
+        assert_eq!(merkle_tree.root().balances, expected_balances);
+
+        // the root should match the one of a tree built with explicit empty entries in the padding positions
+        let mut padded_entries = entries.clone();
+        padded_entries.resize(16, Entry::init_empty());
+        let explicitly_padded_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            padded_entries,
+            cryptocurrencies.clone(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(merkle_tree.root().hash, explicitly_padded_tree.root().hash);
+
+        // should create valid proof for each entry, including the one whose sibling is a padding leaf
+        for i in 0..11 {
+            let proof = merkle_tree.generate_proof(i).unwrap();
+            assert_eq!(proof.path_indices.len(), 4);
+            assert!(merkle_tree.verify_proof(&proof));
+        }
+
+        // shouldn't create a proof for a padding leaf
+        assert!(merkle_tree.generate_proof(11).is_err());
+
+        // a single entry should be paired with a padding leaf
+        let single_entry_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries[..1].to_vec(),
+            cryptocurrencies.clone(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(*single_entry_tree.depth(), 1);
+        let proof = single_entry_tree.generate_proof(0).unwrap();
+        assert!(single_entry_tree.verify_proof(&proof));
+
+        // shouldn't build a tree without entries
+        let empty_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(vec![], cryptocurrencies, false);
+        assert!(empty_tree.is_err());
+    }
+
     #[test]
     fn test_update_mst_leaf() {
         let merkle_tree_1 =
             MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
 
-        let root_hash_1 = merkle_tree_1.root().hash;
+        let root_hash_1 = merkle_tree_1.root().hash;
+
+        //Create the second tree with the 7th entry different from the the first tree
+        let mut merkle_tree_2 =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16_modified.csv").unwrap();
+
+        let root_hash_2 = merkle_tree_2.root().hash;
+        assert!(root_hash_1 != root_hash_2);
+
+        //Update the 7th leaf of the second tree so all the entries now match the first tree
+        let new_root = merkle_tree_2
+            .update_leaf(
+                "RkLzkDun",
+                &[2087.to_biguint().unwrap(), 79731.to_biguint().unwrap()],
+            )
+            .unwrap();
+        //The roots should match
+        assert!(root_hash_1 == new_root.hash);
+    }
+
+    #[test]
+    fn test_update_invalid_mst_leaf() {
+        let mut merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new_sorted("../csv/entry_16.csv").unwrap();
+
+        let new_root = merkle_tree.update_leaf(
+            "non_existing_user", //This username is not present in the tree
+            &[11888.to_biguint().unwrap(), 41163.to_biguint().unwrap()],
+        );
+
+        if let Err(e) = new_root {
+            assert_eq!(e.to_string(), "Username not found");
+        }
+    }
+
+    #[test]
+    fn test_update_mst_leaves() {
+        let updates = [
+            (
+                "dxGaEAii",
+                [1.to_biguint().unwrap(), 2.to_biguint().unwrap()],
+            ),
+            (
+                "RkLzkDun",
+                [2087.to_biguint().unwrap(), 79731.to_biguint().unwrap()],
+            ),
+            (
+                "MBlfbBGI",
+                [3.to_biguint().unwrap(), 4.to_biguint().unwrap()],
+            ),
+        ];
+
+        // Apply the updates one by one
+        let mut merkle_tree_1 =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16_modified.csv").unwrap();
+        for (username, balances) in updates.iter() {
+            merkle_tree_1.update_leaf(username, balances).unwrap();
+        }
+
+        // Apply the updates in a single batch
+        let mut merkle_tree_2 =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16_modified.csv").unwrap();
+        let (new_root, changed_positions) = merkle_tree_2.update_leaves(&updates).unwrap();
+
+        // The roots should match
+        assert_eq!(new_root.hash, merkle_tree_1.root().hash);
+        assert_eq!(new_root.balances, merkle_tree_1.root().balances);
+        assert_eq!(merkle_tree_2.root().hash, merkle_tree_1.root().hash);
+
+        // Every node should match
+        for (level, nodes) in merkle_tree_2.nodes().iter().enumerate() {
+            for (index, node) in nodes.iter().enumerate() {
+                assert_eq!(node.hash, merkle_tree_1.nodes()[level][index].hash);
+            }
+        }
+
+        // Each changed position should be reported once, up to the root
+        let mut deduped_positions = changed_positions.clone();
+        deduped_positions.dedup();
+        assert_eq!(deduped_positions, changed_positions);
+        let changed_leaves = changed_positions.iter().filter(|(level, _)| *level == 0);
+        assert_eq!(changed_leaves.count(), 3);
+        assert_eq!(changed_positions.last(), Some(&(4, 0)));
+
+        // The updated entries should be provable
+        for (username, _) in updates.iter() {
+            let index = merkle_tree_2.index_of_username(username).unwrap();
+            let proof = merkle_tree_2.generate_proof(index).unwrap();
+            assert!(merkle_tree_2.verify_proof(&proof));
+        }
+
+        // A batch with an unknown username should fail and leave the tree untouched
+        let invalid_updates = [
+            (
+                "dxGaEAii",
+                [5.to_biguint().unwrap(), 6.to_biguint().unwrap()],
+            ),
+            (
+                "non_existing_user",
+                [5.to_biguint().unwrap(), 6.to_biguint().unwrap()],
+            ),
+        ];
+        assert!(merkle_tree_2.update_leaves(&invalid_updates).is_err());
+        assert_eq!(merkle_tree_2.root().hash, merkle_tree_1.root().hash);
+
+        // A batch with a duplicate username should fail
+        let duplicate_updates = [updates[0].clone(), updates[0].clone()];
+        assert!(merkle_tree_2.update_leaves(&duplicate_updates).is_err());
+
+        // A batch whose balances overflow the range of the root balances should fail and leave the tree untouched
+        let overflowing_updates = [(
+            "dxGaEAii",
+            [
+                BigUint::from(2_usize).pow(8 * N_BYTES as u32),
+                6.to_biguint().unwrap(),
+            ],
+        )];
+        assert!(merkle_tree_2.update_leaves(&overflowing_updates).is_err());
+        assert_eq!(merkle_tree_2.root().hash, merkle_tree_1.root().hash);
+
+        // Batch updates should work on sorted trees as well
+        let mut sorted_merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new_sorted("../csv/entry_16_modified.csv")
+                .unwrap();
+        let (sorted_root, _) = sorted_merkle_tree.update_leaves(&updates).unwrap();
+        assert_eq!(sorted_root.balances, merkle_tree_1.root().balances);
+    }
+
+    #[test]
+    fn test_insert_mst_entry() {
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+
+        let new_entry = Entry::new(
+            "NewUser1".to_string(),
+            [1000.to_biguint().unwrap(), 2000.to_biguint().unwrap()],
+        )
+        .unwrap();
+
+        // Inserting in a full tree should increase its depth
+        let mut merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries.clone(),
+            cryptocurrencies.clone(),
+            false,
+        )
+        .unwrap();
+        let new_root = merkle_tree.insert_entry(new_entry.clone()).unwrap();
+        assert_eq!(*merkle_tree.depth(), 5);
+
+        // The root should match the one of a tree built from scratch with the new entry appended
+        let mut expected_entries = entries.clone();
+        expected_entries.push(new_entry.clone());
+        let expected_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            expected_entries,
+            cryptocurrencies.clone(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(new_root.hash, expected_tree.root().hash);
+        assert_eq!(new_root.balances, expected_tree.root().balances);
+        assert_eq!(merkle_tree.root().hash, expected_tree.root().hash);
+
+        // Every entry, including the new one, should be provable
+        for i in 0..17 {
+            let proof = merkle_tree.generate_proof(i).unwrap();
+            assert!(merkle_tree.verify_proof(&proof));
+        }
+
+        // Inserting an existing username should fail
+        assert!(merkle_tree.insert_entry(new_entry.clone()).is_err());
+
+        // Inserting in a sorted tree should keep the leaves sorted
+        let mut sorted_merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new_sorted("../csv/entry_16.csv").unwrap();
+        sorted_merkle_tree.insert_entry(new_entry.clone()).unwrap();
+
+        let mut expected_entries = entries;
+        expected_entries.push(new_entry);
+        expected_entries.sort_by(|a, b| a.username().cmp(b.username()));
+        let expected_sorted_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            expected_entries,
+            cryptocurrencies,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            sorted_merkle_tree.root().hash,
+            expected_sorted_tree.root().hash
+        );
+
+        // The binary search should still find every username
+        for entry in expected_sorted_tree.entries() {
+            let index = sorted_merkle_tree.index_of_username(entry.username());
+            let expected_index = expected_sorted_tree.index_of_username(entry.username());
+            assert_eq!(index.unwrap(), expected_index.unwrap());
+        }
+    }
+
+    #[test]
+    fn test_remove_mst_entry() {
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+
+        // Removing from an unsorted tree should move the last entry into the freed leaf
+        let mut merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+        let new_root = merkle_tree.remove_entry("RkLzkDun").unwrap();
+
+        let mut expected_entries = entries.clone();
+        let index = expected_entries
+            .iter()
+            .position(|entry| entry.username() == "RkLzkDun")
+            .unwrap();
+        expected_entries.swap_remove(index);
+        let expected_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            expected_entries.clone(),
+            cryptocurrencies.clone(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(new_root.hash, expected_tree.root().hash);
+        assert_eq!(new_root.balances, expected_tree.root().balances);
+        assert_eq!(merkle_tree.entries_count(), 15);
+        assert!(merkle_tree.index_of_username("RkLzkDun").is_err());
+
+        // No empty entry should be left behind
+        assert!(merkle_tree.index_of_username("").is_err());
+        assert!(merkle_tree.get_entry(15).is_err());
+        assert!(merkle_tree.generate_proof(15).is_err());
+
+        // The last entry should have taken the freed position
+        let proof = merkle_tree.generate_proof(index).unwrap();
+        assert_eq!(proof.entry.username(), entries[15].username());
+        assert!(merkle_tree.verify_proof(&proof));
+
+        // The freed leaf should be reused by the next insertion
+        let new_entry = Entry::new(
+            "newcomer".to_string(),
+            [BigUint::from(5_u32), BigUint::from(7_u32)],
+        )
+        .unwrap();
+        let new_root = merkle_tree.insert_entry(new_entry.clone()).unwrap();
+        expected_entries.push(new_entry);
+        let expected_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            expected_entries,
+            cryptocurrencies.clone(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(new_root.hash, expected_tree.root().hash);
+        assert_eq!(*merkle_tree.depth(), 4);
+
+        // Removing from a sorted tree should shift the following leaves
+        let mut sorted_merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new_sorted("../csv/entry_16.csv").unwrap();
+        sorted_merkle_tree.remove_entry("RkLzkDun").unwrap();
+
+        let mut expected_entries: Vec<Entry<N_CURRENCIES>> = entries
+            .into_iter()
+            .filter(|entry| entry.username() != "RkLzkDun")
+            .collect();
+        expected_entries.sort_by(|a, b| a.username().cmp(b.username()));
+        let expected_sorted_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            expected_entries,
+            cryptocurrencies,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            sorted_merkle_tree.root().hash,
+            expected_sorted_tree.root().hash
+        );
+        assert_eq!(*sorted_merkle_tree.depth(), 4);
+
+        for i in 0..15 {
+            let proof = sorted_merkle_tree.generate_proof(i).unwrap();
+            assert!(sorted_merkle_tree.verify_proof(&proof));
+        }
+
+        // Removing a non existing username should fail
+        assert!(sorted_merkle_tree.remove_entry("RkLzkDun").is_err());
+    }
+
+    #[test]
+    fn test_non_inclusion_proof() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new_sorted("../csv/entry_16.csv").unwrap();
+        let root = merkle_tree.root();
+
+        // The username falls between the 5th and the 6th leaves
+        let proof = merkle_tree
+            .generate_non_inclusion_proof("Pablo123")
+            .unwrap();
+        assert_eq!(proof.lower.as_ref().unwrap().entry.username(), "NjCSRAfD");
+        assert_eq!(proof.upper.as_ref().unwrap().entry.username(), "RZNneNuP");
+        assert!(verify_non_inclusion_proof(&proof, root.hash, &root.balances).is_ok());
+
+        // The username precedes the first leaf
+        let proof = merkle_tree
+            .generate_non_inclusion_proof("AAAAAAAA")
+            .unwrap();
+        assert!(proof.lower.is_none());
+        assert!(verify_non_inclusion_proof(&proof, root.hash, &root.balances).is_ok());
+
+        // The username follows the last leaf of a full tree
+        let proof = merkle_tree
+            .generate_non_inclusion_proof("zzzzzzzz")
+            .unwrap();
+        assert!(proof.upper.is_none());
+        assert!(verify_non_inclusion_proof(&proof, root.hash, &root.balances).is_ok());
+
+        // No proof can be generated for a username in the tree or for an unsorted tree
+        assert!(merkle_tree
+            .generate_non_inclusion_proof("RkLzkDun")
+            .is_err());
+        let unsorted_merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+        assert!(unsorted_merkle_tree
+            .generate_non_inclusion_proof("Pablo123")
+            .is_err());
+
+        // The proof of a username between two leaves can't be reused for a username outside of them
+        let mut forged_proof = merkle_tree
+            .generate_non_inclusion_proof("Pablo123")
+            .unwrap();
+        forged_proof.username = "RkLzkDun".to_string();
+        assert!(matches!(
+            verify_non_inclusion_proof(&forged_proof, root.hash, &root.balances),
+            Err(VerificationError::NotExcluded(_))
+        ));
+
+        // The leaves must be adjacent
+        let mut forged_proof = merkle_tree
+            .generate_non_inclusion_proof("Pablo123")
+            .unwrap();
+        forged_proof.upper = Some(merkle_tree.generate_proof(6).unwrap());
+        assert!(matches!(
+            verify_non_inclusion_proof(&forged_proof, root.hash, &root.balances),
+            Err(VerificationError::NotExcluded(_))
+        ));
+
+        // The leaves must be included in the tree
+        let other_root = unsorted_merkle_tree.root();
+        assert!(verify_non_inclusion_proof(&proof, other_root.hash, &other_root.balances).is_err());
+
+        // The upper leaf of a username following the last entry of a padded tree is a padding leaf
+        let (cryptocurrencies, mut entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+        entries.truncate(11);
+        entries.sort_by(|a, b| a.username_as_big_uint().cmp(b.username_as_big_uint()));
+        let padded_merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(entries, cryptocurrencies, true)
+                .unwrap();
+        let padded_root = padded_merkle_tree.root();
+
+        let proof = padded_merkle_tree
+            .generate_non_inclusion_proof("zzzzzzzz")
+            .unwrap();
+        assert_eq!(proof.upper.as_ref().unwrap().entry.username(), "");
+        assert!(
+            verify_non_inclusion_proof(&proof, padded_root.hash, &padded_root.balances).is_ok()
+        );
+
+        // A padding leaf in the middle of the tree can't be used as the upper leaf
+        let (cryptocurrencies, mut entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+        entries.truncate(11);
+        entries.sort_by(|a, b| a.username_as_big_uint().cmp(b.username_as_big_uint()));
+        entries.insert(5, Entry::init_empty());
+        let forged_merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(entries, cryptocurrencies, false)
+                .unwrap();
+        let forged_root = forged_merkle_tree.root();
+
+        let forged_proof = MerkleNonInclusionProof {
+            username: "zzzzzzzz".to_string(),
+            lower: Some(forged_merkle_tree.generate_proof(4).unwrap()),
+            upper: Some(forged_merkle_tree.generate_proof(5).unwrap()),
+        };
+        assert!(matches!(
+            verify_non_inclusion_proof(&forged_proof, forged_root.hash, &forged_root.balances),
+            Err(VerificationError::NotExcluded(_))
+        ));
+
+        // Unsorted entries can't be used to build a sorted tree
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+        assert!(MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries,
+            cryptocurrencies,
+            true
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_tree_diff() {
+        let previous_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        // Comparing a tree against itself should report no change
+        let diff = diff_trees(&previous_tree, &previous_tree).unwrap();
+        assert!(diff.is_empty());
+
+        // The root balances published for the previous round
+        let published_root_balances: [BigUint; N_CURRENCIES] =
+            std::array::from_fn(|i| fp_to_big_uint(previous_tree.root().balances[i]));
+        assert!(diff.reconcile(&published_root_balances).is_ok());
+
+        // dxGaEAii's balances change, RkLzkDun leaves and a new user joins
+        let mut current_tree = previous_tree.clone();
+        current_tree
+            .update_leaf(
+                "dxGaEAii",
+                &[BigUint::from(10000_u32), BigUint::from(50000_u32)],
+            )
+            .unwrap();
+        let removed_entry = previous_tree
+            .get_entry(previous_tree.index_of_username("RkLzkDun").unwrap())
+            .unwrap();
+        current_tree.remove_entry("RkLzkDun").unwrap();
+        current_tree
+            .insert_entry(
+                Entry::new(
+                    "newcomer".to_string(),
+                    [BigUint::from(5_u32), BigUint::from(7_u32)],
+                )
+                .unwrap(),
+            )
+            .unwrap();
+
+        let diff = diff_trees(&previous_tree, &current_tree).unwrap();
+
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].username(), "newcomer");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].username(), "RkLzkDun");
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].username(), "dxGaEAii");
+        assert_eq!(
+            diff.changed[0].deltas(),
+            [BigInt::from(10000 - 11888), BigInt::from(50000 - 41163)]
+        );
+
+        let expected_root_deltas = [
+            BigInt::from(10000 - 11888 + 5) - BigInt::from(removed_entry.balances()[0].clone()),
+            BigInt::from(50000 - 41163 + 7) - BigInt::from(removed_entry.balances()[1].clone()),
+        ];
+        assert_eq!(diff.root_deltas, expected_root_deltas);
+        assert_eq!(diff.entry_deltas(), expected_root_deltas);
+        assert!(diff.reconcile(&published_root_balances).is_ok());
+
+        // A previous tree that isn't the published one should be caught
+        let mut tampered_root_balances = published_root_balances.clone();
+        tampered_root_balances[0] += 1_u32;
+        assert!(diff.reconcile(&tampered_root_balances).is_err());
+
+        // A current root whose balances don't match the published ones plus the entry deltas should be caught
+        let mut inconsistent_diff = diff.clone();
+        inconsistent_diff.current_root_balances[1] += 1_u32;
+        assert!(inconsistent_diff
+            .reconcile(&published_root_balances)
+            .is_err());
+
+        // The trees must have the same cryptocurrencies
+        let mut cryptocurrencies = previous_tree.cryptocurrencies().to_vec();
+        cryptocurrencies[1].kind = BalanceKind::Debt;
+        let other_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            vec![previous_tree.get_entry(0).unwrap()],
+            cryptocurrencies,
+            false,
+        )
+        .unwrap();
+        assert!(diff_trees(&previous_tree, &other_tree).is_err());
+    }
+
+    #[test]
+    fn test_dynamic_mst() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        // The number of currencies is read from the CSV header
+        let dynamic_tree = DynamicMerkleSumTree::<N_BYTES>::new("../csv/entry_16.csv").unwrap();
+        assert_eq!(dynamic_tree.n_currencies(), N_CURRENCIES);
+        assert_eq!(
+            dynamic_tree.root(),
+            DynamicNode::from(merkle_tree.root().clone())
+        );
+        assert_eq!(dynamic_tree.depth(), *merkle_tree.depth());
+        assert_eq!(dynamic_tree.entries_count(), 16);
+        assert_eq!(
+            dynamic_tree.get_node(1, 3).unwrap(),
+            DynamicNode::from(merkle_tree.get_node(1, 3).unwrap())
+        );
+        assert_eq!(
+            dynamic_tree.get_entry(0).unwrap(),
+            DynamicEntry::from(merkle_tree.get_entry(0).unwrap())
+        );
+        assert!(dynamic_tree.as_tree::<N_CURRENCIES>().is_some());
+        assert!(dynamic_tree.as_tree::<3>().is_none());
+
+        let proof_json = dynamic_tree.generate_proof_json(3).unwrap();
+        assert!(dynamic_tree.verify_proof_json(&proof_json).is_ok());
+        assert!(merkle_tree
+            .verify_proof(&MerkleProof::<N_CURRENCIES, N_BYTES>::from_json(&proof_json).unwrap()));
+
+        // A tree with three currencies hashes as the const-generic tree with N_CURRENCIES = 3
+        let cryptocurrencies: Vec<Cryptocurrency> = ["ETH", "USDT", "BTC"]
+            .iter()
+            .map(|name| Cryptocurrency {
+                name: name.to_string(),
+                chain: "ETH".to_string(),
+                kind: BalanceKind::Liability,
+                decimals: 0,
+            })
+            .collect();
+        let entries: Vec<DynamicEntry> = (0..5_u32)
+            .map(|i| DynamicEntry {
+                username: format!("user{}", i),
+                balances: vec![BigUint::from(i), BigUint::from(2 * i), BigUint::from(3 * i)],
+                salt: None,
+            })
+            .collect();
+        let dynamic_tree = DynamicMerkleSumTree::<N_BYTES>::from_entries(
+            entries.clone(),
+            cryptocurrencies.clone(),
+            false,
+        )
+        .unwrap();
+        let merkle_tree = MerkleSumTree::<3, N_BYTES>::from_entries(
+            entries
+                .iter()
+                .map(|entry| entry.to_entry::<3>().unwrap())
+                .collect(),
+            cryptocurrencies.clone(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(dynamic_tree.n_currencies(), 3);
+        assert_eq!(
+            dynamic_tree.root(),
+            DynamicNode::from(merkle_tree.root().clone())
+        );
+
+        // Entries must hold one balance per cryptocurrency
+        let mut invalid_entries = entries.clone();
+        invalid_entries[2].balances.pop();
+        assert!(DynamicMerkleSumTree::<N_BYTES>::from_entries(
+            invalid_entries,
+            cryptocurrencies.clone(),
+            false
+        )
+        .is_err());
+
+        // The number of currencies is bounded by the precompiled shapes
+        let too_many_cryptocurrencies = vec![cryptocurrencies[0].clone(); 9];
+        assert!(DynamicMerkleSumTree::<N_BYTES>::from_entries(
+            vec![],
+            too_many_cryptocurrencies,
+            false
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_mst_store() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        let path = std::env::temp_dir().join("summa_test_mst_store.bin");
+        MerkleSumTreeStore::<N_CURRENCIES, N_BYTES>::write(&merkle_tree, &path).unwrap();
+
+        // Reopen the tree, checking it against the root of the in-memory tree
+        let store =
+            MerkleSumTreeStore::<N_CURRENCIES, N_BYTES>::open(&path, Some(merkle_tree.root()))
+                .unwrap();
+
+        assert_eq!(store.root().hash, merkle_tree.root().hash);
+        assert_eq!(store.root().balances, merkle_tree.root().balances);
+        assert_eq!(*store.depth(), *merkle_tree.depth());
+        assert_eq!(store.entries_count(), merkle_tree.entries_count());
+        assert_eq!(store.cryptocurrencies()[0].name, "ETH");
+        assert!(store.verify_integrity().is_ok());
+
+        // The accessors provided by the trait should read the whole tree out of the store
+        let leaves = store.leaves().unwrap();
+        assert_eq!(leaves.len(), merkle_tree.leaves().len());
+        assert_eq!(leaves[5].hash, merkle_tree.leaves()[5].hash);
+        let nodes = store.nodes().unwrap();
+        assert_eq!(nodes.len(), merkle_tree.nodes().len());
+        assert_eq!(nodes[*store.depth()][0].hash, merkle_tree.root().hash);
+        let entries = store.entries().unwrap();
+        assert_eq!(entries.len(), merkle_tree.entries().len());
+        assert_eq!(entries[3].username(), merkle_tree.entries()[3].username());
+
+        // The proofs served by the store should match the ones of the in-memory tree
+        for i in 0..16 {
+            let proof = store.generate_proof(i).unwrap();
+            let expected_proof = merkle_tree.generate_proof(i).unwrap();
+            assert_eq!(proof.entry.username(), expected_proof.entry.username());
+            assert_eq!(proof.path_indices, expected_proof.path_indices);
+            assert_eq!(
+                proof.sibling_middle_node_hash_preimages,
+                expected_proof.sibling_middle_node_hash_preimages
+            );
+            assert!(store.verify_proof(&proof));
+        }
+
+        // Opening the store against a different root should fail
+        let mut wrong_root = merkle_tree.root().clone();
+        wrong_root.hash = 0.into();
+        let result = MerkleSumTreeStore::<N_CURRENCIES, N_BYTES>::open(&path, Some(&wrong_root));
+        assert!(result.is_err());
+
+        // Opening the store with a different number of currencies should fail
+        assert!(MerkleSumTreeStore::<1, N_BYTES>::open(&path, None).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_mst_store_corrupted_header() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        let path = std::env::temp_dir().join("summa_test_mst_store_corrupted_header.bin");
+        MerkleSumTreeStore::<N_CURRENCIES, N_BYTES>::write(&merkle_tree, &path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        // The depth is stored at offset 16, the number of entries at offset 20 and the number of cryptocurrencies at offset 28
+        let corruptions: Vec<(usize, Vec<u8>)> = vec![
+            // A depth that would overflow the shifts
+            (16, 63u32.to_le_bytes().to_vec()),
+            (16, u32::MAX.to_le_bytes().to_vec()),
+            // A valid depth whose nodes don't fit in the file
+            (16, 40u32.to_le_bytes().to_vec()),
+            (20, (1u64 << 40).to_le_bytes().to_vec()),
+            // A number of cryptocurrencies that would allocate a huge vector
+            (28, u32::MAX.to_le_bytes().to_vec()),
+            // A name length larger than the file
+            (32, u32::MAX.to_le_bytes().to_vec()),
+        ];
+        for (offset, value) in corruptions {
+            let mut corrupted_bytes = bytes.clone();
+            corrupted_bytes[offset..offset + value.len()].copy_from_slice(&value);
+            std::fs::write(&path, &corrupted_bytes).unwrap();
+            assert!(MerkleSumTreeStore::<N_CURRENCIES, N_BYTES>::open(&path, None).is_err());
+        }
+
+        // A truncated file, cut within the header or within the nodes
+        for length in [10, 30, bytes.len() / 2] {
+            std::fs::write(&path, &bytes[..length]).unwrap();
+            assert!(MerkleSumTreeStore::<N_CURRENCIES, N_BYTES>::open(&path, None).is_err());
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_aggregation_mst() {
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+
+        // Build 4 mini-trees of 4 entries each, as if they were built out of separate shards
+        let mini_trees: Vec<MerkleSumTree<N_CURRENCIES, N_BYTES>> = entries
+            .chunks(4)
+            .map(|shard| {
+                MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+                    shard.to_vec(),
+                    cryptocurrencies.clone(),
+                    false,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let aggregation_mst =
+            AggregationMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(mini_trees.clone()).unwrap();
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        // The aggregated tree should match the tree built out of all the entries
+        assert_eq!(aggregation_mst.root().hash, merkle_tree.root().hash);
+        assert_eq!(aggregation_mst.root().balances, merkle_tree.root().balances);
+        assert_eq!(*aggregation_mst.depth(), 4);
+        assert_eq!(aggregation_mst.mini_tree_depth(), 2);
+        assert_eq!(aggregation_mst.entries_count(), 16);
+        assert_eq!(
+            aggregation_mst.mini_tree(1).unwrap().root().hash,
+            mini_trees[1].root().hash
+        );
+
+        // The proofs should span the mini-tree and top-tree levels
+        for i in 0..16 {
+            let proof = aggregation_mst.generate_proof(i).unwrap();
+            let expected_proof = merkle_tree.generate_proof(i).unwrap();
+            assert_eq!(proof.entry.username(), expected_proof.entry.username());
+            assert_eq!(proof.path_indices, expected_proof.path_indices);
+            assert_eq!(
+                proof.sibling_leaf_node_hash_preimage,
+                expected_proof.sibling_leaf_node_hash_preimage
+            );
+            assert_eq!(
+                proof.sibling_middle_node_hash_preimages,
+                expected_proof.sibling_middle_node_hash_preimages
+            );
+            assert!(aggregation_mst.verify_proof(&proof));
+        }
+        assert!(aggregation_mst.generate_proof(16).is_err());
+
+        // The last mini-tree can be padded
+        let mut padded_mini_trees = mini_trees.clone();
+        padded_mini_trees[3] = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries[12..15].to_vec(),
+            cryptocurrencies.clone(),
+            false,
+        )
+        .unwrap();
+        let padded_aggregation_mst =
+            AggregationMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(padded_mini_trees.clone())
+                .unwrap();
+        let padded_merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries[..15].to_vec(),
+            cryptocurrencies.clone(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            padded_aggregation_mst.root().hash,
+            padded_merkle_tree.root().hash
+        );
+        assert_eq!(padded_aggregation_mst.entries_count(), 15);
+        let proof = padded_aggregation_mst.generate_proof(14).unwrap();
+        assert!(padded_aggregation_mst.verify_proof(&proof));
+
+        // Only the last mini-tree can be padded
+        padded_mini_trees.swap(2, 3);
+        assert!(AggregationMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(padded_mini_trees).is_err());
+
+        // The number of mini-trees must be a power of two
+        assert!(
+            AggregationMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(mini_trees[..3].to_vec())
+                .is_err()
+        );
+
+        // The mini-trees must have the same depth
+        let mut uneven_mini_trees = mini_trees[..2].to_vec();
+        uneven_mini_trees[1] = merkle_tree;
+        assert!(AggregationMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(uneven_mini_trees).is_err());
+    }
+
+    #[test]
+    fn test_build_root_from_csv_in_chunks() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        // The root should not depend on the chunk size
+        for chunk_size in [1, 2, 4, 16, 32] {
+            let (cryptocurrencies, root, entries_count) =
+                build_root_from_csv_in_chunks::<_, N_CURRENCIES, N_BYTES>(
+                    "../csv/entry_16.csv",
+                    chunk_size,
+                )
+                .unwrap();
+            assert_eq!(cryptocurrencies[0].name, "ETH");
+            assert_eq!(root.hash, merkle_tree.root().hash);
+            assert_eq!(root.balances, merkle_tree.root().balances);
+            assert_eq!(entries_count, 16);
+        }
+
+        // A number of entries that is not a power of two should be padded as in the in-memory tree
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<_, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+        let path = std::env::temp_dir().join("summa_test_build_root_from_csv_in_chunks.csv");
+        let mut csv = String::from("username,balance_ETH_ETH,balance_USDT_ETH\n");
+        for entry in entries.iter().take(11) {
+            csv.push_str(&format!(
+                "{},{},{}\n",
+                entry.username(),
+                entry.balances()[0],
+                entry.balances()[1]
+            ));
+        }
+        std::fs::write(&path, csv).unwrap();
+
+        let merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries[..11].to_vec(),
+            cryptocurrencies,
+            false,
+        )
+        .unwrap();
+
+        for chunk_size in [1, 2, 4, 8] {
+            let (_, root, entries_count) =
+                build_root_from_csv_in_chunks::<_, N_CURRENCIES, N_BYTES>(&path, chunk_size)
+                    .unwrap();
+            assert_eq!(root.hash, merkle_tree.root().hash);
+            assert_eq!(root.balances, merkle_tree.root().balances);
+            assert_eq!(entries_count, 11);
+        }
+
+        // The chunk size must be a power of two
+        for chunk_size in [0, 3] {
+            let result =
+                build_root_from_csv_in_chunks::<_, N_CURRENCIES, N_BYTES>(&path, chunk_size);
+            assert!(result.is_err());
+        }
+
+        // An oversized balance should be rejected as its record is read, rather than panic when its leaf is hashed
+        let oversized_balances = [
+            BigUint::from(2_usize).pow(8 * N_BYTES as u32),
+            BigUint::from(2_usize).pow(256),
+        ];
+        for oversized_balance in oversized_balances {
+            std::fs::write(
+                &path,
+                format!(
+                    "username,balance_ETH_ETH,balance_USDT_ETH\nalice,1,2\nbob,{},2\n",
+                    oversized_balance
+                ),
+            )
+            .unwrap();
+            let result = build_root_from_csv_in_chunks::<_, N_CURRENCIES, N_BYTES>(&path, 1);
+            assert!(result.is_err());
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_entry_sources() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        // The CSV source should build the same tree as the CSV parser
+        let csv_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_source(
+            CsvSource::open("../csv/entry_16.csv").unwrap(),
+            &CurrencyManifest::default(),
+        )
+        .unwrap();
+        assert_eq!(csv_tree.root().hash, merkle_tree.root().hash);
+
+        // The JSON Lines values can be either strings or integers, and the empty lines are skipped
+        let path = std::env::temp_dir().join("summa_test_entry_sources.jsonl");
+        let mut jsonl = String::new();
+        for entry in merkle_tree.entries() {
+            jsonl.push_str(&format!(
+                "{{\"username\": \"{}\", \"balance_ETH_ETH\": {}, \"balance_USDT_ETH\": \"{}\"}}\n\n",
+                entry.username(),
+                entry.balances()[0],
+                entry.balances()[1]
+            ));
+        }
+        std::fs::write(&path, &jsonl).unwrap();
+
+        let jsonl_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_source(
+            JsonLinesSource::open(&path).unwrap(),
+            &CurrencyManifest::default(),
+        )
+        .unwrap();
+        assert_eq!(jsonl_tree.root().hash, merkle_tree.root().hash);
+        assert_eq!(jsonl_tree.cryptocurrencies()[1].name, "USDT");
+
+        let sorted_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new_sorted("../csv/entry_16.csv").unwrap();
+        let jsonl_sorted_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_source_sorted(
+            JsonLinesSource::open(&path).unwrap(),
+            &CurrencyManifest::default(),
+        )
+        .unwrap();
+        assert_eq!(jsonl_sorted_tree.root().hash, sorted_tree.root().hash);
+
+        // The entries are read lazily, one record at a time
+        let (cryptocurrencies, mut entries) = read_entries::<_, N_CURRENCIES, N_BYTES>(
+            JsonLinesSource::open(&path).unwrap(),
+            &CurrencyManifest::default(),
+        )
+        .unwrap();
+        assert_eq!(cryptocurrencies.len(), N_CURRENCIES);
+        assert_eq!(entries.next().unwrap().unwrap().username(), "dxGaEAii");
+        assert_eq!(entries.count(), 15);
+
+        // The streaming builder accepts any source
+        let (_, root, entries_count) =
+            build_root_from_source_in_chunks::<_, N_CURRENCIES, N_BYTES>(
+                JsonLinesSource::open(&path).unwrap(),
+                4,
+                &CurrencyManifest::default(),
+            )
+            .unwrap();
+        assert_eq!(root.hash, merkle_tree.root().hash);
+        assert_eq!(entries_count, 16);
+
+        // The headers and the records are validated as in a CSV file
+        let invalid_sources = [
+            // The first column must be the username
+            "{\"balance_ETH_ETH\": 1, \"username\": \"alice\", \"balance_USDT_ETH\": 1}",
+            // Malformed header
+            "{\"username\": \"alice\", \"ETH\": 1, \"balance_USDT_ETH\": 1}",
+            // Wrong number of currencies
+            "{\"username\": \"alice\", \"balance_ETH_ETH\": 1}",
+            // Negative balance
+            "{\"username\": \"alice\", \"balance_ETH_ETH\": -1, \"balance_USDT_ETH\": 1}",
+            // Floating point balance
+            "{\"username\": \"alice\", \"balance_ETH_ETH\": 1.5, \"balance_USDT_ETH\": 1}",
+            // Missing column
+            "{\"username\": \"alice\", \"balance_ETH_ETH\": 1, \"balance_USDT_ETH\": 1}\n{\"username\": \"bob\", \"balance_ETH_ETH\": 1}",
+            // Unknown column
+            "{\"username\": \"alice\", \"balance_ETH_ETH\": 1, \"balance_USDT_ETH\": 1}\n{\"username\": \"bob\", \"balance_ETH_ETH\": 1, \"balance_BTC_BTC\": 1}",
+            // Accumulated balance out of range
+            "{\"username\": \"alice\", \"balance_ETH_ETH\": \"18446744073709551615\", \"balance_USDT_ETH\": 1}\n{\"username\": \"bob\", \"balance_ETH_ETH\": 1, \"balance_USDT_ETH\": 1}",
+            // Not a JSON object
+            "[\"alice\", 1, 1]",
+        ];
+        for invalid_source in invalid_sources {
+            std::fs::write(&path, invalid_source).unwrap();
+            let result = JsonLinesSource::open(&path).and_then(|source| {
+                parse_entries_from_source::<_, N_CURRENCIES, N_BYTES>(
+                    source,
+                    &CurrencyManifest::default(),
+                )
+            });
+            assert!(result.is_err(), "{} should be rejected", invalid_source);
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_source() {
+        use crate::merkle_sum_tree::utils::ParquetSource;
+        use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::parser::parse_message_type;
+        use std::sync::Arc;
+
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        let path = std::env::temp_dir().join("summa_test_parquet_source.parquet");
+        let schema = parse_message_type(
+            "message liabilities {
+                REQUIRED BYTE_ARRAY username (UTF8);
+                REQUIRED INT64 balance_ETH_ETH;
+                REQUIRED INT64 balance_USDT_ETH;
+            }",
+        )
+        .unwrap();
+        let mut writer = SerializedFileWriter::new(
+            std::fs::File::create(&path).unwrap(),
+            Arc::new(schema),
+            Default::default(),
+        )
+        .unwrap();
+        let mut row_group = writer.next_row_group().unwrap();
+
+        let usernames: Vec<ByteArray> = merkle_tree
+            .entries()
+            .iter()
+            .map(|entry| ByteArray::from(entry.username()))
+            .collect();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&usernames, None, None)
+            .unwrap();
+        column.close().unwrap();
+
+        for i in 0..N_CURRENCIES {
+            let balances: Vec<i64> = merkle_tree
+                .entries()
+                .iter()
+                .map(|entry| entry.balances()[i].to_string().parse().unwrap())
+                .collect();
+            let mut column = row_group.next_column().unwrap().unwrap();
+            column
+                .typed::<Int64Type>()
+                .write_batch(&balances, None, None)
+                .unwrap();
+            column.close().unwrap();
+        }
+        row_group.close().unwrap();
+        writer.close().unwrap();
+
+        let parquet_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_source(
+            ParquetSource::open(&path).unwrap(),
+            &CurrencyManifest::default(),
+        )
+        .unwrap();
+        assert_eq!(parquet_tree.root().hash, merkle_tree.root().hash);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_source() {
+        use crate::merkle_sum_tree::utils::SqliteSource;
+
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        let path = std::env::temp_dir().join("summa_test_sqlite_source.db");
+        let _ = std::fs::remove_file(&path);
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        connection
+            .execute(
+                "CREATE TABLE liabilities (username TEXT, balance_ETH_ETH INTEGER, balance_USDT_ETH TEXT)",
+                (),
+            )
+            .unwrap();
+        for entry in merkle_tree.entries() {
+            connection
+                .execute(
+                    "INSERT INTO liabilities VALUES (?1, ?2, ?3)",
+                    (
+                        entry.username(),
+                        entry.balances()[0].to_string().parse::<i64>().unwrap(),
+                        entry.balances()[1].to_string(),
+                    ),
+                )
+                .unwrap();
+        }
+        drop(connection);
+
+        let sqlite_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_source(
+            SqliteSource::open(&path, "liabilities").unwrap(),
+            &CurrencyManifest::default(),
+        )
+        .unwrap();
+        assert_eq!(sqlite_tree.root().hash, merkle_tree.root().hash);
+
+        // The table must exist
+        assert!(SqliteSource::open(&path, "missing").is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_merkle_multiproof() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        // The indices don't need to be sorted nor unique
+        let indices = [9, 0, 5, 1, 15, 5];
+        let multiproof = merkle_tree.generate_multiproof(&indices).unwrap();
+        assert_eq!(multiproof.indices, vec![0, 1, 5, 9, 15]);
+        assert_eq!(
+            multiproof.entries[2].username(),
+            merkle_tree.get_entry(5).unwrap().username()
+        );
+        assert!(merkle_tree.verify_multiproof(&multiproof));
+
+        // The multiproof should include fewer preimages than the single proofs
+        let single_proofs_preimages: usize = multiproof
+            .indices
+            .iter()
+            .map(|index| {
+                let proof = merkle_tree.generate_proof(*index).unwrap();
+                1 + proof.sibling_middle_node_hash_preimages.len()
+            })
+            .sum();
+        let multiproof_preimages = multiproof.sibling_leaf_node_hash_preimages.len()
+            + multiproof.sibling_middle_node_hash_preimages.len();
+        assert!(multiproof_preimages < single_proofs_preimages);
+
+        // A multiproof for a single entry should include the same preimages as the single proof
+        let multiproof_single = merkle_tree.generate_multiproof(&[3]).unwrap();
+        let proof = merkle_tree.generate_proof(3).unwrap();
+        assert_eq!(
+            multiproof_single.sibling_leaf_node_hash_preimages,
+            vec![proof.sibling_leaf_node_hash_preimage]
+        );
+        assert_eq!(
+            multiproof_single.sibling_middle_node_hash_preimages,
+            proof.sibling_middle_node_hash_preimages
+        );
+        assert!(merkle_tree.verify_multiproof(&multiproof_single));
+
+        // A multiproof for all the entries shouldn't include any preimage
+        let all_indices: Vec<usize> = (0..16).collect();
+        let multiproof_all = merkle_tree.generate_multiproof(&all_indices).unwrap();
+        assert!(multiproof_all.sibling_leaf_node_hash_preimages.is_empty());
+        assert!(multiproof_all.sibling_middle_node_hash_preimages.is_empty());
+        assert!(merkle_tree.verify_multiproof(&multiproof_all));
+
+        // Multiproofs can be generated in a padded tree, but not for padding leaves
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+        let padded_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries[..11].to_vec(),
+            cryptocurrencies,
+            false,
+        )
+        .unwrap();
+        let padded_multiproof = padded_tree.generate_multiproof(&[2, 7, 10]).unwrap();
+        assert!(padded_tree.verify_multiproof(&padded_multiproof));
+        assert!(padded_tree.generate_multiproof(&[2, 11]).is_err());
+        assert!(padded_tree.generate_multiproof(&[]).is_err());
+
+        // shouldn't verify a multiproof with a wrong entry
+        let mut invalid_multiproof_1 = multiproof.clone();
+        invalid_multiproof_1.entries[1] = Entry::new(
+            "AtwIxZHo".to_string(),
+            [35479.to_biguint().unwrap(), 35479.to_biguint().unwrap()],
+        )
+        .unwrap();
+        assert!(!merkle_tree.verify_multiproof(&invalid_multiproof_1));
+
+        // shouldn't verify a multiproof with a wrong root balance
+        let mut invalid_multiproof_2 = multiproof.clone();
+        invalid_multiproof_2.root.balances[0] = 0.into();
+        assert!(!merkle_tree.verify_multiproof(&invalid_multiproof_2));
+
+        // shouldn't verify a multiproof with unused or missing preimages
+        let mut invalid_multiproof_3 = multiproof.clone();
+        invalid_multiproof_3
+            .sibling_middle_node_hash_preimages
+            .push(proof.sibling_middle_node_hash_preimages[0]);
+        assert!(!merkle_tree.verify_multiproof(&invalid_multiproof_3));
+
+        let mut invalid_multiproof_4 = multiproof.clone();
+        invalid_multiproof_4.sibling_leaf_node_hash_preimages.pop();
+        assert!(!merkle_tree.verify_multiproof(&invalid_multiproof_4));
+
+        // shouldn't verify a multiproof with unsorted indices
+        let mut invalid_multiproof_5 = multiproof.clone();
+        invalid_multiproof_5.indices.swap(0, 1);
+        invalid_multiproof_5.entries.swap(0, 1);
+        assert!(!merkle_tree.verify_multiproof(&invalid_multiproof_5));
+
+        // A user holding the multiproof can verify it against the published root
+        let root_hash = merkle_tree.root().hash;
+        let root_balances = merkle_tree.root().balances;
+        assert!(verify_merkle_multiproof(&multiproof, root_hash, &root_balances).is_ok());
+        assert_eq!(
+            verify_merkle_multiproof(&invalid_multiproof_1, root_hash, &root_balances),
+            Err(VerificationError::InvalidProof)
+        );
+        assert_eq!(
+            verify_merkle_multiproof(&invalid_multiproof_2, root_hash, &root_balances),
+            Err(VerificationError::RootMismatch)
+        );
+
+        // A depth that doesn't fit in the leaf indices should be rejected rather than overflow
+        for depth in [usize::BITS as usize, 200] {
+            let mut invalid_multiproof_6 = multiproof.clone();
+            invalid_multiproof_6.depth = depth;
+            assert!(matches!(
+                verify_merkle_multiproof(&invalid_multiproof_6, root_hash, &root_balances),
+                Err(VerificationError::MalformedProof(_))
+            ));
+            assert!(!merkle_tree.verify_multiproof(&invalid_multiproof_6));
+        }
+    }
+
+    #[test]
+    fn test_standalone_merkle_proof_verification() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+        let root_hash = merkle_tree.root().hash;
+        let root_balances = merkle_tree.root().balances;
+        let proof = merkle_tree.generate_proof(3).unwrap();
+
+        // A user holding the serialized proof can verify it against the published root
+        assert!(verify_merkle_proof(&proof, root_hash, &root_balances).is_ok());
+        let verified_proof = verify_merkle_proof_json::<N_CURRENCIES, N_BYTES>(
+            &proof.to_json().unwrap(),
+            root_hash,
+            &root_balances,
+        )
+        .unwrap();
+        assert_eq!(verified_proof.entry.username(), "nuZweYtO");
+        assert!(verify_merkle_proof_bytes::<N_CURRENCIES, N_BYTES>(
+            &proof.to_bytes().unwrap(),
+            root_hash,
+            &root_balances
+        )
+        .is_ok());
 
-        //Create the second tree with the 7th entry different from the the first tree
-        let mut merkle_tree_2 =
-            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16_modified.csv").unwrap();
+        // The published root balances can be provided as integers
+        let published_balances = [
+            556862_u32.to_biguint().unwrap(),
+            556862_u32.to_biguint().unwrap(),
+        ];
+        assert_eq!(
+            root_balances_from_big_uints(&published_balances),
+            Ok(root_balances)
+        );
 
-        let root_hash_2 = merkle_tree_2.root().hash;
-        assert!(root_hash_1 != root_hash_2);
+        // A published root balance that is not a field element should be rejected rather than panic
+        let modulus = fp_to_big_uint(-Fp::one()) + 1_u32;
+        for invalid_balance in [modulus, BigUint::from(2_usize).pow(256)] {
+            assert_eq!(
+                root_balances_from_big_uints(&[invalid_balance, 556862_u32.to_biguint().unwrap()]),
+                Err(VerificationError::InvalidRootBalance)
+            );
+        }
 
-        //Update the 7th leaf of the second tree so all the entries now match the first tree
-        let new_root = merkle_tree_2
-            .update_leaf(
-                "RkLzkDun",
-                &[2087.to_biguint().unwrap(), 79731.to_biguint().unwrap()],
+        // shouldn't verify against a different published root
+        let result = verify_merkle_proof(&proof, 0.into(), &root_balances);
+        assert_eq!(result, Err(VerificationError::RootMismatch));
+
+        // shouldn't verify a proof with a wrong entry
+        let mut invalid_proof = proof.clone();
+        invalid_proof.entry = Entry::new(
+            "nuZweYtO".to_string(),
+            [1.to_biguint().unwrap(), 2.to_biguint().unwrap()],
+        )
+        .unwrap();
+        let result = verify_merkle_proof(&invalid_proof, root_hash, &root_balances);
+        assert_eq!(result, Err(VerificationError::InvalidProof));
+
+        // shouldn't verify a proof with a balance out of range
+        invalid_proof.entry = Entry::new(
+            "nuZweYtO".to_string(),
+            [BigUint::from(2_u32).pow(64), 2.to_biguint().unwrap()],
+        )
+        .unwrap();
+        let result = verify_merkle_proof(&invalid_proof, root_hash, &root_balances);
+        assert_eq!(result, Err(VerificationError::BalanceOutOfRange));
+
+        // should reject malformed proofs without panicking
+        let mut malformed_proof_1 = proof.clone();
+        malformed_proof_1.sibling_middle_node_hash_preimages.pop();
+        let result = verify_merkle_proof(&malformed_proof_1, root_hash, &root_balances);
+        assert!(matches!(result, Err(VerificationError::MalformedProof(_))));
+        assert!(!merkle_tree.verify_proof(&malformed_proof_1));
+
+        let mut malformed_proof_2 = proof.clone();
+        malformed_proof_2.path_indices.clear();
+        let result = verify_merkle_proof(&malformed_proof_2, root_hash, &root_balances);
+        assert!(matches!(result, Err(VerificationError::MalformedProof(_))));
+        assert!(!merkle_tree.verify_proof(&malformed_proof_2));
+
+        let mut malformed_proof_3 = proof;
+        malformed_proof_3.path_indices[1] = 2.into();
+        let result = verify_merkle_proof(&malformed_proof_3, root_hash, &root_balances);
+        assert!(matches!(result, Err(VerificationError::MalformedProof(_))));
+
+        let result = verify_merkle_proof_json::<N_CURRENCIES, N_BYTES>(
+            "{\"version\":1}",
+            root_hash,
+            &root_balances,
+        );
+        assert!(matches!(result, Err(VerificationError::Deserialization(_))));
+    }
+
+    #[test]
+    fn test_salted_mst() {
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+
+        let salted_entries: Vec<Entry<N_CURRENCIES>> = entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                Entry::new_salted(
+                    entry.username().to_string(),
+                    entry.balances().clone(),
+                    Fp::from(1000 + i as u64),
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            salted_entries.clone(),
+            cryptocurrencies.clone(),
+            false,
+        )
+        .unwrap();
+        let unsalted_merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        // Salting changes the root hash but not the root balances
+        assert_ne!(merkle_tree.root().hash, unsalted_merkle_tree.root().hash);
+        assert_eq!(
+            merkle_tree.root().balances,
+            unsalted_merkle_tree.root().balances
+        );
+
+        // The sibling leaf hash preimage reveals the username commitment instead of the username
+        let proof = merkle_tree.generate_proof(0).unwrap();
+        let sibling_entry = &salted_entries[1];
+        assert_eq!(
+            proof.sibling_leaf_node_hash_preimage[0],
+            Node::<N_CURRENCIES>::username_commitment(
+                sibling_entry.username_as_big_uint(),
+                sibling_entry.salt().unwrap()
             )
-            .unwrap();
-        //The roots should match
-        assert!(root_hash_1 == new_root.hash);
+        );
+        assert_ne!(
+            proof.sibling_leaf_node_hash_preimage[0],
+            big_uint_to_fp(sibling_entry.username_as_big_uint())
+        );
+        assert!(merkle_tree.verify_proof(&proof));
+
+        // The salt is delivered to the user alongside the proof
+        let proof_from_json =
+            MerkleProof::<N_CURRENCIES, N_BYTES>::from_json(&proof.to_json().unwrap()).unwrap();
+        assert_eq!(proof_from_json.entry.salt(), Some(Fp::from(1000)));
+        assert!(merkle_tree.verify_proof(&proof_from_json));
+
+        // shouldn't verify a proof without the salt
+        let mut unsalted_proof = proof;
+        unsalted_proof.entry = entries[0].clone();
+        assert!(!merkle_tree.verify_proof(&unsalted_proof));
+
+        // The salts can be provided in a `salt` column of the CSV file
+        let path = std::env::temp_dir().join("summa_test_salted_mst.csv");
+        let mut csv = String::from("username,salt,balance_ETH_ETH,balance_USDT_ETH\n");
+        for entry in salted_entries.iter() {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                entry.username(),
+                fp_to_hex(entry.salt().unwrap()),
+                entry.balances()[0],
+                entry.balances()[1]
+            ));
+        }
+        std::fs::write(&path, csv).unwrap();
+
+        let merkle_tree_from_csv =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(merkle_tree_from_csv.root().hash, merkle_tree.root().hash);
+        assert_eq!(merkle_tree_from_csv.cryptocurrencies().len(), N_CURRENCIES);
+
+        let (_, root, _) =
+            build_root_from_csv_in_chunks::<_, N_CURRENCIES, N_BYTES>(&path, 4).unwrap();
+        assert_eq!(root.hash, merkle_tree.root().hash);
+
+        std::fs::remove_file(&path).unwrap();
+
+        // The salts are persisted by the store
+        let path = std::env::temp_dir().join("summa_test_salted_mst_store.bin");
+        MerkleSumTreeStore::<N_CURRENCIES, N_BYTES>::write(&merkle_tree, &path).unwrap();
+        let store =
+            MerkleSumTreeStore::<N_CURRENCIES, N_BYTES>::open(&path, Some(merkle_tree.root()))
+                .unwrap();
+        assert_eq!(store.get_entry(5).unwrap().salt(), Some(Fp::from(1005)));
+        assert!(store.verify_integrity().is_ok());
+
+        std::fs::remove_file(&path).unwrap();
     }
 
     #[test]
-    fn test_update_invalid_mst_leaf() {
-        let mut merkle_tree =
-            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new_sorted("../csv/entry_16.csv").unwrap();
+    fn test_long_usernames() {
+        // Usernames of up to 31 bytes are mapped as is
+        let username = "a".repeat(31);
+        assert_eq!(
+            big_intify_username(&username),
+            BigUint::from_bytes_be(username.as_bytes())
+        );
 
-        let new_root = merkle_tree.update_leaf(
-            "non_existing_user", //This username is not present in the tree
-            &[11888.to_biguint().unwrap(), 41163.to_biguint().unwrap()],
+        // Longer usernames are hashed into 31 bytes, even if their bytes exceed the modulus
+        let modulus = fp_to_big_uint(-Fp::one()) + 1_u32;
+        let long_usernames = [
+            "z".repeat(32),
+            "alice.the.long.named.customer@example.com".to_string(),
+            "123e4567-e89b-12d3-a456-426614174000".to_string(),
+        ];
+        for username in long_usernames.iter() {
+            let username_as_big_uint = big_intify_username(username);
+            assert!(username_as_big_uint < modulus);
+            assert!(username_as_big_uint.bits() <= 248);
+            assert_ne!(
+                username_as_big_uint,
+                BigUint::from_bytes_be(username.as_bytes())
+            );
+            assert_eq!(username_as_big_uint, big_intify_username(username));
+        }
+
+        // A tree can be built out of long usernames
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+        let long_entries: Vec<Entry<N_CURRENCIES>> = entries
+            .iter()
+            .map(|entry| {
+                Entry::new(
+                    format!("{}@a-very-long-exchange-domain.com", entry.username()),
+                    entry.balances().clone(),
+                )
+                .unwrap()
+            })
+            .collect();
+        let merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            long_entries,
+            cryptocurrencies,
+            false,
+        )
+        .unwrap();
+        for i in 0..16 {
+            let proof = merkle_tree.generate_proof(i).unwrap();
+            assert!(merkle_tree.verify_proof(&proof));
+        }
+
+        // Empty usernames can't be distinguished from the padding leaves
+        assert!(check_username("").is_err());
+        let zero_balances = [0.to_biguint().unwrap(), 0.to_biguint().unwrap()];
+        assert!(Entry::<N_CURRENCIES>::new(String::new(), zero_balances.clone()).is_err());
+        assert!(Entry::<N_CURRENCIES>::new_salted(
+            String::new(),
+            zero_balances.clone(),
+            Fp::from(1)
+        )
+        .is_err());
+
+        // A username made of NUL bytes would be represented by 0, as the padding leaves
+        assert!(check_username("\0").is_err());
+        assert!(check_username("\0\0").is_err());
+        assert!(Entry::<N_CURRENCIES>::new("\0".to_string(), zero_balances.clone()).is_err());
+
+        // A username starting with a NUL byte would be represented as the username without it
+        assert_eq!(big_intify_username("\0alice"), big_intify_username("alice"));
+        assert!(check_username("\0alice").is_err());
+        assert!(check_username("alice").is_ok());
+        assert!(check_username("al\0ice").is_ok());
+        assert!(Entry::<N_CURRENCIES>::new("\0alice".to_string(), zero_balances).is_err());
+        let path = std::env::temp_dir().join("summa_test_long_usernames.csv");
+        std::fs::write(
+            &path,
+            "username,balance_ETH_ETH,balance_USDT_ETH\ndxGaEAii,1,2\n,3,4\n",
+        )
+        .unwrap();
+        assert!(parse_csv_to_entries::<_, N_CURRENCIES, N_BYTES>(&path).is_err());
+        assert!(build_root_from_csv_in_chunks::<_, N_CURRENCIES, N_BYTES>(&path, 2).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_validate_csv() {
+        // A well-formed file has no issue
+        let report =
+            validate_csv::<_, N_BYTES>("../csv/entry_16.csv", &CurrencyManifest::default())
+                .unwrap();
+        assert_eq!(report.rows, 16);
+        assert!(report.issues.is_empty());
+        assert!(report.is_valid());
+        assert_eq!(report.totals.len(), N_CURRENCIES);
+        assert!(!report.totals[0].overflows);
+
+        // Every issue of the file is reported, rather than only the first one
+        let path = std::env::temp_dir().join("summa_test_validate_csv.csv");
+        std::fs::write(
+            &path,
+            "username,balance_ETH_ETH,balance_USDT_ETH\n\
+             dxGaEAii,11888,41163\n\
+             ,100,200\n\
+             dxGaEAii,1,2\n\
+             MBlfbBGI,abc,2\n\
+             NjCSRAfD,-5,2\n\
+             RkLzkDun,0,0\n\
+             AtwIxZHo,18446744073709551616,0\n\
+             HfMDmNLp,1,2,3\n",
+        )
+        .unwrap();
+        let report = validate_csv::<_, N_BYTES>(&path, &CurrencyManifest::default()).unwrap();
+        assert_eq!(report.rows, 8);
+        assert!(!report.is_valid());
+
+        let rows: Vec<u64> = report
+            .issues
+            .iter()
+            .map(|issue| match issue {
+                CsvIssue::MalformedRow { row, .. }
+                | CsvIssue::EmptyUsername { row }
+                | CsvIssue::InvalidUsername { row, .. }
+                | CsvIssue::DuplicateUsername { row, .. }
+                | CsvIssue::InvalidSalt { row, .. }
+                | CsvIssue::UnparsableBalance { row, .. }
+                | CsvIssue::BalanceOutOfRange { row, .. }
+                | CsvIssue::ZeroBalances { row, .. } => *row,
+            })
+            .collect();
+        assert_eq!(rows, vec![3, 4, 5, 6, 7, 8, 9]);
+
+        assert_eq!(report.issues[0], CsvIssue::EmptyUsername { row: 3 });
+        assert_eq!(
+            report.issues[1],
+            CsvIssue::DuplicateUsername {
+                row: 4,
+                username: "dxGaEAii".to_string(),
+                first_row: 2
+            }
         );
+        assert!(
+            matches!(report.issues[2], CsvIssue::UnparsableBalance { ref value, .. } if value == "abc")
+        );
+        assert!(
+            matches!(report.issues[3], CsvIssue::UnparsableBalance { ref value, .. } if value == "-5")
+        );
+        assert!(!report.issues[4].is_error());
+        assert!(
+            matches!(report.issues[5], CsvIssue::BalanceOutOfRange { ref column, .. } if column == "balance_ETH_ETH")
+        );
+        assert!(matches!(report.issues[6], CsvIssue::MalformedRow { .. }));
 
-        if let Err(e) = new_root {
-            assert_eq!(e.to_string(), "Username not found");
+        // The out of range balance makes the ETH total overflow the root balance
+        assert!(report.totals[0].overflows);
+        assert!(!report.totals[1].overflows);
+        assert_eq!(
+            report.totals[1].total,
+            (41163 + 200 + 2 + 2 + 2).to_string()
+        );
+
+        // The report is machine-readable
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["issues"][0]["kind"], "empty_username");
+        assert_eq!(json["issues"][1]["first_row"], 2);
+    }
+
+    #[test]
+    fn test_debt_columns() {
+        // alice holds 100 ETH, bob owes 30 ETH and carol holds 50 ETH while owing 20 ETH
+        let path = std::env::temp_dir().join("summa_test_debt_columns.csv");
+        std::fs::write(
+            &path,
+            "username,balance_ETH_ETH,debt_ETH_ETH\nalice,100,0\nbob,0,30\ncarol,50,20\n",
+        )
+        .unwrap();
+
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new(path.to_str().unwrap()).unwrap();
+
+        let cryptocurrencies = merkle_tree.cryptocurrencies();
+        assert_eq!(cryptocurrencies[0].kind, BalanceKind::Liability);
+        assert_eq!(cryptocurrencies[1].kind, BalanceKind::Debt);
+        assert_eq!(cryptocurrencies[1].name, "ETH");
+        assert_eq!(cryptocurrencies[1].column_name(), "debt_ETH_ETH");
+
+        // The root sums give the total liabilities and the total debt separately
+        assert_eq!(merkle_tree.root().balances[0], Fp::from(150));
+        assert_eq!(merkle_tree.root().balances[1], Fp::from(50));
+
+        for i in 0..3 {
+            let proof = merkle_tree.generate_proof(i).unwrap();
+            assert!(merkle_tree.verify_proof(&proof));
         }
+        assert_eq!(
+            merkle_tree.get_entry(1).unwrap().balances()[1],
+            BigUint::from(30_u32)
+        );
+
+        let (_, root, _) =
+            build_root_from_csv_in_chunks::<_, N_CURRENCIES, N_BYTES>(&path, 2).unwrap();
+        assert_eq!(root.hash, merkle_tree.root().hash);
+
+        // The balance kinds are persisted by the store
+        let store_path = std::env::temp_dir().join("summa_test_debt_columns_store.bin");
+        MerkleSumTreeStore::<N_CURRENCIES, N_BYTES>::write(&merkle_tree, &store_path).unwrap();
+        let store = MerkleSumTreeStore::<N_CURRENCIES, N_BYTES>::open(
+            &store_path,
+            Some(merkle_tree.root()),
+        )
+        .unwrap();
+        assert_eq!(store.cryptocurrencies()[1].kind, BalanceKind::Debt);
+        std::fs::remove_file(&store_path).unwrap();
+
+        // Negative balances are rejected instead of wrapping around the field
+        std::fs::write(
+            &path,
+            "username,balance_ETH_ETH,balance_USDT_ETH\nalice,100,1\nbob,-30,2\n",
+        )
+        .unwrap();
+        let error = parse_csv_to_entries::<_, N_CURRENCIES, N_BYTES>(&path)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("debt_ETH_ETH"));
+        assert!(build_root_from_csv_in_chunks::<_, N_CURRENCIES, N_BYTES>(&path, 2).is_err());
+
+        // A column can't appear twice
+        std::fs::write(&path, "username,debt_ETH_ETH,debt_ETH_ETH\nalice,100,1\n").unwrap();
+        assert!(parse_csv_to_entries::<_, N_CURRENCIES, N_BYTES>(&path).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_currency_manifest() {
+        assert_eq!(
+            parse_decimal_balance("1.23456789", 8),
+            Some(BigUint::from(123456789_u32))
+        );
+        assert_eq!(
+            parse_decimal_balance("1.5", 6),
+            Some(BigUint::from(1500000_u32))
+        );
+        assert_eq!(
+            parse_decimal_balance("42", 2),
+            Some(BigUint::from(4200_u32))
+        );
+        assert_eq!(parse_decimal_balance("0.10", 1), Some(BigUint::from(1_u32)));
+        // Over-precise and malformed amounts are rejected
+        assert_eq!(parse_decimal_balance("1.123456789", 8), None);
+        assert_eq!(parse_decimal_balance("1.5", 0), None);
+        assert_eq!(parse_decimal_balance(".5", 1), None);
+        assert_eq!(parse_decimal_balance("1.", 1), None);
+        assert_eq!(parse_decimal_balance("1e18", 18), None);
+        assert_eq!(parse_decimal_balance("1.2.3", 8), None);
+
+        assert_eq!(
+            format_decimal_balance(&BigUint::from(123456789_u32), 8),
+            "1.23456789"
+        );
+        assert_eq!(format_decimal_balance(&BigUint::from(5_u32), 6), "0.000005");
+        assert_eq!(format_decimal_balance(&BigUint::from(5_u32), 0), "5");
+
+        let manifest_path = std::env::temp_dir().join("summa_test_currency_manifest.json");
+        std::fs::write(
+            &manifest_path,
+            r#"{"balance_ETH_ETH": 18, "balance_USDT_ETH": 6}"#,
+        )
+        .unwrap();
+        let manifest = CurrencyManifest::from_json_file(&manifest_path).unwrap();
+        assert_eq!(
+            manifest,
+            CurrencyManifest::new([("balance_ETH_ETH", 18), ("balance_USDT_ETH", 6)]).unwrap()
+        );
+
+        // Decimals whose scale factor would not be a field element are rejected, rather than allocated
+        assert!(CurrencyManifest::new([("balance_ETH_ETH", MAX_DECIMALS)]).is_ok());
+        assert!(CurrencyManifest::new([("balance_ETH_ETH", MAX_DECIMALS + 1)]).is_err());
+        std::fs::write(&manifest_path, r#"{"balance_ETH_ETH": 1800000000}"#).unwrap();
+        assert!(CurrencyManifest::from_json_file(&manifest_path).is_err());
+        std::fs::remove_file(&manifest_path).unwrap();
+
+        let path = std::env::temp_dir().join("summa_test_currency_manifest.csv");
+        std::fs::write(
+            &path,
+            "username,balance_ETH_ETH,balance_USDT_ETH\ndxGaEAii,0.000000000000011888,0.041163\nMBlfbBGI,0.00000000000006799,0.0004\n",
+        )
+        .unwrap();
+
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries_with_manifest::<_, N_CURRENCIES, N_BYTES>(&path, &manifest)
+                .unwrap();
+        assert_eq!(cryptocurrencies[0].decimals, 18);
+        assert_eq!(cryptocurrencies[1].decimals, 6);
+        assert_eq!(entries[0].balances()[0], BigUint::from(11888_u32));
+        assert_eq!(entries[0].balances()[1], BigUint::from(41163_u32));
+        assert_eq!(entries[1].balances()[0], BigUint::from(67990_u32));
+        assert_eq!(
+            cryptocurrencies[1].format_balance(&entries[1].balances()[1]),
+            "0.000400"
+        );
+
+        let merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::new_with_manifest(
+            path.to_str().unwrap(),
+            &manifest,
+        )
+        .unwrap();
+        assert_eq!(merkle_tree.cryptocurrencies()[0].decimals, 18);
+        assert_eq!(merkle_tree.root().balances[1], Fp::from(41563));
+
+        let (_, root, _) = build_root_from_csv_in_chunks_with_manifest::<_, N_CURRENCIES, N_BYTES>(
+            &path, 2, &manifest,
+        )
+        .unwrap();
+        assert_eq!(root.hash, merkle_tree.root().hash);
+
+        // The decimals are persisted by the store
+        let store_path = std::env::temp_dir().join("summa_test_currency_manifest_store.bin");
+        MerkleSumTreeStore::<N_CURRENCIES, N_BYTES>::write(&merkle_tree, &store_path).unwrap();
+        let store = MerkleSumTreeStore::<N_CURRENCIES, N_BYTES>::open(
+            &store_path,
+            Some(merkle_tree.root()),
+        )
+        .unwrap();
+        assert_eq!(store.cryptocurrencies()[1].decimals, 6);
+        std::fs::remove_file(&store_path).unwrap();
+
+        // Without a manifest, decimal amounts are rejected
+        assert!(parse_csv_to_entries::<_, N_CURRENCIES, N_BYTES>(&path).is_err());
+
+        // Every column must be declared by a non-empty manifest
+        let partial_manifest = CurrencyManifest::new([("balance_ETH_ETH", 18)]).unwrap();
+        assert!(
+            parse_csv_to_entries_with_manifest::<_, N_CURRENCIES, N_BYTES>(
+                &path,
+                &partial_manifest
+            )
+            .is_err()
+        );
+
+        // Amounts more precise than the declared decimals are rejected
+        let coarse_manifest =
+            CurrencyManifest::new([("balance_ETH_ETH", 18), ("balance_USDT_ETH", 2)]).unwrap();
+        assert!(
+            parse_csv_to_entries_with_manifest::<_, N_CURRENCIES, N_BYTES>(&path, &coarse_manifest)
+                .is_err()
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_merkle_proof_serialization() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+        let proof = merkle_tree.generate_proof(3).unwrap();
+
+        // JSON round trip
+        let json = proof.to_json().unwrap();
+        let proof_from_json = MerkleProof::<N_CURRENCIES, N_BYTES>::from_json(&json).unwrap();
+        assert_eq!(proof_from_json.entry.username(), proof.entry.username());
+        assert_eq!(proof_from_json.entry.balances(), proof.entry.balances());
+        assert_eq!(proof_from_json.root.hash, proof.root.hash);
+        assert_eq!(
+            proof_from_json.sibling_middle_node_hash_preimages,
+            proof.sibling_middle_node_hash_preimages
+        );
+        assert!(merkle_tree.verify_proof(&proof_from_json));
+
+        // The JSON is tagged with the format version and field elements are canonical hex strings
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["version"], 2);
+        assert_eq!(value["proof"]["root"]["hash"], fp_to_hex(proof.root.hash));
+        assert_eq!(value["proof"]["entry"]["balances"][0], "22073");
+
+        // Binary round trip
+        let bytes = proof.to_bytes().unwrap();
+        assert!(bytes.len() < json.len());
+        let proof_from_bytes = MerkleProof::<N_CURRENCIES, N_BYTES>::from_bytes(&bytes).unwrap();
+        assert_eq!(proof_from_bytes.root.balances, proof.root.balances);
+        assert_eq!(proof_from_bytes.path_indices, proof.path_indices);
+        assert!(merkle_tree.verify_proof(&proof_from_bytes));
+
+        // An unknown format version should be rejected
+        let unknown_version = json.replacen("\"version\":2", "\"version\":1", 1);
+        assert!(MerkleProof::<N_CURRENCIES, N_BYTES>::from_json(&unknown_version).is_err());
+
+        // A proof with a different number of currencies should be rejected
+        assert!(MerkleProof::<1, N_BYTES>::from_json(&json).is_err());
+    }
+
+    #[test]
+    fn test_fp_hex_encoding() {
+        let fp = big_uint_to_fp(&0x1f2f3f.to_biguint().unwrap());
+        let hex = fp_to_hex(fp);
+        assert_eq!(
+            hex,
+            "0x00000000000000000000000000000000000000000000000000000000001f2f3f"
+        );
+        assert_eq!(hex_to_fp(&hex).unwrap(), fp);
+
+        // Missing prefix, wrong length and values above the modulus should be rejected
+        assert!(hex_to_fp("1f2f3f").is_err());
+        assert!(hex_to_fp("0x1f2f3f").is_err());
+        assert!(hex_to_fp(&format!("0x{}", "f".repeat(64))).is_err());
     }
 
     #[test]
@@ -230,4 +2036,246 @@ mod test {
         // The hash of the leaf should match the hash computed from the hash preimage
         assert_eq!(leaf.hash, computed_leaf.hash);
     }
+
+    #[test]
+    fn test_node_hashers() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        // The default hasher is the halo2 Poseidon, whose nodes match the ones built by `Node`
+        let poseidon_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES, PoseidonHasher>::new("../csv/entry_16.csv")
+                .unwrap();
+        assert_eq!(poseidon_tree.root().hash, merkle_tree.root().hash);
+        let entry = &merkle_tree.entries()[0];
+        assert_eq!(
+            PoseidonHasher::compute_leaf(entry).hash,
+            entry.compute_leaf().hash
+        );
+
+        // circomlib test vector: poseidon([1, 2])
+        assert_eq!(
+            CircomPoseidonHasher::hash_username(Fp::from(1), Fp::from(2)),
+            hex_to_fp("0x115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a")
+                .unwrap()
+        );
+
+        // The field elements are converted back and forth without loss
+        let element = -Fp::from(12345);
+        assert_eq!(fr_to_fp(fp_to_fr(element)), element);
+
+        let mut circom_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES, CircomPoseidonHasher>::new(
+            "../csv/entry_16.csv",
+        )
+        .unwrap();
+
+        // Only the hashes depend on the hasher
+        assert_ne!(circom_tree.root().hash, merkle_tree.root().hash);
+        assert_eq!(circom_tree.root().balances, merkle_tree.root().balances);
+        assert_eq!(
+            circom_tree.leaves()[0].hash,
+            CircomPoseidonHasher::compute_leaf(entry).hash
+        );
+
+        // A proof is verified with the hasher of the tree only
+        let proof = circom_tree.generate_proof(0).unwrap();
+        assert!(circom_tree.verify_proof(&proof));
+        assert!(
+            verify_merkle_proof_with_hasher::<N_CURRENCIES, N_BYTES, CircomPoseidonHasher>(
+                &proof,
+                circom_tree.root().hash,
+                &circom_tree.root().balances
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            verify_merkle_proof(
+                &proof,
+                circom_tree.root().hash,
+                &circom_tree.root().balances
+            ),
+            Err(VerificationError::InvalidProof)
+        );
+        assert!(!merkle_tree.verify_proof(&proof));
+
+        let multiproof = circom_tree.generate_multiproof(&[0, 5, 6]).unwrap();
+        assert!(circom_tree.verify_multiproof(&multiproof));
+
+        // The updates are hashed with the hasher of the tree as well
+        let username = entry.username().to_string();
+        let new_balances = [BigUint::from(1u32), BigUint::from(2u32)];
+        circom_tree.update_leaf(&username, &new_balances).unwrap();
+        let updated_entry = Entry::new(username, new_balances).unwrap();
+        assert_eq!(
+            circom_tree.leaves()[0].hash,
+            CircomPoseidonHasher::compute_leaf(&updated_entry).hash
+        );
+        let proof = circom_tree.generate_proof(0).unwrap();
+        assert!(circom_tree.verify_proof(&proof));
+
+        // The store and the aggregation tree hash their nodes with the hasher of the tree too
+        let path = std::env::temp_dir().join("summa_test_circom_mst_store.bin");
+        MerkleSumTreeStore::<N_CURRENCIES, N_BYTES, CircomPoseidonHasher>::write(
+            &circom_tree,
+            &path,
+        )
+        .unwrap();
+        let store = MerkleSumTreeStore::<N_CURRENCIES, N_BYTES, CircomPoseidonHasher>::open(
+            &path,
+            Some(circom_tree.root()),
+        )
+        .unwrap();
+        assert!(store.verify_integrity().is_ok());
+        assert!(store.verify_proof(&store.generate_proof(0).unwrap()));
+        // A file written with one hasher can't be opened with another
+        assert!(MerkleSumTreeStore::<N_CURRENCIES, N_BYTES>::open(&path, None).is_err());
+        std::fs::remove_file(&path).unwrap();
+
+        let (cryptocurrencies, entries) =
+            parse_csv_to_entries::<&str, N_CURRENCIES, N_BYTES>("../csv/entry_16.csv").unwrap();
+        let circom_mini_trees = entries
+            .chunks(8)
+            .map(|chunk| {
+                MerkleSumTree::<N_CURRENCIES, N_BYTES, CircomPoseidonHasher>::from_entries(
+                    chunk.to_vec(),
+                    cryptocurrencies.clone(),
+                    false,
+                )
+                .unwrap()
+            })
+            .collect();
+        let circom_aggregation_tree =
+            AggregationMerkleSumTree::<N_CURRENCIES, N_BYTES, CircomPoseidonHasher>::new(
+                circom_mini_trees,
+            )
+            .unwrap();
+        let circom_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES, CircomPoseidonHasher>::from_entries(
+                entries,
+                cryptocurrencies,
+                false,
+            )
+            .unwrap();
+        assert_eq!(circom_aggregation_tree.root().hash, circom_tree.root().hash);
+        assert!(circom_aggregation_tree
+            .verify_proof(&circom_aggregation_tree.generate_proof(9).unwrap()));
+    }
+
+    #[test]
+    fn test_nova_poseidon_hasher() {
+        let nova_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES, NovaPoseidonHasher>::new("../csv/entry_16.csv")
+                .unwrap();
+        let circom_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES, CircomPoseidonHasher>::new(
+            "../csv/entry_16.csv",
+        )
+        .unwrap();
+
+        // The leaves are hashed as by circom's Poseidon, the middle nodes as H(left.hash, left.balances, right.hash, right.balances)
+        assert_eq!(nova_tree.leaves()[0].hash, circom_tree.leaves()[0].hash);
+        assert_eq!(nova_tree.root().balances, circom_tree.root().balances);
+        assert_ne!(nova_tree.root().hash, circom_tree.root().hash);
+
+        let left = nova_tree.get_node(0, 0).unwrap();
+        let right = nova_tree.get_node(0, 1).unwrap();
+        let preimage = std::iter::once(left.hash)
+            .chain(left.balances)
+            .chain(std::iter::once(right.hash))
+            .chain(right.balances)
+            .map(fp_to_fr)
+            .collect();
+        assert_eq!(
+            nova_tree.get_node(1, 0).unwrap().hash,
+            fr_to_fp(poseidon_rs::Poseidon::new().hash(preimage).unwrap())
+        );
+
+        // Every level is hashed with the Nova layout, up to the root
+        let mut node = nova_tree.get_node(0, 0).unwrap();
+        for level in 0..*nova_tree.depth() {
+            let sibling = nova_tree.get_node(level, 1).unwrap();
+            node = NovaPoseidonHasher::middle(&node, &sibling);
+        }
+        assert_eq!(node.hash, nova_tree.root().hash);
+
+        // The proofs carry the default middle node preimages, which don't hash back into the Nova nodes
+        let proof = nova_tree.generate_proof(0).unwrap();
+        assert!(!nova_tree.verify_proof(&proof));
+    }
+
+    #[test]
+    fn test_quaternary_mst() {
+        let binary_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+        let merkle_tree =
+            QuaternaryMerkleSumTree::<N_CURRENCIES, N_BYTES>::new("../csv/entry_16.csv").unwrap();
+
+        // 16 entries fill a 4-ary tree of depth 2
+        assert_eq!(*merkle_tree.depth(), 2);
+        assert_eq!(merkle_tree.leaves().len(), 16);
+        assert_eq!(merkle_tree.root().balances, binary_tree.root().balances);
+        assert_eq!(merkle_tree.leaves()[3].hash, binary_tree.leaves()[3].hash);
+        assert_ne!(merkle_tree.root().hash, binary_tree.root().hash);
+
+        for index in 0..16 {
+            let proof = merkle_tree.generate_proof(index).unwrap();
+            assert_eq!(proof.path_indices.len(), 4);
+            assert_eq!(proof.sibling_leaf_node_hash_preimages.len(), 3);
+            assert_eq!(proof.sibling_middle_node_hash_preimages.len(), 3);
+            assert!(merkle_tree.verify_proof(&proof));
+        }
+        assert!(merkle_tree.generate_proof(16).is_err());
+
+        // The path indices are the bits of the leaf index, from the least significant one
+        let proof = merkle_tree.generate_proof(6).unwrap();
+        assert_eq!(
+            proof.path_indices,
+            vec![Fp::zero(), Fp::one(), Fp::one(), Fp::zero()]
+        );
+
+        let root_hash = merkle_tree.root().hash;
+        let root_balances = merkle_tree.root().balances;
+        assert!(verify_quaternary_merkle_proof(&proof, root_hash, &root_balances).is_ok());
+
+        // The node can't be moved to another position among its siblings
+        let mut forged_proof = proof.clone();
+        forged_proof.path_indices[0] = Fp::one();
+        assert_eq!(
+            verify_quaternary_merkle_proof(&forged_proof, root_hash, &root_balances),
+            Err(VerificationError::InvalidProof)
+        );
+
+        // The siblings must be complete
+        let mut forged_proof = proof.clone();
+        forged_proof.sibling_middle_node_hash_preimages.pop();
+        assert!(matches!(
+            verify_quaternary_merkle_proof(&forged_proof, root_hash, &root_balances),
+            Err(VerificationError::MalformedProof(_))
+        ));
+
+        let mut forged_proof = proof;
+        forged_proof.path_indices.pop();
+        assert!(matches!(
+            verify_quaternary_merkle_proof(&forged_proof, root_hash, &root_balances),
+            Err(VerificationError::MalformedProof(_))
+        ));
+
+        // The leaves are padded up to the next power of four
+        let entries = binary_tree.entries()[..5].to_vec();
+        let padded_tree = QuaternaryMerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries,
+            binary_tree.cryptocurrencies().to_vec(),
+        )
+        .unwrap();
+        assert_eq!(*padded_tree.depth(), 2);
+        assert_eq!(
+            padded_tree.leaves()[5].hash,
+            Node::<N_CURRENCIES>::padding_leaf().hash
+        );
+        let proof = padded_tree.generate_proof(4).unwrap();
+        assert!(padded_tree.verify_proof(&proof));
+        assert_eq!(
+            proof.sibling_leaf_node_hash_preimages[0],
+            [Fp::zero(); N_CURRENCIES + 1]
+        );
+    }
 }
@@ -1,28 +1,70 @@
 use crate::merkle_sum_tree::utils::big_uint_to_fp;
+use crate::merkle_sum_tree::verifier::{
+    verify_merkle_multiproof_with_hasher, verify_merkle_proof_with_hasher,
+};
 use crate::merkle_sum_tree::Cryptocurrency;
-use crate::merkle_sum_tree::{Entry, MerkleProof, Node};
+use crate::merkle_sum_tree::{
+    Entry, MerkleMultiProof, MerkleProof, Node, NodeHasher, PoseidonHasher,
+};
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
 
 /// A trait representing the basic operations for a Merkle-Sum-like Tree.
-pub trait Tree<const N_CURRENCIES: usize, const N_BYTES: usize> {
+///
+/// The nodes of the tree are hashed with `H`, which is also used to verify the proofs. It defaults to [`PoseidonHasher`], the hash constrained by the circuits.
+pub trait Tree<const N_CURRENCIES: usize, const N_BYTES: usize, H: NodeHasher = PoseidonHasher> {
     /// Returns a reference to the root node.
     fn root(&self) -> &Node<N_CURRENCIES>;
 
     /// Returns the depth of the tree.
     fn depth(&self) -> &usize;
 
-    /// Returns a slice of the leaf nodes.
-    fn leaves(&self) -> &[Node<N_CURRENCIES>];
-
-    /// Returns a slice of the nodes.
-    fn nodes(&self) -> &[Vec<Node<N_CURRENCIES>>];
-
     /// Returns the cryptocurrencies whose balances are in the tree. The order of cryptocurrencies and balances is supposed to agree for all the entries.
     fn cryptocurrencies(&self) -> &[Cryptocurrency];
 
-    fn get_entry(&self, index: usize) -> &Entry<N_CURRENCIES>;
+    /// Returns the number of entries in the tree. The leaves past the last entry are padding leaves.
+    fn entries_count(&self) -> usize;
 
-    fn entries(&self) -> &[Entry<N_CURRENCIES>];
+    /// Returns the node at the given level and index. Level 0 is the leaves level and level `depth` is the root level.
+    fn get_node(
+        &self,
+        level: usize,
+        index: usize,
+    ) -> Result<Node<N_CURRENCIES>, Box<dyn std::error::Error>>;
+
+    /// Returns the entry at the given index.
+    fn get_entry(&self, index: usize) -> Result<Entry<N_CURRENCIES>, Box<dyn std::error::Error>>;
+
+    /// Returns the leaf nodes, including the padding leaves.
+    ///
+    /// All the leaves are read at once, which is costly for a tree that is not held in memory, such as a [`MerkleSumTreeStore`](crate::merkle_sum_tree::MerkleSumTreeStore). Prefer [`Tree::get_node`] to read a single leaf.
+    fn leaves(&self) -> Result<Vec<Node<N_CURRENCIES>>, Box<dyn std::error::Error>> {
+        (0..1 << *self.depth())
+            .map(|index| self.get_node(0, index))
+            .collect()
+    }
+
+    /// Returns the nodes, level by level, from the leaves to the root.
+    ///
+    /// The whole tree is read at once, see [`Tree::leaves`]. Prefer [`Tree::get_node`] to read a single node.
+    fn nodes(&self) -> Result<Vec<Vec<Node<N_CURRENCIES>>>, Box<dyn std::error::Error>> {
+        let depth = *self.depth();
+        (0..=depth)
+            .map(|level| {
+                (0..1 << (depth - level))
+                    .map(|index| self.get_node(level, index))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns the entries of the tree.
+    ///
+    /// All the entries are read at once, see [`Tree::leaves`]. Prefer [`Tree::get_entry`] to read a single entry.
+    fn entries(&self) -> Result<Vec<Entry<N_CURRENCIES>>, Box<dyn std::error::Error>> {
+        (0..self.entries_count())
+            .map(|index| self.get_entry(index))
+            .collect()
+    }
 
     /// Returns the hash preimage of a middle node.
     fn get_middle_node_hash_preimage(
@@ -37,14 +79,13 @@ pub trait Tree<const N_CURRENCIES: usize, const N_BYTES: usize> {
             return Err(Box::from("Invalid depth"));
         }
 
-        self.nodes()
-            .get(level)
-            .and_then(|layer| layer.get(index))
-            .ok_or_else(|| Box::<dyn std::error::Error>::from("Node not found"))?;
+        if index >= 1 << (*self.depth() - level) {
+            return Err(Box::from("Node not found"));
+        }
 
         // Assuming the left and right children are stored in order
-        let left_child = &self.nodes()[level - 1][2 * index];
-        let right_child = &self.nodes()[level - 1][2 * index + 1];
+        let left_child = self.get_node(level - 1, 2 * index)?;
+        let right_child = self.get_node(level - 1, 2 * index + 1)?;
 
         // Constructing preimage
         let mut preimage = [Fp::zero(); N_CURRENCIES + 2];
@@ -61,7 +102,7 @@ pub trait Tree<const N_CURRENCIES: usize, const N_BYTES: usize> {
         Ok(preimage)
     }
 
-    /// Returns the hash preimage of a leaf node.
+    /// Returns the hash preimage of a leaf node. The hash preimage of a padding leaf is `[0, 0, ..., 0]`.
     fn get_leaf_node_hash_preimage(
         &self,
         index: usize,
@@ -69,17 +110,23 @@ pub trait Tree<const N_CURRENCIES: usize, const N_BYTES: usize> {
     where
         [usize; N_CURRENCIES + 1]: Sized,
     {
+        if index >= 1 << *self.depth() {
+            return Err(Box::from("Node not found"));
+        }
+
+        // Leaves past the last entry are padding leaves
+        if index >= self.entries_count() {
+            return Ok([Fp::zero(); N_CURRENCIES + 1]);
+        }
+
         // Fetch entry corresponding to index
-        let entry = self
-            .entries()
-            .get(index)
-            .ok_or_else(|| Box::<dyn std::error::Error>::from("Node not found"))?;
+        let entry = self.get_entry(index)?;
 
         // Constructing preimage
         let mut preimage = [Fp::zero(); N_CURRENCIES + 1];
 
-        // Add username to preimage
-        preimage[0] = big_uint_to_fp(entry.username_as_big_uint());
+        // Add username, or its commitment for a salted entry, to preimage
+        preimage[0] = H::username_commitment(&entry);
 
         // Add balances to preimage
         for (i, balance) in preimage.iter_mut().enumerate().skip(1).take(N_CURRENCIES) {
@@ -89,7 +136,7 @@ pub trait Tree<const N_CURRENCIES: usize, const N_BYTES: usize> {
         Ok(preimage)
     }
 
-    /// Generates a MerkleProof for the user with the given index.
+    /// Generates a MerkleProof for the user with the given index. Padding leaves have no user, so no proof can be generated for them.
     fn generate_proof(
         &self,
         index: usize,
@@ -98,11 +145,26 @@ pub trait Tree<const N_CURRENCIES: usize, const N_BYTES: usize> {
         [usize; N_CURRENCIES + 1]: Sized,
         [usize; N_CURRENCIES + 2]: Sized,
     {
-        let nodes = self.nodes();
+        if index >= self.entries_count() {
+            return Err(Box::from("Index out of bounds"));
+        }
+
+        self.generate_leaf_proof(index)
+    }
+
+    /// Generates a MerkleProof for the leaf with the given index, which can be a padding leaf. The entry of a padding leaf is the empty entry, see [`Entry::init_empty`].
+    fn generate_leaf_proof(
+        &self,
+        index: usize,
+    ) -> Result<MerkleProof<N_CURRENCIES, N_BYTES>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
         let depth = *self.depth();
         let root = self.root();
 
-        if index >= nodes[0].len() {
+        if index >= 1 << depth {
             return Err(Box::from("Index out of bounds"));
         }
 
@@ -119,7 +181,7 @@ pub trait Tree<const N_CURRENCIES: usize, const N_BYTES: usize> {
             let position = current_index % 2;
             let sibling_index = current_index - position + (1 - position);
 
-            if sibling_index < nodes[level].len() && level != 0 {
+            if sibling_index < 1 << (depth - level) && level != 0 {
                 // Fetch hash preimage for sibling middle nodes
                 let sibling_node_preimage =
                     self.get_middle_node_hash_preimage(level, sibling_index)?;
@@ -130,7 +192,11 @@ pub trait Tree<const N_CURRENCIES: usize, const N_BYTES: usize> {
             current_index /= 2;
         }
 
-        let entry = self.get_entry(index).clone();
+        let entry = if index < self.entries_count() {
+            self.get_entry(index)?
+        } else {
+            Entry::init_empty()
+        };
 
         Ok(MerkleProof {
             entry,
@@ -141,53 +207,101 @@ pub trait Tree<const N_CURRENCIES: usize, const N_BYTES: usize> {
         })
     }
 
-    /// Verifies a MerkleProof.
+    /// Verifies a MerkleProof against the root embedded in it. A malformed proof is rejected rather than causing a panic.
+    /// Users that only hold a proof can use [`verify_merkle_proof_with_hasher`] instead, which doesn't require a tree.
     fn verify_proof(&self, proof: &MerkleProof<N_CURRENCIES, N_BYTES>) -> bool
     where
         [usize; N_CURRENCIES + 1]: Sized,
         [usize; N_CURRENCIES + 2]: Sized,
     {
-        let mut node = proof.entry.compute_leaf();
-
-        let sibling_leaf_node =
-            Node::<N_CURRENCIES>::leaf_node_from_preimage(&proof.sibling_leaf_node_hash_preimage);
+        verify_merkle_proof_with_hasher::<N_CURRENCIES, N_BYTES, H>(
+            proof,
+            proof.root.hash,
+            &proof.root.balances,
+        )
+        .is_ok()
+    }
 
-        let mut hash_preimage = [Fp::zero(); N_CURRENCIES + 2];
-        for (i, balance) in hash_preimage.iter_mut().enumerate().take(N_CURRENCIES) {
-            *balance = node.balances[i] + sibling_leaf_node.balances[i];
+    /// Generates a MerkleMultiProof for the users with the given indices. Each sibling hash preimage is included at most once, and the ones that can be computed from the included entries are omitted.
+    fn generate_multiproof(
+        &self,
+        indices: &[usize],
+    ) -> Result<MerkleMultiProof<N_CURRENCIES, N_BYTES>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        if indices.is_empty() {
+            return Err(Box::from("Cannot generate a multiproof without indices"));
         }
 
-        if proof.path_indices[0] == 0.into() {
-            hash_preimage[N_CURRENCIES] = node.hash;
-            hash_preimage[N_CURRENCIES + 1] = sibling_leaf_node.hash;
-            node = Node::middle_node_from_preimage(&hash_preimage);
-        } else {
-            hash_preimage[N_CURRENCIES] = sibling_leaf_node.hash;
-            hash_preimage[N_CURRENCIES + 1] = node.hash;
-            node = Node::middle_node_from_preimage(&hash_preimage);
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+
+        if *indices.last().unwrap() >= self.entries_count() {
+            return Err(Box::from("Index out of bounds"));
         }
 
-        for i in 1..proof.path_indices.len() {
-            let sibling_node = Node::<N_CURRENCIES>::middle_node_from_preimage(
-                &proof.sibling_middle_node_hash_preimages[i - 1],
-            );
+        let depth = *self.depth();
+        let mut sibling_leaf_node_hash_preimages = Vec::new();
+        let mut sibling_middle_node_hash_preimages = Vec::new();
 
-            let mut hash_preimage = [Fp::zero(); N_CURRENCIES + 2];
-            for (i, balance) in hash_preimage.iter_mut().enumerate().take(N_CURRENCIES) {
-                *balance = node.balances[i] + sibling_node.balances[i];
+        // Walk up the tree level by level, keeping track of the indices of the nodes that can be computed by the verifier
+        let mut known_indices = indices.clone();
+        for level in 0..depth {
+            let mut i = 0;
+            while i < known_indices.len() {
+                let index = known_indices[i];
+
+                // The sibling is known if it is the next known node
+                if index % 2 == 0 && known_indices.get(i + 1) == Some(&(index + 1)) {
+                    i += 2;
+                    continue;
+                }
+
+                let sibling_index = index ^ 1;
+                if level == 0 {
+                    sibling_leaf_node_hash_preimages
+                        .push(self.get_leaf_node_hash_preimage(sibling_index)?);
+                } else {
+                    sibling_middle_node_hash_preimages
+                        .push(self.get_middle_node_hash_preimage(level, sibling_index)?);
+                }
+                i += 1;
             }
 
-            if proof.path_indices[i] == 0.into() {
-                hash_preimage[N_CURRENCIES] = node.hash;
-                hash_preimage[N_CURRENCIES + 1] = sibling_node.hash;
-                node = Node::middle_node_from_preimage(&hash_preimage);
-            } else {
-                hash_preimage[N_CURRENCIES] = sibling_node.hash;
-                hash_preimage[N_CURRENCIES + 1] = node.hash;
-                node = Node::middle_node_from_preimage(&hash_preimage);
-            }
+            known_indices = known_indices.iter().map(|index| index / 2).collect();
+            known_indices.dedup();
         }
 
-        proof.root.hash == node.hash && proof.root.balances == node.balances
+        let entries = indices
+            .iter()
+            .map(|index| self.get_entry(*index))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(MerkleMultiProof {
+            entries,
+            indices,
+            root: self.root().clone(),
+            depth,
+            sibling_leaf_node_hash_preimages,
+            sibling_middle_node_hash_preimages,
+        })
+    }
+
+    /// Verifies a MerkleMultiProof against the root embedded in it. A malformed proof is rejected rather than causing a panic.
+    /// Users that only hold a multiproof can use [`verify_merkle_multiproof_with_hasher`] instead, which doesn't require a tree.
+    fn verify_multiproof(&self, proof: &MerkleMultiProof<N_CURRENCIES, N_BYTES>) -> bool
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        verify_merkle_multiproof_with_hasher::<N_CURRENCIES, N_BYTES, H>(
+            proof,
+            proof.root.hash,
+            &proof.root.balances,
+        )
+        .is_ok()
     }
 }
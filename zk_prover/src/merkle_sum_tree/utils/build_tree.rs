@@ -1,4 +1,4 @@
-use crate::merkle_sum_tree::{Entry, Node};
+use crate::merkle_sum_tree::{Entry, Node, NodeHasher, PoseidonHasher};
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
 use rayon::prelude::*;
 
@@ -7,12 +7,31 @@ pub fn build_merkle_tree_from_leaves<const N_CURRENCIES: usize>(
     depth: usize,
     nodes: &mut Vec<Vec<Node<N_CURRENCIES>>>,
 ) -> Result<Node<N_CURRENCIES>, Box<dyn std::error::Error>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    build_merkle_tree_from_leaves_with_hasher::<N_CURRENCIES, PoseidonHasher>(leaves, depth, nodes)
+}
+
+/// Builds the middle levels of a Merkle Sum Tree above `leaves`, hashing the middle nodes with `H`
+pub fn build_merkle_tree_from_leaves_with_hasher<const N_CURRENCIES: usize, H: NodeHasher>(
+    leaves: &[Node<N_CURRENCIES>],
+    depth: usize,
+    nodes: &mut Vec<Vec<Node<N_CURRENCIES>>>,
+) -> Result<Node<N_CURRENCIES>, Box<dyn std::error::Error>>
 where
     [usize; N_CURRENCIES + 1]: Sized,
     [usize; N_CURRENCIES + 2]: Sized,
 {
     let n = leaves.len();
 
+    if n != 1 << depth {
+        return Err(Box::from(
+            "The number of leaves must be equal to 2^depth, pad the leaves first",
+        ));
+    }
+
     let mut tree: Vec<Vec<Node<N_CURRENCIES>>> = Vec::with_capacity(depth + 1);
 
     tree.push(vec![
@@ -41,7 +60,7 @@ where
     }
 
     for level in 1..=depth {
-        build_middle_level(level, &mut tree)
+        build_middle_level::<N_CURRENCIES, H>(level, &mut tree)
     }
 
     let root = tree[depth][0].clone();
@@ -55,33 +74,31 @@ pub fn build_leaves_from_entries<const N_CURRENCIES: usize>(
 where
     [usize; N_CURRENCIES + 1]: Sized,
 {
-    let leaves = entries
-        .par_iter()
-        .map(|entry| entry.compute_leaf())
-        .collect::<Vec<_>>();
+    build_leaves_from_entries_with_hasher::<N_CURRENCIES, PoseidonHasher>(entries)
+}
+
+/// Builds the leaves of the entries, hashing them with `H`
+pub fn build_leaves_from_entries_with_hasher<const N_CURRENCIES: usize, H: NodeHasher>(
+    entries: &[Entry<N_CURRENCIES>],
+) -> Vec<Node<N_CURRENCIES>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+{
+    let leaves = entries.par_iter().map(H::compute_leaf).collect::<Vec<_>>();
 
     leaves
 }
 
-fn build_middle_level<const N_CURRENCIES: usize>(level: usize, tree: &mut [Vec<Node<N_CURRENCIES>>])
-where
+fn build_middle_level<const N_CURRENCIES: usize, H: NodeHasher>(
+    level: usize,
+    tree: &mut [Vec<Node<N_CURRENCIES>>],
+) where
     [usize; N_CURRENCIES + 2]: Sized,
 {
     let results: Vec<Node<N_CURRENCIES>> = (0..tree[level - 1].len())
         .into_par_iter()
         .step_by(2)
-        .map(|index| {
-            let mut hash_preimage = [Fp::zero(); N_CURRENCIES + 2];
-
-            for (i, balance) in hash_preimage.iter_mut().enumerate().take(N_CURRENCIES) {
-                *balance =
-                    tree[level - 1][index].balances[i] + tree[level - 1][index + 1].balances[i];
-            }
-
-            hash_preimage[N_CURRENCIES] = tree[level - 1][index].hash;
-            hash_preimage[N_CURRENCIES + 1] = tree[level - 1][index + 1].hash;
-            Node::middle_node_from_preimage(&hash_preimage)
-        })
+        .map(|index| H::middle(&tree[level - 1][index], &tree[level - 1][index + 1]))
         .collect();
 
     for (index, new_node) in results.into_iter().enumerate() {
@@ -1,69 +1,113 @@
-use crate::merkle_sum_tree::{Cryptocurrency, Entry};
+use crate::merkle_sum_tree::utils::{
+    parse_decimal_balance, parse_entries_from_source, CsvSource, CurrencyManifest,
+};
+use crate::merkle_sum_tree::{BalanceKind, Cryptocurrency, Entry};
+use csv::StringRecord;
 use num_bigint::BigUint;
-use std::collections::HashMap;
 use std::error::Error;
-use std::fs::File;
 use std::path::Path;
 
+/// Parses a CSV file stored at `path` into entries. The CSV file must be formatted as follows:
+///
+/// `username,balance_<cryptocurrency>_<chain>,balance_<cryptocurrency>_<chain>,...`
+///
+/// For salted entries, a `salt` column holding the salt of each user as a `0x`-prefixed big-endian hex string of 64 digits follows the `username` column:
+///
+/// `username,salt,balance_<cryptocurrency>_<chain>,balance_<cryptocurrency>_<chain>,...`
+///
+/// Negative positions can't be expressed as negative balances. The amount owed by a user goes into a `debt_<cryptocurrency>_<chain>` column instead, e.g.
+///
+/// `username,balance_ETH_ETH,debt_ETH_ETH`
 pub fn parse_csv_to_entries<P: AsRef<Path>, const N_CURRENCIES: usize, const N_BYTES: usize>(
     path: P,
 ) -> Result<(Vec<Cryptocurrency>, Vec<Entry<N_CURRENCIES>>), Box<dyn Error>> {
-    let file = File::open(path)?;
-    let mut rdr = csv::ReaderBuilder::new().from_reader(file);
+    parse_csv_to_entries_with_manifest::<P, N_CURRENCIES, N_BYTES>(
+        path,
+        &CurrencyManifest::default(),
+    )
+}
+
+/// Parses a CSV file stored at `path` into entries, as [`parse_csv_to_entries`], with balances given as decimal amounts, e.g. `1.23456789`.
+/// The balances of each column are scaled into integer base units according to the decimals declared by `manifest`, and balances more precise than the declared decimals are rejected.
+pub fn parse_csv_to_entries_with_manifest<
+    P: AsRef<Path>,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+>(
+    path: P,
+    manifest: &CurrencyManifest,
+) -> Result<(Vec<Cryptocurrency>, Vec<Entry<N_CURRENCIES>>), Box<dyn Error>> {
+    parse_entries_from_source::<_, N_CURRENCIES, N_BYTES>(CsvSource::open(path)?, manifest)
+}
 
-    let headers = rdr.headers()?.clone();
-    let mut cryptocurrencies: Vec<Cryptocurrency> = Vec::with_capacity(N_CURRENCIES);
+/// Extracts the cryptocurrencies from the CSV headers `username,balance_<cryptocurrency>_<chain>,balance_<cryptocurrency>_<chain>,...`, optionally with a `salt` column after the `username` column.
+/// Each balance column is either a `balance_<cryptocurrency>_<chain>` or a `debt_<cryptocurrency>_<chain>` column, whose decimals are looked up in `manifest`.
+pub fn parse_cryptocurrencies_from_headers(
+    headers: &StringRecord,
+    manifest: &CurrencyManifest,
+) -> Result<Vec<Cryptocurrency>, Box<dyn Error>> {
+    let mut cryptocurrencies: Vec<Cryptocurrency> = Vec::new();
 
     // Extracting cryptocurrency names from column names
-    for header in headers.iter().skip(1) {
-        // Skipping 'username' column
+    for header in headers.iter().skip(balances_column_offset(headers)) {
+        // Skipping 'username' and 'salt' columns
         let parts: Vec<&str> = header.split('_').collect();
-        if parts.len() == 3 && parts[0] == "balance" {
-            cryptocurrencies.push(Cryptocurrency {
-                name: parts[1].to_owned(),
-                chain: parts[2].to_owned(),
-            });
-        } else {
+        let kind = match parts[0] {
+            "balance" => BalanceKind::Liability,
+            "debt" => BalanceKind::Debt,
             // Throw an error if the header is malformed
+            _ => return Err(format!("Invalid header: {}", header).into()),
+        };
+        if parts.len() != 3 {
             return Err(format!("Invalid header: {}", header).into());
         }
-    }
-
-    let mut entries = Vec::new();
-    let mut balances_acc: Vec<BigUint> = vec![BigUint::from(0_usize); N_CURRENCIES];
 
-    for result in rdr.deserialize() {
-        let record: HashMap<String, String> = result?;
-        let username = record.get("username").ok_or("Username not found")?.clone();
-
-        let mut balances_big_int = Vec::new();
-        for cryptocurrency in &cryptocurrencies {
-            let balance_str = record
-                .get(format!("balance_{}_{}", cryptocurrency.name, cryptocurrency.chain).as_str())
-                .ok_or(format!(
-                    "Balance for {} on {} not found",
-                    cryptocurrency.name, cryptocurrency.chain
-                ))?;
-            let balance = BigUint::parse_bytes(balance_str.as_bytes(), 10).ok_or(format!(
-                "Invalid balance for {} on {}",
-                cryptocurrency.name, cryptocurrency.chain
-            ))?;
-            balances_big_int.push(balance);
+        let cryptocurrency = Cryptocurrency {
+            name: parts[1].to_owned(),
+            chain: parts[2].to_owned(),
+            kind,
+            decimals: manifest.decimals(header)?,
+        };
+        if cryptocurrencies
+            .iter()
+            .any(|c| c.column_name() == cryptocurrency.column_name())
+        {
+            return Err(format!("Duplicate header: {}", header).into());
         }
+        cryptocurrencies.push(cryptocurrency);
+    }
 
-        balances_acc = balances_acc
-            .iter()
-            .zip(balances_big_int.iter())
-            .map(|(x, y)| x + y)
-            .collect();
+    Ok(cryptocurrencies)
+}
 
-        let entry = Entry::new(username, balances_big_int.try_into().unwrap())?;
-        entries.push(entry);
+/// Parses a balance of the given cryptocurrency column as a non-negative decimal amount with at most `cryptocurrency.decimals` decimals, scaled into integer base units
+pub fn parse_balance(
+    balance_str: &str,
+    cryptocurrency: &Cryptocurrency,
+) -> Result<BigUint, Box<dyn Error>> {
+    if balance_str.starts_with('-') {
+        return Err(format!(
+            "Negative balance for {} on {}, negative positions must be reported in a debt_{}_{} column",
+            cryptocurrency.name, cryptocurrency.chain, cryptocurrency.name, cryptocurrency.chain
+        )
+        .into());
     }
 
-    // Iterate through the balance accumulator and throw error if any balance is not in range 0, 2 ^ (8 * N_BYTES):
+    parse_decimal_balance(balance_str, cryptocurrency.decimals).ok_or(
+        format!(
+            "Invalid balance for {} on {}, expected a decimal amount with at most {} decimals",
+            cryptocurrency.name, cryptocurrency.chain, cryptocurrency.decimals
+        )
+        .into(),
+    )
+}
+
+/// Throws an error if any accumulated balance is not in range 0, 2 ^ (8 * N_BYTES)
+pub fn check_accumulated_balances<const N_BYTES: usize>(
+    balances_acc: &[BigUint],
+) -> Result<(), Box<dyn Error>> {
     for balance in balances_acc {
-        if balance >= BigUint::from(2_usize).pow(8 * N_BYTES as u32) {
+        if *balance >= BigUint::from(2_usize).pow(8 * N_BYTES as u32) {
             return Err(
                 "Accumulated balance is not in the expected range, proof generation will fail!"
                     .into(),
@@ -71,5 +115,14 @@ pub fn parse_csv_to_entries<P: AsRef<Path>, const N_CURRENCIES: usize, const N_B
         }
     }
 
-    Ok((cryptocurrencies, entries))
+    Ok(())
+}
+
+/// Returns the index of the first balance column, namely 2 if the `username` column is followed by a `salt` column and 1 otherwise
+pub fn balances_column_offset(headers: &StringRecord) -> usize {
+    if headers.get(1) == Some("salt") {
+        2
+    } else {
+        1
+    }
 }
@@ -0,0 +1,240 @@
+use crate::merkle_sum_tree::utils::{
+    balances_column_offset, big_intify_username, check_username, parse_balance,
+    parse_cryptocurrencies_from_headers, CurrencyManifest,
+};
+use crate::merkle_sum_tree::{hex_to_fp, Cryptocurrency};
+use num_bigint::BigUint;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::path::Path;
+
+/// A problem found in a row of a CSV file. Each issue carries the line number of the row in the file, the header being on line 1.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CsvIssue {
+    /// The row can't be read, for example because it doesn't have as many fields as the header
+    MalformedRow { row: u64, reason: String },
+    /// The username is empty
+    EmptyUsername { row: u64 },
+    /// The username can't be represented in a leaf, for example because it starts with a NUL byte, see [`check_username`]
+    InvalidUsername {
+        row: u64,
+        username: String,
+        reason: String,
+    },
+    /// The username, or its integer representation, already appeared at `first_row`
+    DuplicateUsername {
+        row: u64,
+        username: String,
+        first_row: u64,
+    },
+    /// The salt is not a `0x`-prefixed hex string of 64 digits
+    InvalidSalt {
+        row: u64,
+        username: String,
+        reason: String,
+    },
+    /// The balance is not a non-negative decimal amount with at most the declared decimals
+    UnparsableBalance {
+        row: u64,
+        username: String,
+        column: String,
+        value: String,
+        reason: String,
+    },
+    /// The balance doesn't fit in `N_BYTES` bytes, so that no proof can be generated for the entry
+    BalanceOutOfRange {
+        row: u64,
+        username: String,
+        column: String,
+        value: String,
+    },
+    /// Every balance of the entry is zero. Such an entry is valid, but is usually a leftover of the export
+    ZeroBalances { row: u64, username: String },
+}
+
+impl CsvIssue {
+    /// Returns true if the issue prevents building a tree out of the file, and false if it is only a warning
+    pub fn is_error(&self) -> bool {
+        !matches!(self, CsvIssue::ZeroBalances { .. })
+    }
+}
+
+/// The sum of a balance column over the valid balances, compared to the range of the root balances
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnTotal {
+    pub column: String,
+    /// The sum of the balances of the column, in base units
+    pub total: String,
+    /// The exclusive upper bound of the root balance, namely `2^(8 * N_BYTES)`
+    pub limit: String,
+    /// The share of the limit used by the total, in percent
+    pub utilization_percent: f64,
+    /// True if the total doesn't fit in `N_BYTES` bytes, so that no proof can be generated for the tree
+    pub overflows: bool,
+}
+
+/// Machine-readable report of the validation of a CSV file, see [`validate_csv`]
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvValidationReport {
+    /// The number of rows of the file, excluding the header
+    pub rows: usize,
+    /// The cryptocurrencies of the balance columns
+    pub cryptocurrencies: Vec<Cryptocurrency>,
+    /// The issues found, in order of row
+    pub issues: Vec<CsvIssue>,
+    /// The total of each balance column
+    pub totals: Vec<ColumnTotal>,
+}
+
+impl CsvValidationReport {
+    /// Returns true if a tree can be built out of the file, namely if no issue is an error and no total overflows
+    pub fn is_valid(&self) -> bool {
+        !self.issues.iter().any(CsvIssue::is_error)
+            && !self.totals.iter().any(|total| total.overflows)
+    }
+
+    /// Serializes the report to JSON
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Scans the whole CSV file stored at `path`, formatted as for [`parse_csv_to_entries`](crate::merkle_sum_tree::utils::parse_csv_to_entries), and reports every issue instead of stopping at the first one.
+///
+/// The balances are parsed according to `manifest`, and are expected to fit in `N_BYTES` bytes, as are the totals of each column, which become the root balances of the tree.
+/// Only a malformed header, which prevents reading the balance columns, is returned as an error.
+pub fn validate_csv<P: AsRef<Path>, const N_BYTES: usize>(
+    path: P,
+    manifest: &CurrencyManifest,
+) -> Result<CsvValidationReport, Box<dyn Error>> {
+    let file = File::open(path)?;
+    let mut rdr = csv::ReaderBuilder::new().from_reader(file);
+
+    let headers = rdr.headers()?.clone();
+    let cryptocurrencies = parse_cryptocurrencies_from_headers(&headers, manifest)?;
+    let offset = balances_column_offset(&headers);
+    let has_salt = offset == 2;
+
+    let limit = BigUint::from(2_usize).pow(8 * N_BYTES as u32);
+    let mut totals = vec![BigUint::from(0_usize); cryptocurrencies.len()];
+    let mut first_rows: HashMap<BigUint, u64> = HashMap::new();
+    let mut issues = Vec::new();
+    let mut rows = 0;
+
+    for result in rdr.records() {
+        rows += 1;
+        // The header is on line 1, so the rows start on line 2
+        let fallback_row = rows as u64 + 1;
+        let record = match result {
+            Ok(record) => record,
+            Err(e) => {
+                if let csv::ErrorKind::Io(_) = e.kind() {
+                    return Err(e.into());
+                }
+                let row = e.position().map_or(fallback_row, |p| p.line());
+                issues.push(CsvIssue::MalformedRow {
+                    row,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let row = record.position().map_or(fallback_row, |p| p.line());
+
+        let username = record[0].to_string();
+        if username.is_empty() {
+            issues.push(CsvIssue::EmptyUsername { row });
+        } else if let Err(reason) = check_username(&username) {
+            issues.push(CsvIssue::InvalidUsername {
+                row,
+                username: username.clone(),
+                reason: reason.to_string(),
+            });
+        } else {
+            // Long usernames are compared through their integer representation, as two usernames with the same representation can't be told apart in the tree
+            let first_row = *first_rows
+                .entry(big_intify_username(&username))
+                .or_insert(row);
+            if first_row != row {
+                issues.push(CsvIssue::DuplicateUsername {
+                    row,
+                    username: username.clone(),
+                    first_row,
+                });
+            }
+        }
+
+        if has_salt {
+            if let Err(e) = hex_to_fp(&record[1]) {
+                issues.push(CsvIssue::InvalidSalt {
+                    row,
+                    username: username.clone(),
+                    reason: e.to_string(),
+                });
+            }
+        }
+
+        let mut all_zero = true;
+        for (i, cryptocurrency) in cryptocurrencies.iter().enumerate() {
+            let value = &record[offset + i];
+            let balance = match parse_balance(value, cryptocurrency) {
+                Ok(balance) => balance,
+                Err(e) => {
+                    issues.push(CsvIssue::UnparsableBalance {
+                        row,
+                        username: username.clone(),
+                        column: cryptocurrency.column_name(),
+                        value: value.to_string(),
+                        reason: e.to_string(),
+                    });
+                    all_zero = false;
+                    continue;
+                }
+            };
+
+            if balance >= limit {
+                issues.push(CsvIssue::BalanceOutOfRange {
+                    row,
+                    username: username.clone(),
+                    column: cryptocurrency.column_name(),
+                    value: value.to_string(),
+                });
+            }
+            all_zero &= balance == BigUint::from(0_usize);
+            totals[i] += balance;
+        }
+
+        if all_zero {
+            issues.push(CsvIssue::ZeroBalances { row, username });
+        }
+    }
+
+    let totals = cryptocurrencies
+        .iter()
+        .zip(totals)
+        .map(|(cryptocurrency, total)| ColumnTotal {
+            column: cryptocurrency.column_name(),
+            total: total.to_string(),
+            limit: limit.to_string(),
+            utilization_percent: utilization_percent(&total, &limit),
+            overflows: total >= limit,
+        })
+        .collect();
+
+    Ok(CsvValidationReport {
+        rows,
+        cryptocurrencies,
+        issues,
+        totals,
+    })
+}
+
+/// Returns `100 * total / limit`, with a precision of 1/10000 of a percent
+fn utilization_percent(total: &BigUint, limit: &BigUint) -> f64 {
+    let scaled = total * BigUint::from(1_000_000_u32) / limit;
+    // The ratio of a total larger than the u64 range isn't meaningful anyway
+    u64::try_from(scaled).map_or(f64::INFINITY, |scaled| scaled as f64 / 10_000.0)
+}
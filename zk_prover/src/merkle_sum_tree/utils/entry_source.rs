@@ -0,0 +1,144 @@
+use crate::merkle_sum_tree::utils::{
+    balances_column_offset, check_accumulated_balances, check_username, parse_balance,
+    parse_cryptocurrencies_from_headers, CurrencyManifest,
+};
+use crate::merkle_sum_tree::{hex_to_fp, Cryptocurrency, Entry};
+use csv::StringRecord;
+use num_bigint::BigUint;
+use std::error::Error;
+use summa_sources::EntrySource;
+
+/// A lazy iterator over the entries of an [`EntrySource`], returned by [`read_entries`].
+///
+/// Each record is validated as it is read, including the range of its balances. Once the source is exhausted, the iterator yields an error if any accumulated balance is out of range, so the iterator must be consumed to the end before the entries are trusted.
+/// The iteration stops at the first error.
+pub struct SourceEntries<S: EntrySource, const N_CURRENCIES: usize, const N_BYTES: usize> {
+    source: S,
+    cryptocurrencies: Vec<Cryptocurrency>,
+    balances_offset: usize,
+    balances_acc: Vec<BigUint>,
+    is_exhausted: bool,
+}
+
+impl<S: EntrySource, const N_CURRENCIES: usize, const N_BYTES: usize> Iterator
+    for SourceEntries<S, N_CURRENCIES, N_BYTES>
+{
+    type Item = Result<Entry<N_CURRENCIES>, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_exhausted {
+            return None;
+        }
+
+        let record = match self.source.next_record() {
+            Some(record) => record,
+            None => {
+                self.is_exhausted = true;
+                // The accumulated balances are only known once every record is read
+                return check_accumulated_balances::<N_BYTES>(&self.balances_acc)
+                    .err()
+                    .map(Err);
+            }
+        };
+
+        let entry = record.and_then(|record| {
+            parse_record::<N_CURRENCIES, N_BYTES>(
+                &record,
+                &self.cryptocurrencies,
+                self.balances_offset,
+            )
+        });
+        match &entry {
+            Ok(entry) => {
+                for (acc, balance) in self.balances_acc.iter_mut().zip(entry.balances().iter()) {
+                    *acc += balance;
+                }
+            }
+            Err(_) => self.is_exhausted = true,
+        }
+
+        Some(entry)
+    }
+}
+
+/// Reads the header of `source` into cryptocurrencies, whose decimals are declared by `manifest`, and returns them along with a lazy iterator over the entries of the source.
+/// The header is validated as the one of a CSV file: the first column must be `username`, and the balance columns must be `balance_<cryptocurrency>_<chain>` or `debt_<cryptocurrency>_<chain>` columns, one per cryptocurrency.
+pub fn read_entries<S: EntrySource, const N_CURRENCIES: usize, const N_BYTES: usize>(
+    source: S,
+    manifest: &CurrencyManifest,
+) -> Result<(Vec<Cryptocurrency>, SourceEntries<S, N_CURRENCIES, N_BYTES>), Box<dyn Error>> {
+    let headers = source.headers();
+    if headers.get(0) != Some("username") {
+        return Err(Box::from("Username not found"));
+    }
+    let cryptocurrencies = parse_cryptocurrencies_from_headers(headers, manifest)?;
+    if cryptocurrencies.len() != N_CURRENCIES {
+        return Err(Box::from("Number of currencies does not match"));
+    }
+    let balances_offset = balances_column_offset(headers);
+
+    Ok((
+        cryptocurrencies.clone(),
+        SourceEntries {
+            source,
+            cryptocurrencies,
+            balances_offset,
+            balances_acc: vec![BigUint::from(0_usize); N_CURRENCIES],
+            is_exhausted: false,
+        },
+    ))
+}
+
+/// Reads all the entries of `source`, as [`read_entries`], throwing an error if any record is invalid or any accumulated balance is out of range
+pub fn parse_entries_from_source<
+    S: EntrySource,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+>(
+    source: S,
+    manifest: &CurrencyManifest,
+) -> Result<(Vec<Cryptocurrency>, Vec<Entry<N_CURRENCIES>>), Box<dyn Error>> {
+    let (cryptocurrencies, entries) = read_entries::<S, N_CURRENCIES, N_BYTES>(source, manifest)?;
+    let entries = entries.collect::<Result<Vec<_>, _>>()?;
+
+    Ok((cryptocurrencies, entries))
+}
+
+/// Parses a record `username,balance_<cryptocurrency>_<chain>,...` or `username,salt,balance_<cryptocurrency>_<chain>,...` into an entry.
+/// Every balance must fit in `N_BYTES` bytes, so that the leaf can be hashed without waiting for the accumulated balances to be checked.
+fn parse_record<const N_CURRENCIES: usize, const N_BYTES: usize>(
+    record: &StringRecord,
+    cryptocurrencies: &[Cryptocurrency],
+    balances_offset: usize,
+) -> Result<Entry<N_CURRENCIES>, Box<dyn Error>> {
+    let username = record.get(0).ok_or("Username not found")?.to_owned();
+    check_username(&username)?;
+
+    let mut balances_big_int = Vec::with_capacity(N_CURRENCIES);
+    for (i, cryptocurrency) in cryptocurrencies.iter().enumerate() {
+        let balance_str = record.get(i + balances_offset).ok_or(format!(
+            "Balance for {} on {} not found",
+            cryptocurrency.name, cryptocurrency.chain
+        ))?;
+        let balance = parse_balance(balance_str, cryptocurrency)?;
+        // A single balance out of range already puts the accumulated balance out of range, so the error is the same as the one of the accumulated balances
+        if balance.bits() > 8 * N_BYTES as u64 {
+            return Err(
+                "Accumulated balance is not in the expected range, proof generation will fail!"
+                    .into(),
+            );
+        }
+        balances_big_int.push(balance);
+    }
+
+    let balances = balances_big_int.try_into().unwrap();
+
+    // The salt column, if any, sits between the username and the balances
+    if balances_offset == 2 {
+        let salt = record.get(1).ok_or("Salt not found")?;
+        let salt = hex_to_fp(salt).map_err(|e| format!("Invalid salt for {}: {}", username, e))?;
+        return Ok(Entry::new_salted(username, balances, salt)?);
+    }
+
+    Ok(Entry::new(username, balances)?)
+}
@@ -1,7 +1,31 @@
 mod build_tree;
 mod csv_parser;
+mod csv_validator;
+mod entry_source;
 mod operation_helpers;
+mod stream_builder;
 
-pub use build_tree::{build_leaves_from_entries, build_merkle_tree_from_leaves};
-pub use csv_parser::parse_csv_to_entries;
+pub use build_tree::{
+    build_leaves_from_entries, build_leaves_from_entries_with_hasher,
+    build_merkle_tree_from_leaves, build_merkle_tree_from_leaves_with_hasher,
+};
+pub use csv_parser::{
+    balances_column_offset, check_accumulated_balances, parse_balance,
+    parse_cryptocurrencies_from_headers, parse_csv_to_entries, parse_csv_to_entries_with_manifest,
+};
+pub use csv_validator::{validate_csv, ColumnTotal, CsvIssue, CsvValidationReport};
+pub use entry_source::{parse_entries_from_source, read_entries, SourceEntries};
 pub use operation_helpers::*;
+pub use stream_builder::{
+    build_root_from_csv_in_chunks, build_root_from_csv_in_chunks_with_manifest,
+    build_root_from_source_in_chunks,
+};
+// The sources of liabilities and the currency manifest are shared with the `kzg_prover` crate through the `summa-sources` crate
+#[cfg(feature = "parquet")]
+pub use summa_sources::ParquetSource;
+pub use summa_sources::{
+    format_decimal_balance, parse_decimal_balance, CsvSource, CurrencyManifest, EntrySource,
+    JsonLinesSource, MAX_DECIMALS,
+};
+#[cfg(feature = "sqlite")]
+pub use summa_sources::{SqliteSource, SQLITE_BATCH_SIZE};
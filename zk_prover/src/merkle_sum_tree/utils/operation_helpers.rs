@@ -1,12 +1,45 @@
+use ethers::utils::keccak256;
 use halo2_proofs::halo2curves::{bn256::Fr as Fp, group::ff::PrimeField};
 use num_bigint::BigUint;
 
-/// Return a BigUint representation of the username
+/// Maximum number of UTF-8 bytes of a username that is mapped to the field as is.
+/// Any integer of up to 31 bytes is lower than the BN254 scalar field modulus.
+pub const MAX_RAW_USERNAME_BYTES: usize = 31;
+
+/// Return a BigUint representation of the username, which always lies in the field and is lower than `2^248`.
+///
+/// A username of up to [`MAX_RAW_USERNAME_BYTES`] bytes is represented by the big-endian integer of its UTF-8 bytes.
+/// A longer username, such as an email or a UUID string, is represented by the last [`MAX_RAW_USERNAME_BYTES`] bytes of the Keccak-256 digest of its UTF-8 bytes, read as a big-endian integer.
+/// Keeping every representation within 31 bytes lets the circuits compare usernames, as done for the non-inclusion proofs of sorted trees.
 pub fn big_intify_username(username: &str) -> BigUint {
     let utf8_bytes = username.as_bytes();
-    BigUint::from_bytes_be(utf8_bytes)
+    if utf8_bytes.len() <= MAX_RAW_USERNAME_BYTES {
+        return BigUint::from_bytes_be(utf8_bytes);
+    }
+
+    let digest = keccak256(utf8_bytes);
+    BigUint::from_bytes_be(&digest[digest.len() - MAX_RAW_USERNAME_BYTES..])
+}
+
+/// Checks that a username can be represented in a leaf.
+/// The empty username is represented by `0`, which is reserved for the padding leaves, so it is rejected.
+/// The leading NUL bytes of a username vanish from its big-endian integer, so that `"\0alice"` would be represented as `"alice"` and `"\0"` as `0`.
+/// A username starting with a NUL byte is therefore rejected, as is any username represented by `0`, so that the representation of the accepted usernames is injective.
+pub fn check_username(username: &str) -> Result<(), &'static str> {
+    if username.is_empty() {
+        return Err("Username cannot be empty, as 0 is reserved for the padding leaves");
+    }
+    if username.starts_with('\0') {
+        return Err(
+            "Username cannot start with a NUL byte, as it would be represented as another username",
+        );
+    }
+    if big_intify_username(username) == BigUint::from(0_u32) {
+        return Err("Username cannot be represented by 0, as 0 is reserved for the padding leaves");
+    }
+    Ok(())
 }
-/// Converts a BigUint to a Field Element
+/// Converts a BigUint to a Field Element. Panics if the BigUint is not lower than the field modulus.
 pub fn big_uint_to_fp(big_uint: &BigUint) -> Fp {
     Fp::from_str_vartime(&big_uint.to_str_radix(10)[..]).unwrap()
 }
@@ -0,0 +1,167 @@
+use crate::merkle_sum_tree::utils::{read_entries, CsvSource, CurrencyManifest, EntrySource};
+use crate::merkle_sum_tree::{Cryptocurrency, Entry, Node};
+use rayon::prelude::*;
+use std::error::Error;
+use std::path::Path;
+
+/// Builds the root of a Merkle Sum Tree out of a CSV file stored at `path` without loading all the entries in memory.
+/// The CSV file must be formatted as for [`MerkleSumTree::new`](crate::merkle_sum_tree::MerkleSumTree::new).
+///
+/// The records are read in chunks of `chunk_size` entries, which must be a power of two. The leaves of each chunk are hashed in parallel and reduced to the root of the chunk subtree.
+/// The subtree roots are then merged incrementally, so that at most one subtree root per level is kept in memory. Once the whole file is read, the tree is padded following the same padding rule as [`MerkleSumTree`](crate::merkle_sum_tree::MerkleSumTree).
+/// Peak memory is therefore bounded by `chunk_size` entries, and the resulting root is identical to the one of `MerkleSumTree::new`.
+///
+/// # Returns
+///
+/// The cryptocurrencies, the root of the tree and the number of entries
+pub fn build_root_from_csv_in_chunks<
+    P: AsRef<Path>,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+>(
+    path: P,
+    chunk_size: usize,
+) -> Result<(Vec<Cryptocurrency>, Node<N_CURRENCIES>, usize), Box<dyn Error>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    build_root_from_csv_in_chunks_with_manifest::<P, N_CURRENCIES, N_BYTES>(
+        path,
+        chunk_size,
+        &CurrencyManifest::default(),
+    )
+}
+
+/// Builds the root of a Merkle Sum Tree out of a CSV file stored at `path` in chunks, as [`build_root_from_csv_in_chunks`], with balances given as decimal amounts whose decimals are declared by `manifest`
+pub fn build_root_from_csv_in_chunks_with_manifest<
+    P: AsRef<Path>,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+>(
+    path: P,
+    chunk_size: usize,
+    manifest: &CurrencyManifest,
+) -> Result<(Vec<Cryptocurrency>, Node<N_CURRENCIES>, usize), Box<dyn Error>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    build_root_from_source_in_chunks::<_, N_CURRENCIES, N_BYTES>(
+        CsvSource::open(path)?,
+        chunk_size,
+        manifest,
+    )
+}
+
+/// Builds the root of a Merkle Sum Tree out of the entries of `source` in chunks, as [`build_root_from_csv_in_chunks`], with balances given as decimal amounts whose decimals are declared by `manifest`
+pub fn build_root_from_source_in_chunks<
+    S: EntrySource,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+>(
+    source: S,
+    chunk_size: usize,
+    manifest: &CurrencyManifest,
+) -> Result<(Vec<Cryptocurrency>, Node<N_CURRENCIES>, usize), Box<dyn Error>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    if !chunk_size.is_power_of_two() {
+        return Err(Box::from("Chunk size must be a power of two"));
+    }
+
+    // The accumulated balances are checked by the iterator once the source is exhausted
+    let (cryptocurrencies, mut entries) =
+        read_entries::<S, N_CURRENCIES, N_BYTES>(source, manifest)?;
+
+    // Stack of (height, root) of the perfect subtrees built so far, with strictly decreasing heights
+    let mut subtrees: Vec<(usize, Node<N_CURRENCIES>)> = Vec::new();
+    let mut entries_count = 0;
+
+    let mut chunk: Vec<Entry<N_CURRENCIES>> = Vec::with_capacity(chunk_size);
+
+    loop {
+        chunk.clear();
+        for entry in entries.by_ref().take(chunk_size) {
+            chunk.push(entry?);
+        }
+
+        if chunk.is_empty() {
+            break;
+        }
+        entries_count += chunk.len();
+
+        let leaves: Vec<Node<N_CURRENCIES>> =
+            chunk.par_iter().map(|entry| entry.compute_leaf()).collect();
+
+        // A partial chunk is split into perfect subtrees of decreasing sizes, following the binary representation of its length
+        let mut start = 0;
+        while start < leaves.len() {
+            let mut height = (leaves.len() - start).ilog2() as usize;
+            let size = 1 << height;
+            let mut root = build_subtree_root(&leaves[start..start + size]);
+
+            // Merge the subtrees of equal height, as in a binary counter
+            while let Some((top_height, _)) = subtrees.last() {
+                if *top_height != height {
+                    break;
+                }
+                let (_, left) = subtrees.pop().unwrap();
+                root = Node::middle(&left, &root);
+                height += 1;
+            }
+            subtrees.push((height, root));
+
+            start += size;
+        }
+    }
+
+    if entries_count == 0 {
+        return Err(Box::from("Cannot build a Merkle Sum Tree without entries"));
+    }
+
+    // Pad the tree up to the next power of two, with at least one level
+    let depth = std::cmp::max(
+        entries_count.next_power_of_two().trailing_zeros() as usize,
+        1,
+    );
+
+    let (mut height, mut root) = subtrees.pop().unwrap();
+    let mut padding_node = Node::padding_leaf();
+    for _ in 0..height {
+        padding_node = Node::middle(&padding_node, &padding_node);
+    }
+
+    while height < depth {
+        root = match subtrees.last() {
+            Some((top_height, _)) if *top_height == height => {
+                let (_, left) = subtrees.pop().unwrap();
+                Node::middle(&left, &root)
+            }
+            _ => Node::middle(&root, &padding_node),
+        };
+        padding_node = Node::middle(&padding_node, &padding_node);
+        height += 1;
+    }
+
+    Ok((cryptocurrencies, root, entries_count))
+}
+
+/// Reduces a power of two number of leaves to the root of their subtree, hashing each level in parallel
+fn build_subtree_root<const N_CURRENCIES: usize>(
+    leaves: &[Node<N_CURRENCIES>],
+) -> Node<N_CURRENCIES>
+where
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .par_chunks(2)
+            .map(|pair| Node::middle(&pair[0], &pair[1]))
+            .collect();
+    }
+    level.pop().unwrap()
+}
@@ -0,0 +1,620 @@
+//! Verification of a [`MerkleProof`], of a [`MerkleMultiProof`], of a [`MerkleNonInclusionProof`] or of a [`QuaternaryMerkleProof`], without access to the Merkle Sum Tree.
+//!
+//! A user only needs the proof received from the custodian and the root hash and root balances published by the custodian, for example on-chain.
+//! Malformed proofs are reported as a [`VerificationError`] rather than causing a panic.
+use crate::merkle_sum_tree::utils::big_intify_username;
+use crate::merkle_sum_tree::{
+    MerkleMultiProof, MerkleNonInclusionProof, MerkleProof, Node, NodeHasher, PoseidonHasher,
+    QuaternaryMerkleProof,
+};
+use halo2_proofs::halo2curves::{bn256::Fr as Fp, group::ff::PrimeField};
+use num_bigint::BigUint;
+use std::fmt;
+
+/// Reason why a [`MerkleProof`] is rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationError {
+    /// The proof could not be deserialized
+    Deserialization(String),
+    /// The proof is not well formed, for example the number of sibling hash preimages doesn't match the number of path indices
+    MalformedProof(String),
+    /// A balance of the entry is not in the range `[0, 2^(8 * N_BYTES))`
+    BalanceOutOfRange,
+    /// The root embedded in the proof doesn't match the published root
+    RootMismatch,
+    /// The root computed from the entry and the sibling hash preimages doesn't match the published root
+    InvalidProof,
+    /// The leaves of a non-inclusion proof are valid but don't surround the username
+    NotExcluded(String),
+    /// A published root balance is not lower than the field modulus
+    InvalidRootBalance,
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::Deserialization(reason) => {
+                write!(f, "Failed to deserialize the proof: {}", reason)
+            }
+            VerificationError::MalformedProof(reason) => write!(f, "Malformed proof: {}", reason),
+            VerificationError::BalanceOutOfRange => {
+                write!(f, "Entry balance is not in the expected range")
+            }
+            VerificationError::RootMismatch => {
+                write!(f, "Proof root doesn't match the published root")
+            }
+            VerificationError::InvalidProof => {
+                write!(f, "Computed root doesn't match the published root")
+            }
+            VerificationError::NotExcluded(reason) => {
+                write!(f, "The proof doesn't exclude the username: {}", reason)
+            }
+            VerificationError::InvalidRootBalance => {
+                write!(f, "Published root balance is not a field element")
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+/// Verifies a proof against the published `root_hash` and `root_balances`.
+///
+/// The root is recomputed from the entry, the sibling hash preimages and the path indices. Both its hash and its balances must match the published ones.
+pub fn verify_merkle_proof<const N_CURRENCIES: usize, const N_BYTES: usize>(
+    proof: &MerkleProof<N_CURRENCIES, N_BYTES>,
+    root_hash: Fp,
+    root_balances: &[Fp; N_CURRENCIES],
+) -> Result<(), VerificationError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    verify_merkle_proof_with_hasher::<N_CURRENCIES, N_BYTES, PoseidonHasher>(
+        proof,
+        root_hash,
+        root_balances,
+    )
+}
+
+/// Verifies a proof generated by a tree whose nodes are hashed with `H`, see [`verify_merkle_proof`]
+pub fn verify_merkle_proof_with_hasher<
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+    H: NodeHasher,
+>(
+    proof: &MerkleProof<N_CURRENCIES, N_BYTES>,
+    root_hash: Fp,
+    root_balances: &[Fp; N_CURRENCIES],
+) -> Result<(), VerificationError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    if proof.root.hash != root_hash || proof.root.balances != *root_balances {
+        return Err(VerificationError::RootMismatch);
+    }
+
+    let root = compute_root::<N_CURRENCIES, N_BYTES, H>(proof)?;
+
+    if root.hash != root_hash || root.balances != *root_balances {
+        return Err(VerificationError::InvalidProof);
+    }
+
+    Ok(())
+}
+
+/// Deserializes a proof from JSON, as produced by [`MerkleProof::to_json`], and verifies it against the published `root_hash` and `root_balances`.
+///
+/// Returns the proof, so that the caller can inspect the verified entry
+pub fn verify_merkle_proof_json<const N_CURRENCIES: usize, const N_BYTES: usize>(
+    json: &str,
+    root_hash: Fp,
+    root_balances: &[Fp; N_CURRENCIES],
+) -> Result<MerkleProof<N_CURRENCIES, N_BYTES>, VerificationError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    let proof = MerkleProof::<N_CURRENCIES, N_BYTES>::from_json(json)
+        .map_err(|e| VerificationError::Deserialization(e.to_string()))?;
+    verify_merkle_proof(&proof, root_hash, root_balances)?;
+    Ok(proof)
+}
+
+/// Deserializes a proof from its binary encoding, as produced by [`MerkleProof::to_bytes`], and verifies it against the published `root_hash` and `root_balances`.
+///
+/// Returns the proof, so that the caller can inspect the verified entry
+pub fn verify_merkle_proof_bytes<const N_CURRENCIES: usize, const N_BYTES: usize>(
+    bytes: &[u8],
+    root_hash: Fp,
+    root_balances: &[Fp; N_CURRENCIES],
+) -> Result<MerkleProof<N_CURRENCIES, N_BYTES>, VerificationError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    let proof = MerkleProof::<N_CURRENCIES, N_BYTES>::from_bytes(bytes)
+        .map_err(|e| VerificationError::Deserialization(e.to_string()))?;
+    verify_merkle_proof(&proof, root_hash, root_balances)?;
+    Ok(proof)
+}
+
+/// Verifies a multiproof against the published `root_hash` and `root_balances`.
+///
+/// The root is recomputed from the included entries and the sibling hash preimages. Every sibling hash preimage must be used exactly once, and both the hash and the balances of the root must match the published ones.
+pub fn verify_merkle_multiproof<const N_CURRENCIES: usize, const N_BYTES: usize>(
+    proof: &MerkleMultiProof<N_CURRENCIES, N_BYTES>,
+    root_hash: Fp,
+    root_balances: &[Fp; N_CURRENCIES],
+) -> Result<(), VerificationError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    verify_merkle_multiproof_with_hasher::<N_CURRENCIES, N_BYTES, PoseidonHasher>(
+        proof,
+        root_hash,
+        root_balances,
+    )
+}
+
+/// Verifies a multiproof generated by a tree whose nodes are hashed with `H`, see [`verify_merkle_multiproof`]
+pub fn verify_merkle_multiproof_with_hasher<
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+    H: NodeHasher,
+>(
+    proof: &MerkleMultiProof<N_CURRENCIES, N_BYTES>,
+    root_hash: Fp,
+    root_balances: &[Fp; N_CURRENCIES],
+) -> Result<(), VerificationError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    if proof.root.hash != root_hash || proof.root.balances != *root_balances {
+        return Err(VerificationError::RootMismatch);
+    }
+
+    let root = compute_multiproof_root::<N_CURRENCIES, N_BYTES, H>(proof)?;
+
+    if root.hash != root_hash || root.balances != *root_balances {
+        return Err(VerificationError::InvalidProof);
+    }
+
+    Ok(())
+}
+
+/// Verifies a non-inclusion proof against the published `root_hash` and `root_balances`.
+///
+/// Both leaves must be included in the tree and be adjacent, the lower leaf must precede the username and the upper leaf must follow it or be a padding leaf followed by padding leaves only, so that the lower leaf is the last entry.
+/// A missing lower leaf must be replaced by the upper leaf being the first leaf, and a missing upper leaf by the lower leaf being the last leaf.
+/// The proof is only meaningful for a tree whose leaves are sorted, as built by [`MerkleSumTree::new_sorted`](crate::merkle_sum_tree::MerkleSumTree::new_sorted).
+pub fn verify_non_inclusion_proof<const N_CURRENCIES: usize, const N_BYTES: usize>(
+    proof: &MerkleNonInclusionProof<N_CURRENCIES, N_BYTES>,
+    root_hash: Fp,
+    root_balances: &[Fp; N_CURRENCIES],
+) -> Result<(), VerificationError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    if proof.username.is_empty() {
+        return Err(VerificationError::MalformedProof(
+            "username is empty".to_string(),
+        ));
+    }
+
+    let (lower, upper) = (proof.lower.as_ref(), proof.upper.as_ref());
+    let depth = match lower.or(upper) {
+        Some(neighbour) => neighbour.path_indices.len(),
+        None => {
+            return Err(VerificationError::MalformedProof(
+                "no neighbouring leaf".to_string(),
+            ))
+        }
+    };
+    if depth >= usize::BITS as usize {
+        return Err(VerificationError::MalformedProof(
+            "too many path indices".to_string(),
+        ));
+    }
+
+    for neighbour in lower.iter().chain(upper.iter()) {
+        if neighbour.path_indices.len() != depth {
+            return Err(VerificationError::MalformedProof(
+                "the neighbouring leaves have different depths".to_string(),
+            ));
+        }
+        verify_merkle_proof(*neighbour, root_hash, root_balances)?;
+    }
+
+    let username_as_big_uint = big_intify_username(&proof.username);
+
+    if let Some(lower) = lower {
+        if lower.entry.username().is_empty()
+            || *lower.entry.username_as_big_uint() >= username_as_big_uint
+        {
+            return Err(VerificationError::NotExcluded(
+                "the lower leaf doesn't precede the username".to_string(),
+            ));
+        }
+    }
+
+    if let Some(upper) = upper {
+        let is_padding = upper.entry.username().is_empty()
+            && upper.entry.salt().is_none()
+            && upper
+                .entry
+                .balances()
+                .iter()
+                .all(|balance| *balance == BigUint::from(0u32));
+        if !is_padding && *upper.entry.username_as_big_uint() <= username_as_big_uint {
+            return Err(VerificationError::NotExcluded(
+                "the upper leaf doesn't follow the username".to_string(),
+            ));
+        }
+        // A padding leaf only follows the username if it sits right after the last entry, namely if every leaf after it is a padding leaf too
+        if is_padding && !is_followed_by_padding(upper) {
+            return Err(VerificationError::NotExcluded(
+                "the padding leaf doesn't follow the last entry".to_string(),
+            ));
+        }
+    }
+
+    let are_adjacent = match (lower, upper) {
+        (Some(lower), Some(upper)) => leaf_index(upper) == leaf_index(lower) + 1,
+        (None, Some(upper)) => leaf_index(upper) == 0,
+        (Some(lower), None) => leaf_index(lower) == (1 << depth) - 1,
+        (None, None) => false,
+    };
+    if !are_adjacent {
+        return Err(VerificationError::NotExcluded(
+            "the leaves are not adjacent".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns true if every leaf following the leaf of a proof is a padding leaf, namely if each right sibling along the path is the root of a subtree of padding leaves
+fn is_followed_by_padding<const N_CURRENCIES: usize, const N_BYTES: usize>(
+    proof: &MerkleProof<N_CURRENCIES, N_BYTES>,
+) -> bool
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    // The preimage of the padding leaf is made of zeros
+    if proof.path_indices[0] == Fp::zero()
+        && proof.sibling_leaf_node_hash_preimage != [Fp::zero(); N_CURRENCIES + 1]
+    {
+        return false;
+    }
+
+    let mut padding_node = PoseidonHasher::padding_leaf::<N_CURRENCIES>();
+    for (path_index, preimage) in proof
+        .path_indices
+        .iter()
+        .skip(1)
+        .zip(proof.sibling_middle_node_hash_preimages.iter())
+    {
+        let padding_preimage = Node::middle_node_hash_preimage(&padding_node, &padding_node);
+        if *path_index == Fp::zero() && *preimage != padding_preimage {
+            return false;
+        }
+        padding_node = PoseidonHasher::middle_node_from_preimage(&padding_preimage);
+    }
+
+    true
+}
+
+/// Returns the index of the leaf of a proof, whose bits from the least significant one are the path indices
+fn leaf_index<const N_CURRENCIES: usize, const N_BYTES: usize>(
+    proof: &MerkleProof<N_CURRENCIES, N_BYTES>,
+) -> usize
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    proof
+        .path_indices
+        .iter()
+        .rev()
+        .fold(0, |index, bit| 2 * index + usize::from(*bit == Fp::one()))
+}
+
+/// Computes the root from the entry, the sibling hash preimages and the path indices of a proof, checking that the proof is well formed
+fn compute_root<const N_CURRENCIES: usize, const N_BYTES: usize, H: NodeHasher>(
+    proof: &MerkleProof<N_CURRENCIES, N_BYTES>,
+) -> Result<Node<N_CURRENCIES>, VerificationError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    if proof.path_indices.is_empty() {
+        return Err(VerificationError::MalformedProof(
+            "path indices are empty".to_string(),
+        ));
+    }
+
+    if proof.sibling_middle_node_hash_preimages.len() != proof.path_indices.len() - 1 {
+        return Err(VerificationError::MalformedProof(format!(
+            "expected {} sibling middle node hash preimages, found {}",
+            proof.path_indices.len() - 1,
+            proof.sibling_middle_node_hash_preimages.len()
+        )));
+    }
+
+    if proof
+        .path_indices
+        .iter()
+        .any(|index| *index != Fp::zero() && *index != Fp::one())
+    {
+        return Err(VerificationError::MalformedProof(
+            "path indices must be 0 or 1".to_string(),
+        ));
+    }
+
+    // The balances are range checked by the circuit, so a proof with a balance out of range can't be valid
+    let max_balance = BigUint::from(2_usize).pow(8 * N_BYTES as u32);
+    if proof
+        .entry
+        .balances()
+        .iter()
+        .any(|balance| *balance >= max_balance)
+    {
+        return Err(VerificationError::BalanceOutOfRange);
+    }
+
+    let mut node = H::compute_leaf(&proof.entry);
+
+    let sibling_leaf_node =
+        H::leaf_node_from_preimage::<N_CURRENCIES>(&proof.sibling_leaf_node_hash_preimage);
+    let sibling_middle_nodes = proof
+        .sibling_middle_node_hash_preimages
+        .iter()
+        .map(H::middle_node_from_preimage::<N_CURRENCIES>);
+
+    for (path_index, sibling_node) in proof
+        .path_indices
+        .iter()
+        .zip(std::iter::once(sibling_leaf_node).chain(sibling_middle_nodes))
+    {
+        node = if *path_index == Fp::zero() {
+            H::middle(&node, &sibling_node)
+        } else {
+            H::middle(&sibling_node, &node)
+        };
+    }
+
+    Ok(node)
+}
+
+/// Computes the root from the entries and the sibling hash preimages of a multiproof, checking that the multiproof is well formed
+fn compute_multiproof_root<const N_CURRENCIES: usize, const N_BYTES: usize, H: NodeHasher>(
+    proof: &MerkleMultiProof<N_CURRENCIES, N_BYTES>,
+) -> Result<Node<N_CURRENCIES>, VerificationError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    if proof.entries.is_empty() || proof.entries.len() != proof.indices.len() {
+        return Err(VerificationError::MalformedProof(
+            "the number of entries doesn't match the number of indices".to_string(),
+        ));
+    }
+
+    if proof.depth == 0 || proof.depth >= usize::BITS as usize {
+        return Err(VerificationError::MalformedProof(format!(
+            "invalid depth {}",
+            proof.depth
+        )));
+    }
+
+    // The indices must be strictly increasing and lie within the leaves
+    if proof.indices.windows(2).any(|pair| pair[0] >= pair[1])
+        || *proof.indices.last().unwrap() >= 1 << proof.depth
+    {
+        return Err(VerificationError::MalformedProof(
+            "indices must be strictly increasing and lie within the leaves".to_string(),
+        ));
+    }
+
+    // The balances are range checked by the circuit, so a proof with a balance out of range can't be valid
+    let max_balance = BigUint::from(2_usize).pow(8 * N_BYTES as u32);
+    if proof
+        .entries
+        .iter()
+        .flat_map(|entry| entry.balances().iter())
+        .any(|balance| *balance >= max_balance)
+    {
+        return Err(VerificationError::BalanceOutOfRange);
+    }
+
+    let mut nodes: Vec<(usize, Node<N_CURRENCIES>)> = proof
+        .indices
+        .iter()
+        .zip(proof.entries.iter())
+        .map(|(index, entry)| (*index, H::compute_leaf(entry)))
+        .collect();
+
+    let mut sibling_leaf_nodes = proof
+        .sibling_leaf_node_hash_preimages
+        .iter()
+        .map(H::leaf_node_from_preimage::<N_CURRENCIES>);
+    let mut sibling_middle_nodes = proof
+        .sibling_middle_node_hash_preimages
+        .iter()
+        .map(H::middle_node_from_preimage::<N_CURRENCIES>);
+
+    for level in 0..proof.depth {
+        let mut parents = Vec::with_capacity(nodes.len());
+        let mut i = 0;
+        while i < nodes.len() {
+            let (index, node) = &nodes[i];
+
+            let (left, right) = if index % 2 == 0
+                && nodes.get(i + 1).map(|(next_index, _)| *next_index) == Some(index + 1)
+            {
+                i += 1;
+                (node.clone(), nodes[i].1.clone())
+            } else {
+                let sibling = if level == 0 {
+                    sibling_leaf_nodes.next()
+                } else {
+                    sibling_middle_nodes.next()
+                };
+                let sibling = sibling.ok_or_else(|| {
+                    VerificationError::MalformedProof(format!(
+                        "missing sibling hash preimage at level {}",
+                        level
+                    ))
+                })?;
+
+                if index % 2 == 0 {
+                    (node.clone(), sibling)
+                } else {
+                    (sibling, node.clone())
+                }
+            };
+
+            parents.push((index / 2, H::middle(&left, &right)));
+            i += 1;
+        }
+        nodes = parents;
+    }
+
+    // Every sibling hash preimage must have been used
+    if sibling_leaf_nodes.next().is_some() || sibling_middle_nodes.next().is_some() {
+        return Err(VerificationError::MalformedProof(
+            "unused sibling hash preimages".to_string(),
+        ));
+    }
+
+    let (_, root) = nodes.swap_remove(0);
+    Ok(root)
+}
+
+/// Verifies a proof of a 4-ary tree against the published `root_hash` and `root_balances`, as [`verify_merkle_proof`] does for a binary tree.
+pub fn verify_quaternary_merkle_proof<const N_CURRENCIES: usize, const N_BYTES: usize>(
+    proof: &QuaternaryMerkleProof<N_CURRENCIES, N_BYTES>,
+    root_hash: Fp,
+    root_balances: &[Fp; N_CURRENCIES],
+) -> Result<(), VerificationError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 4]: Sized,
+{
+    if proof.root.hash != root_hash || proof.root.balances != *root_balances {
+        return Err(VerificationError::RootMismatch);
+    }
+
+    let root = compute_quaternary_root(proof)?;
+
+    if root.hash != root_hash || root.balances != *root_balances {
+        return Err(VerificationError::InvalidProof);
+    }
+
+    Ok(())
+}
+
+/// Computes the root from the entry, the sibling hash preimages and the path indices of a proof of a 4-ary tree, checking that the proof is well formed
+fn compute_quaternary_root<const N_CURRENCIES: usize, const N_BYTES: usize>(
+    proof: &QuaternaryMerkleProof<N_CURRENCIES, N_BYTES>,
+) -> Result<Node<N_CURRENCIES>, VerificationError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 4]: Sized,
+{
+    if proof.path_indices.is_empty() || proof.path_indices.len() % 2 != 0 {
+        return Err(VerificationError::MalformedProof(
+            "path indices must be a non-empty sequence of pairs of bits".to_string(),
+        ));
+    }
+    let depth = proof.path_indices.len() / 2;
+
+    if proof.sibling_leaf_node_hash_preimages.len() != 3 {
+        return Err(VerificationError::MalformedProof(format!(
+            "expected 3 sibling leaf node hash preimages, found {}",
+            proof.sibling_leaf_node_hash_preimages.len()
+        )));
+    }
+
+    if proof.sibling_middle_node_hash_preimages.len() != 3 * (depth - 1) {
+        return Err(VerificationError::MalformedProof(format!(
+            "expected {} sibling middle node hash preimages, found {}",
+            3 * (depth - 1),
+            proof.sibling_middle_node_hash_preimages.len()
+        )));
+    }
+
+    if proof
+        .path_indices
+        .iter()
+        .any(|index| *index != Fp::zero() && *index != Fp::one())
+    {
+        return Err(VerificationError::MalformedProof(
+            "path indices must be 0 or 1".to_string(),
+        ));
+    }
+
+    // The balances are range checked by the circuit, so a proof with a balance out of range can't be valid
+    let max_balance = BigUint::from(2_usize).pow(8 * N_BYTES as u32);
+    if proof
+        .entry
+        .balances()
+        .iter()
+        .any(|balance| *balance >= max_balance)
+    {
+        return Err(VerificationError::BalanceOutOfRange);
+    }
+
+    let mut node = proof.entry.compute_leaf();
+
+    let sibling_leaf_nodes: Vec<Node<N_CURRENCIES>> = proof
+        .sibling_leaf_node_hash_preimages
+        .iter()
+        .map(Node::<N_CURRENCIES>::leaf_node_from_preimage)
+        .collect();
+    let sibling_middle_nodes: Vec<Node<N_CURRENCIES>> = proof
+        .sibling_middle_node_hash_preimages
+        .iter()
+        .map(Node::<N_CURRENCIES>::quaternary_middle_node_from_preimage)
+        .collect();
+
+    for (level, bits) in proof.path_indices.chunks(2).enumerate() {
+        let position = usize::from(bits[0] == Fp::one()) + 2 * usize::from(bits[1] == Fp::one());
+        let siblings = if level == 0 {
+            &sibling_leaf_nodes[..]
+        } else {
+            &sibling_middle_nodes[3 * (level - 1)..3 * level]
+        };
+
+        // The node takes its position among the siblings, which are in increasing order of position
+        let mut children = siblings.to_vec();
+        children.insert(position, node);
+        node = Node::quaternary_middle(&children.try_into().unwrap());
+    }
+
+    Ok(node)
+}
+
+/// Converts the root balances published as integers into field elements, as expected by [`verify_merkle_proof`].
+/// Returns [`VerificationError::InvalidRootBalance`] if a balance is not lower than the field modulus.
+pub fn root_balances_from_big_uints<const N_CURRENCIES: usize>(
+    balances: &[BigUint; N_CURRENCIES],
+) -> Result<[Fp; N_CURRENCIES], VerificationError> {
+    let mut root_balances = [Fp::zero(); N_CURRENCIES];
+    for (root_balance, balance) in root_balances.iter_mut().zip(balances.iter()) {
+        let mut bytes = balance.to_bytes_le();
+        if bytes.len() > 32 {
+            return Err(VerificationError::InvalidRootBalance);
+        }
+        bytes.resize(32, 0);
+        *root_balance = Option::from(Fp::from_repr(bytes.try_into().unwrap()))
+            .ok_or(VerificationError::InvalidRootBalance)?;
+    }
+    Ok(root_balances)
+}